@@ -165,29 +165,50 @@ class CLIArguments:
 def create_or_clear_output_dir(output_dir_path: str, remove_old_files: bool) -> None:
     """
     Create the output dir if it does not exist.
-    If it exists, remove all ".csv" and ".gv" files, if remove_old_files is True.
+    If it exists, remove all ".csv" and ".gv" files, and the run manifest, if remove_old_files is True.
+    NOTE: The pipelines of a same call are saved in subfolders of the output dir, they are cleared too.
     """
     # create output dir if it does not exist
     if not os.path.exists(output_dir_path):
         os.makedirs(output_dir_path)
     else:
         if remove_old_files:
-            # remove all ".csv" an ".gv" files in the output dir
-            for filename in os.listdir(output_dir_path):
-                if filename.endswith(".csv") or filename.endswith(".gv"):
-                    os.remove(os.path.join(output_dir_path, filename))
-                    print(f" 󰆴 -> Removed {filename} in {output_dir_path}")
+            # remove all ".csv" an ".gv" files in the output dir and its subfolders, and the run manifests
+            for (dir_path, _, filenames) in os.walk(output_dir_path):
+                for filename in filenames:
+                    if filename.endswith(".csv") or filename.endswith(".gv") or filename.endswith(".tmp") or filename == "run_manifest.jsonl":
+                        os.remove(os.path.join(dir_path, filename))
+                        print(f" 󰆴 -> Removed {filename} in {dir_path}")
+
+def group_pipelines_by_additional_args(
+    pipelines_names_to_additional_args: list[tuple[str, list[str]]]
+) -> list[tuple[list[str], list[str]]]:
+    """
+    Group the pipelines with the same additional arguments, in order.
+    They are run in a single call, so that the graph of each heap dump is built once for all of them.
+    """
+    groups: list[tuple[list[str], list[str]]] = []
+    for (pipeline_name, additional_args) in pipelines_names_to_additional_args:
+        for (pipeline_names, group_additional_args) in groups:
+            if group_additional_args == additional_args:
+                pipeline_names.append(pipeline_name)
+                break
+        else:
+            groups.append(([pipeline_name], additional_args))
+    return groups
 
 def build_arg_compute_instances(cli: CLIArguments) -> list[list[str]]:
     """
     Create a list of CLI commands with arguments to run the executables.
+    NOTE: The pipelines with the same arguments are given together (comma separated) to a single call,
+        each of them being saved in its own subfolder of the output dir (ex: 0_chunk-semantic-embedding).
     """
 
     # create a list of arguments to run the executables
     arg_compute_instances: list[list[str]] = []
 
     # create args with entropy
-    for (pipeline_name, additional_agrs) in PIPELINES_NAMES_TO_ADDITIONAL_ARGS_FILTER:
+    for (pipeline_names, additional_agrs) in group_pipelines_by_additional_args(PIPELINES_NAMES_TO_ADDITIONAL_ARGS_FILTER):
         for entropy_filtering_flag in LIST_ENTROPY_FILTERING_FLAGS:  
             for byte_size_filter in LIST_BYTE_SIZE_FILTERING_FLAGS:    
                 # output dir preparation
@@ -203,7 +224,7 @@ def build_arg_compute_instances(cli: CLIArguments) -> list[list[str]]:
                 output_dir_path = (
                     current_dir + "/data/" + str(compute_instance_index) + 
                     "_" + filtering_tag + 
-                    pipeline_name.replace("-", "_") + 
+                    "_".join(pipeline_name.replace("-", "_") for pipeline_name in pipeline_names) + 
                     "_" + "_".join(additional_agrs) +
                     "_-e_" + entropy_filtering_flag +
                     "_-s_" + byte_size_filter
@@ -216,7 +237,7 @@ def build_arg_compute_instances(cli: CLIArguments) -> list[list[str]]:
                     "cargo", "run", "--",
                     "-d", INPUT_FILE_DIR_PATH,
                     "-o", output_dir_path, 
-                    "-p", pipeline_name,
+                    "-p", ",".join(pipeline_names),
                     "-e", entropy_filtering_flag,
                     "-s", byte_size_filter,
                 ]
@@ -224,18 +245,24 @@ def build_arg_compute_instances(cli: CLIArguments) -> list[list[str]]:
                 # append additional arguments
                 if len(additional_agrs) > 0:
                     args.extend(additional_agrs)
+                if cli.args.keep_old_output:
+                    args.append("--resume")
 
                 arg_compute_instances.append(args)
         
     # create args without entropy
-    for (pipeline_name, additional_agrs) in PIPELINES_NAMES_TO_ADDITIONAL_ARGS_NO_FILTER:
+    for (pipeline_names, additional_agrs) in group_pipelines_by_additional_args(PIPELINES_NAMES_TO_ADDITIONAL_ARGS_NO_FILTER):
         # output dir preparation
         current_dir = os.getcwd()
         additional_param_list_as_str = "_".join(
             additional_agrs
         )
         compute_instance_index = len(arg_compute_instances)
-        output_dir_path = current_dir + "/data/" + str(compute_instance_index) + "_" + pipeline_name.replace("-", "_") + additional_param_list_as_str
+        output_dir_path = (
+            current_dir + "/data/" + str(compute_instance_index) + "_" + 
+            "_".join(pipeline_name.replace("-", "_") for pipeline_name in pipeline_names) + 
+            additional_param_list_as_str
+        )
         
         create_or_clear_output_dir(output_dir_path, not cli.args.keep_old_output)
         
@@ -244,12 +271,14 @@ def build_arg_compute_instances(cli: CLIArguments) -> list[list[str]]:
             "cargo", "run", "--",
             "-d", INPUT_FILE_DIR_PATH,
             "-o", output_dir_path, 
-            "-p", pipeline_name,
+            "-p", ",".join(pipeline_names),
         ]
 
         # append additional arguments
         if len(additional_agrs) > 0:
             args.extend(additional_agrs)
+        if cli.args.keep_old_output:
+            args.append("--resume")
         
         arg_compute_instances.append(args)
 
@@ -0,0 +1,321 @@
+use std::{path::{Path, PathBuf}, fs::File, sync::Mutex};
+use csv::Writer;
+
+use crate::graph_embedding::GraphEmbedding;
+use super::manifest::get_tmp_file_path;
+use crate::graph_embedding::samples::{SampleValue, Columns};
+use super::sink::SamplesSink;
+
+/// columns added to every sample of an aggregated dataset, before the label
+pub const PROVENANCE_COLUMNS: [&str; 4] = ["source_file", "directory_category", "ssh_version", "heap_start"];
+
+/// Origin of the samples of a heap dump file, in an aggregated dataset.
+/// NOTE: The directory category and SSH version are parsed from the dataset layout
+///     (ex: Training/basic/V_7_1_P1/24/17016-1643962152-heap.raw), and are empty outside of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapDumpProvenance {
+    /// end of the path of the heap dump file (ex: Training/basic/V_7_1_P1/24/17016-1643962152-heap.raw)
+    pub source_file: String,
+    /// directories before the SSH version (ex: Training/basic)
+    pub directory_category: String,
+    /// ex: V_7_1_P1
+    pub ssh_version: String,
+    /// address of the first block of the heap dump
+    pub heap_start: u64,
+}
+
+impl HeapDumpProvenance {
+    pub fn new(heap_dump_raw_file_path: &Path, heap_start: u64) -> HeapDumpProvenance {
+        // file name, and its 4 parent directories at most
+        let mut path_end: Vec<String> = heap_dump_raw_file_path.components()
+            .filter_map(|component| match component {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        path_end.drain(..path_end.len().saturating_sub(5));
+        let dir_names = &path_end[..path_end.len().saturating_sub(1)];
+
+        // the SSH version directory is like V_7_1_P1
+        let ssh_version_index = dir_names.iter().position(|dir_name| {
+            dir_name.starts_with("V_") && dir_name[2..].split('_').all(
+                |part| !part.is_empty() && part.trim_start_matches('P').chars().all(|c| c.is_ascii_digit())
+            )
+        });
+        let (directory_category, ssh_version) = match ssh_version_index {
+            Some(index) => (dir_names[..index].join("/"), dir_names[index].clone()),
+            None => (String::new(), String::new()),
+        };
+
+        HeapDumpProvenance {
+            source_file: path_end.join("/"),
+            directory_category,
+            ssh_version,
+            heap_start,
+        }
+    }
+
+    pub fn from_graph_embedding(graph_embedding: &GraphEmbedding) -> HeapDumpProvenance {
+        let heap_dump_data = graph_embedding.graph_annotate.graph_data.heap_dump_data.as_ref().unwrap();
+        HeapDumpProvenance::new(&heap_dump_data.heap_dump_raw_file_path, heap_dump_data.min_addr)
+    }
+
+    /// values of the provenance columns
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.source_file.clone(),
+            self.directory_category.clone(),
+            self.ssh_version.clone(),
+            self.heap_start.to_string(),
+        ]
+    }
+}
+
+struct ShardedDatasetState {
+    /// columns of the samples, with the provenance columns and the label
+    columns: Option<Vec<String>>,
+    csv_writer: Option<Writer<File>>,
+    nb_samples_in_shard: usize,
+    shard_file_paths: Vec<PathBuf>,
+}
+
+/// Single dataset of the samples of all the files, split in CSV shards
+/// (ex: ChunkSemanticEmbedding_Training_dataset_00000.csv).
+/// The samples are written by several threads, in batches (see ProvenanceSink).
+/// NOTE: The shards are temporary files until the dataset is finished.
+pub struct ShardedDataset {
+    output_folder: PathBuf,
+    dataset_name: String,
+    max_nb_samples_per_shard: usize,
+    state: Mutex<ShardedDatasetState>,
+}
+
+impl ShardedDataset {
+    /// NOTE: The shards of a previous dataset with the same name are removed,
+    /// with the temporary shards left by an interrupted run.
+    pub fn create(output_folder: PathBuf, dataset_name: String, max_nb_samples_per_shard: usize) -> ShardedDataset {
+        assert!(max_nb_samples_per_shard > 0, "The number of samples per shard must be positive.");
+        let old_shard_file_paths = ShardedDataset::get_existing_shard_file_paths(&output_folder, &dataset_name, ".csv").into_iter()
+            .chain(ShardedDataset::get_existing_shard_file_paths(&output_folder, &dataset_name, ".csv.tmp"));
+        for shard_file_path in old_shard_file_paths {
+            std::fs::remove_file(&shard_file_path).unwrap_or_else(
+                |_| panic!("Cannot remove old shard: {:?}", shard_file_path)
+            );
+        }
+
+        ShardedDataset {
+            output_folder,
+            dataset_name,
+            max_nb_samples_per_shard,
+            state: Mutex::new(ShardedDatasetState {
+                columns: None,
+                csv_writer: None,
+                nb_samples_in_shard: 0,
+                shard_file_paths: Vec::new(),
+            }),
+        }
+    }
+
+    /// shards of the dataset in the output folder, with the given extension (ex: ".csv"), in order
+    fn get_existing_shard_file_paths(output_folder: &Path, dataset_name: &str, extension: &str) -> Vec<PathBuf> {
+        let prefix = format!("{}_", dataset_name);
+        let mut shard_file_paths: Vec<PathBuf> = match std::fs::read_dir(output_folder) {
+            Ok(entries) => entries
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| {
+                    let file_name = path.file_name().unwrap().to_string_lossy();
+                    file_name.strip_prefix(&prefix)
+                        .and_then(|shard| shard.strip_suffix(extension))
+                        .is_some_and(|shard_index| !shard_index.is_empty() && shard_index.chars().all(|c| c.is_ascii_digit()))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        shard_file_paths.sort();
+        shard_file_paths
+    }
+
+    /// path of a file of the dataset, other than its shards (ex: ".config.toml")
+    pub fn get_dataset_file_path(&self, extension: &str) -> PathBuf {
+        self.output_folder.join(format!("{}{}", self.dataset_name, extension))
+    }
+
+    /// set the columns of the dataset, or check that they are the ones of the dataset
+    fn write_columns(&self, columns: Vec<String>) {
+        let mut state = self.state.lock().unwrap();
+        match &state.columns {
+            Some(dataset_columns) => {
+                if dataset_columns != &columns {
+                    panic!("🚩 Headers mismatch between the files of the dataset {}", self.dataset_name);
+                }
+            },
+            None => state.columns = Some(columns),
+        }
+    }
+
+    /// write a batch of samples (with their provenance and label) in the current shard,
+    /// starting a new shard each time it is full
+    /// NOTE: The dataset is locked once per batch, the samples of a batch are contiguous.
+    fn write_samples(&self, rows: &[Vec<String>]) {
+        let mut state = self.state.lock().unwrap();
+        for values in rows.iter() {
+            if state.csv_writer.is_none() || state.nb_samples_in_shard >= self.max_nb_samples_per_shard {
+                if let Some(csv_writer) = state.csv_writer.as_mut() {
+                    csv_writer.flush().unwrap();
+                }
+
+                let shard_file_path = self.output_folder.join(
+                    format!("{}_{:05}.csv", self.dataset_name, state.shard_file_paths.len())
+                );
+                let tmp_shard_file_path = get_tmp_file_path(&shard_file_path);
+                let mut csv_writer = Writer::from_path(&tmp_shard_file_path).unwrap_or_else(
+                    |_| panic!("Cannot create csv file: {:?}, no such file.", tmp_shard_file_path)
+                );
+                csv_writer.write_record(state.columns.as_ref().unwrap()).unwrap();
+
+                state.csv_writer = Some(csv_writer);
+                state.nb_samples_in_shard = 0;
+                state.shard_file_paths.push(shard_file_path);
+            }
+
+            state.csv_writer.as_mut().unwrap().write_record(values).unwrap();
+            state.nb_samples_in_shard += 1;
+        }
+    }
+
+    /// Flush the last shard, and rename all the shards to their final name.
+    /// :return: The shard file paths.
+    pub fn finish(self) -> Vec<PathBuf> {
+        let mut state = self.state.into_inner().unwrap();
+        if let Some(mut csv_writer) = state.csv_writer.take() {
+            csv_writer.flush().unwrap();
+        }
+        for shard_file_path in state.shard_file_paths.iter() {
+            let tmp_shard_file_path = get_tmp_file_path(shard_file_path);
+            std::fs::rename(&tmp_shard_file_path, shard_file_path).unwrap_or_else(
+                |_| panic!("Cannot rename {:?} to {:?}", tmp_shard_file_path, shard_file_path)
+            );
+        }
+        state.shard_file_paths
+    }
+}
+
+/// Sink of the samples of a heap dump file, into a sharded dataset, with their provenance.
+/// NOTE: The samples are written in batches of PROVENANCE_SINK_BATCH_NB_SAMPLES at most,
+/// so that the threads don't lock the dataset for each sample.
+pub struct ProvenanceSink<'a> {
+    dataset: &'a ShardedDataset,
+    provenance_values: Vec<String>,
+    batch: Vec<Vec<String>>,
+}
+
+/// maximum number of samples of a batch of a ProvenanceSink
+pub const PROVENANCE_SINK_BATCH_NB_SAMPLES: usize = 1024;
+
+impl<'a> ProvenanceSink<'a> {
+    pub fn new(dataset: &'a ShardedDataset, provenance: &HeapDumpProvenance) -> ProvenanceSink<'a> {
+        ProvenanceSink { dataset, provenance_values: provenance.values(), batch: Vec::new() }
+    }
+
+    /// write the batch to the dataset
+    fn write_batch(&mut self) {
+        if !self.batch.is_empty() {
+            self.dataset.write_samples(&self.batch);
+            self.batch.clear();
+        }
+    }
+}
+
+impl SamplesSink for ProvenanceSink<'_> {
+    fn write_columns(&mut self, columns: &Columns) {
+        let mut all_columns = columns.names().to_vec();
+        all_columns.extend(PROVENANCE_COLUMNS.iter().map(|column| column.to_string()));
+        all_columns.push("label".to_string());
+        self.dataset.write_columns(all_columns);
+    }
+
+    fn write_sample(&mut self, values: &[SampleValue], label: usize) {
+        let mut values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        values.extend(self.provenance_values.iter().cloned());
+        values.push(label.to_string());
+        self.batch.push(values);
+        if self.batch.len() >= PROVENANCE_SINK_BATCH_NB_SAMPLES {
+            self.write_batch();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.write_batch();
+    }
+}
+
+impl Drop for ProvenanceSink<'_> {
+    /// NOTE: The remaining samples are written even without flush.
+    fn drop(&mut self) {
+        self.write_batch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_embedding::samples::ColumnType;
+
+    #[test]
+    fn test_heap_dump_provenance() {
+        let provenance = HeapDumpProvenance::new(
+            Path::new("/data/phdtrack_data/Training/basic/V_7_1_P1/24/17016-1643962152-heap.raw"),
+            0x55a6d2b6c000,
+        );
+        assert_eq!(provenance.source_file, "Training/basic/V_7_1_P1/24/17016-1643962152-heap.raw");
+        assert_eq!(provenance.directory_category, "Training/basic");
+        assert_eq!(provenance.ssh_version, "V_7_1_P1");
+        assert_eq!(provenance.heap_start, 0x55a6d2b6c000);
+
+        // outside of the dataset layout
+        let provenance = HeapDumpProvenance::new(Path::new("test/17016-1643962152-heap.raw"), 0);
+        assert_eq!(provenance.source_file, "test/17016-1643962152-heap.raw");
+        assert_eq!(provenance.directory_category, "");
+        assert_eq!(provenance.ssh_version, "");
+    }
+
+    #[test]
+    fn test_sharded_dataset() {
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_sharded_dataset_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+        // shard of a previous dataset, and temporary shard of an interrupted run
+        std::fs::write(output_folder.join("dataset_00007.csv"), "old").unwrap();
+        std::fs::write(output_folder.join("dataset_00003.csv.tmp"), "old").unwrap();
+
+        let dataset = ShardedDataset::create(output_folder.clone(), "dataset".to_string(), 2);
+        assert!(ShardedDataset::get_existing_shard_file_paths(&output_folder, "dataset", ".csv.tmp").is_empty());
+        let columns = Columns::of_type(["a", "b"], ColumnType::Int);
+        for (file_index, nb_samples) in [3, 2].iter().enumerate() {
+            let provenance = HeapDumpProvenance::new(Path::new(&format!("{}-heap.raw", file_index)), 16);
+            let mut sink = ProvenanceSink::new(&dataset, &provenance);
+            sink.write_columns(&columns);
+            for i in 0..*nb_samples {
+                sink.write_sample(&[SampleValue::Int(i), SampleValue::Int(0)], 1);
+            }
+            if file_index == 0 {
+                // the samples are batched until the flush
+                assert!(dataset.state.lock().unwrap().shard_file_paths.is_empty());
+            }
+            sink.flush();
+        }
+        let shard_file_paths = dataset.finish();
+
+        assert_eq!(shard_file_paths, ShardedDataset::get_existing_shard_file_paths(&output_folder, "dataset", ".csv"));
+        assert!(ShardedDataset::get_existing_shard_file_paths(&output_folder, "dataset", ".csv.tmp").is_empty());
+        assert_eq!(shard_file_paths.len(), 3);
+        let first_shard = std::fs::read_to_string(&shard_file_paths[0]).unwrap();
+        assert_eq!(
+            first_shard,
+            "a,b,source_file,directory_category,ssh_version,heap_start,label\n0,0,0-heap.raw,,,16,1\n1,0,0-heap.raw,,,16,1\n"
+        );
+        let last_shard = std::fs::read_to_string(&shard_file_paths[2]).unwrap();
+        assert_eq!(last_shard.lines().count(), 2);
+
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+}
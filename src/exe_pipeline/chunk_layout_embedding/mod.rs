@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use crate::graph_embedding::GraphEmbedding;
+
+use super::stream_embedding;
+use super::sink::{SamplesSink, CsvSink};
+
+/// Chunk layout embedding, for a given file.
+/// Save the embedding to a CSV file.
+pub fn gen_and_save_chunk_layout_embedding(
+    output_file_path: PathBuf, 
+    graph_embedding: &GraphEmbedding,
+) -> usize {
+    // generate the embedding, and save it to CSV sample by sample
+    stream_embedding(
+        graph_embedding.iter_chunk_layout_embedding(),
+        &mut CsvSink::create(output_file_path),
+    )
+}
+
+/// Chunk layout embedding, for a given file.
+/// Write the embedding to the given sink.
+pub fn gen_and_write_chunk_layout_embedding(
+    graph_embedding: &GraphEmbedding,
+    sink: &mut dyn SamplesSink,
+) -> usize {
+    stream_embedding(graph_embedding.iter_chunk_layout_embedding(), sink)
+}
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 use crate::graph_embedding::GraphEmbedding;
-use crate::params::{N_GRAM, BLOCK_BYTE_SIZE};
-use super::save_embedding_with_f64;
+use crate::params::BLOCK_BYTE_SIZE;
+use super::stream_embedding;
+use super::sink::{SamplesSink, CsvSink};
 
 /// Chunk statistic embedding, for a given file.
 /// Save the embedding to a CSV file.
@@ -9,20 +10,24 @@ pub fn gen_and_save_chunk_statistic_embedding(
     output_file_path: PathBuf, 
     graph_embedding: &GraphEmbedding,
 ) -> usize {
-    // generate the value embedding
-    let (samples, labels) 
-        = graph_embedding.generate_chunk_statistic_embedding(
-            &(*N_GRAM), 
+    // generate the embedding, and save it to CSV sample by sample
+    stream_embedding(
+        graph_embedding.iter_chunk_statistic_embedding(
+            graph_embedding.get_n_gram(), 
             BLOCK_BYTE_SIZE
-        );
-    let samples_length = samples.len();
-    
-    // save the value embedding to CSV
-    save_embedding_with_f64(
-        samples, 
-        labels, 
-        output_file_path
-    );
+        ),
+        &mut CsvSink::create(output_file_path),
+    )
+}
 
-    return samples_length;
-}
\ No newline at end of file
+/// Chunk statistic embedding, for a given file.
+/// Write the embedding to the given sink.
+pub fn gen_and_write_chunk_statistic_embedding(
+    graph_embedding: &GraphEmbedding,
+    sink: &mut dyn SamplesSink,
+) -> usize {
+    stream_embedding(graph_embedding.iter_chunk_statistic_embedding(
+        graph_embedding.get_n_gram(), 
+        BLOCK_BYTE_SIZE
+    ), sink)
+}
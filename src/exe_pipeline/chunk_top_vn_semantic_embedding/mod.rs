@@ -2,7 +2,8 @@ use std::path::PathBuf;
 
 use crate::graph_embedding::GraphEmbedding;
 
-use super::save_embedding;
+use super::stream_embedding;
+use super::sink::{SamplesSink, CsvSink};
 
 /// Value node semantic embedding, for value nodes that are first 
 /// blocks of the user data section of a chunk. 
@@ -18,17 +19,19 @@ pub fn gen_and_save_chunk_top_vn_semantic_embedding(
     output_file_path: PathBuf, 
     graph_embedding: &GraphEmbedding
 ) -> usize {
-    // generate the value embedding
-    let (samples, labels) = 
-        graph_embedding.generate_chunk_top_vn_semantic_embedding();
-    let samples_length = samples.len();
-    
-    // save the value embedding to CSV
-    save_embedding(
-        samples, 
-        labels, 
-        output_file_path
-    );
+    // generate the embedding, and save it to CSV sample by sample
+    stream_embedding(
+        graph_embedding.iter_chunk_top_vn_semantic_embedding(),
+        &mut CsvSink::create(output_file_path),
+    )
+}
 
-    return samples_length;
-}
\ No newline at end of file
+/// Value node semantic embedding, for value nodes that are first
+/// blocks of the user data section of a chunk, for a given file.
+/// Write the embedding to the given sink.
+pub fn gen_and_write_chunk_top_vn_semantic_embedding(
+    graph_embedding: &GraphEmbedding,
+    sink: &mut dyn SamplesSink,
+) -> usize {
+    stream_embedding(graph_embedding.iter_chunk_top_vn_semantic_embedding(), sink)
+}
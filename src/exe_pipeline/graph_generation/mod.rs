@@ -1,16 +1,20 @@
 use std::{path::PathBuf, fs::File, io::Write};
 use crate::graph_embedding::GraphEmbedding;
+use crate::params::argv::GraphFormat;
 
-/// Generate a graph to dot file for the given file.
+/// Generate a graph to dot file (or GraphML, GEXF file) for the given file.
 pub fn gen_and_save_memory_graph(
     output_file_path: PathBuf, 
     graph_embedding: &GraphEmbedding,
 ) -> usize {
-    // annotate the graph
-    let mut dot_file = File::create(output_file_path).unwrap();
-    dot_file.write_all(
-        format!("{}", 
-        graph_embedding.graph_annotate.graph_data
-    ).as_bytes()).unwrap(); // using the custom formatter
+    let graph_data = &graph_embedding.graph_annotate.graph_data;
+    let graph_str = match graph_embedding.get_graph_format() {
+        GraphFormat::Dot => format!("{}", graph_data), // using the custom formatter
+        GraphFormat::Graphml => graph_data.to_graphml(None),
+        GraphFormat::Gexf => graph_data.to_gexf(None),
+    };
+
+    let mut graph_file = File::create(output_file_path).unwrap();
+    graph_file.write_all(graph_str.as_bytes()).unwrap();
     return 0; // no samples, only the graph
 }
@@ -2,26 +2,42 @@ use std::collections::HashMap;
 use std::{path::PathBuf, fs::File, io::Write};
 
 use crate::graph_embedding::GraphEmbedding;
-use crate::graph_embedding::embedding::chunk_semantic_embedding::generate_semantic_samples_of_a_chunk;
-use crate::graph_embedding::embedding::chunk_start_bytes_embedding::generate_chunk_start_bytes_sample;
-use crate::graph_embedding::embedding::chunk_statistic_embedding::generate_chunk_statistic_samples;
+use crate::graph_embedding::embedding::chunk_semantic_embedding::{chunk_semantic_columns, generate_semantic_samples_of_a_chunk};
+use crate::graph_embedding::embedding::chunk_byte_histogram_embedding::{chunk_byte_histogram_columns, generate_chunk_byte_histogram_sample};
+use crate::graph_embedding::embedding::chunk_entropy_profile_embedding::{chunk_entropy_profile_columns, generate_chunk_entropy_profile_sample};
+use crate::graph_embedding::embedding::chunk_layout_embedding::{chunk_layout_columns, generate_chunk_layout_sample};
+use crate::graph_embedding::embedding::chunk_start_bytes_embedding::{chunk_start_bytes_columns, generate_chunk_start_bytes_sample};
+use crate::graph_embedding::embedding::chunk_statistic_embedding::{chunk_statistic_columns, generate_chunk_statistic_samples};
+use crate::graph_data::export::ExtraNodeAttributes;
+use crate::graph_embedding::samples::{SampleValue, Columns, ColumnType};
 use crate::graph_structs::Node;
-use crate::params::{N_GRAM, BLOCK_BYTE_SIZE, self};
-use crate::params::argv::{SelectAnnotationLocation, Pipeline};
+use crate::params::BLOCK_BYTE_SIZE;
+use crate::params::argv::{SelectAnnotationLocation, Pipeline, GraphFormat};
+
+/// columns of the embedding used for the node comments (see generate_chunk_node_embedding)
+pub fn get_embedding_columns(graph_embedding: &GraphEmbedding) -> Columns {
+    match graph_embedding.get_graph_comment_embedding_type() {
+        Pipeline::ChunkSemanticEmbedding => chunk_semantic_columns(graph_embedding),
+        Pipeline::ChunkStatisticEmbedding => chunk_statistic_columns(graph_embedding, graph_embedding.get_n_gram()),
+        Pipeline::ChunkStartBytesEmbedding => chunk_start_bytes_columns(graph_embedding),
+        Pipeline::ChunkByteHistogramEmbedding => chunk_byte_histogram_columns(graph_embedding),
+        Pipeline::ChunkEntropyProfileEmbedding => chunk_entropy_profile_columns(graph_embedding),
+        Pipeline::ChunkLayoutEmbedding => chunk_layout_columns(graph_embedding),
+        _ => {
+            panic!("🚩 {:?} not supported for graph generation with embedding comments",
+                graph_embedding.get_graph_comment_embedding_type()
+            );
+        }
+    }
+}
 
 /// Generate a string representing the header of the embedding.
 /// It is composed of the names of the fields of the embedding, 
 /// separated by commas.
 fn generate_embedding_header(
     graph_embedding: &GraphEmbedding,
-    node_embeddings: &Vec<(&u64, HashMap<String, String>, f64)>,
+    embedding_fields: &[String],
 ) -> String {
-    // sorted header of the embedding
-    let (_, first_embedding, _) = node_embeddings.get(0).unwrap();
-
-    let mut embedding_fields = first_embedding.keys().cloned().collect::<Vec<String>>();
-    embedding_fields.sort();
-
     let optional_filtering = {
         if graph_embedding.is_filtering_active() {
             ",'filtered'"
@@ -75,16 +91,49 @@ fn get_len_of_str_list(list_as_str: &String) -> usize {
     nb_of_elements
 }
 
+/// Embedding of a chunk, with the embedding type used for the node comments.
+/// NOTE: The values are in the order of get_embedding_columns.
+pub fn generate_chunk_node_embedding(graph_embedding: &GraphEmbedding, chn_addr: u64) -> Vec<SampleValue> {
+    match graph_embedding.get_graph_comment_embedding_type() {
+        Pipeline::ChunkSemanticEmbedding => {
+            generate_semantic_samples_of_a_chunk(graph_embedding, chn_addr)
+        }, 
+        Pipeline::ChunkStatisticEmbedding => {
+            generate_chunk_statistic_samples(
+                graph_embedding, chn_addr, graph_embedding.get_n_gram(), BLOCK_BYTE_SIZE
+            )
+        }
+        Pipeline::ChunkStartBytesEmbedding => {
+            generate_chunk_start_bytes_sample(graph_embedding, chn_addr)
+        }
+        Pipeline::ChunkByteHistogramEmbedding => {
+            generate_chunk_byte_histogram_sample(graph_embedding, chn_addr)
+        }
+        Pipeline::ChunkEntropyProfileEmbedding => {
+            generate_chunk_entropy_profile_sample(graph_embedding, chn_addr)
+        }
+        Pipeline::ChunkLayoutEmbedding => {
+            generate_chunk_layout_sample(graph_embedding, chn_addr)
+        }
+        _ => {
+            panic!("🚩 {:?} not supported for graph generation with embedding comments",
+                graph_embedding.get_graph_comment_embedding_type()
+            );
+        }
+    }
+}
+
 /// Parse each node of the graph to generate its embedding
 /// Returns a list of tuples (node_addr, node_embedding, entropy)
+/// NOTE: The values of each node embedding are in the order of the columns of the embedding.
 fn generate_base_nodes_embedding(
     graph_embedding: &GraphEmbedding,
-) ->  Vec<(&u64, HashMap<String, String>, f64)>{
+) ->  Vec<(&u64, Vec<SampleValue>, f64)>{
     // parse each node of the graph to generate its embedding
     let graph =  &graph_embedding.graph_annotate.graph_data;
 
     // generate the node embeddings
-    let mut node_embeddings: Vec<(&u64, HashMap<String, String>, f64)> = Vec::new();
+    let mut node_embeddings: Vec<(&u64, Vec<SampleValue>, f64)> = Vec::new();
     for chn_addr in graph.chn_addrs.iter() {
         let chn = match graph.addr_to_node.get(chn_addr) {
             Some(chn) => chn,
@@ -100,47 +149,7 @@ fn generate_base_nodes_embedding(
                 }
                 
                 // compute embedding
-                let node_embedding: HashMap<String, String> = match params::ARGV.graph_comment_embedding_type {
-                    Pipeline::ChunkSemanticEmbedding => {
-                        let features = generate_semantic_samples_of_a_chunk(
-                            graph_embedding, *chn_addr
-                        )
-                        // convert the features to string
-                        .iter().map(|(k, v)| {
-                            (k.clone(), v.to_string())
-                        }).collect::<HashMap<String, String>>();
-                        features
-                    }, 
-                    Pipeline::ChunkStatisticEmbedding => {
-                        let (feature_usize, feature_f64) = generate_chunk_statistic_samples(
-                            graph_embedding, *chn_addr, &*N_GRAM, BLOCK_BYTE_SIZE
-                        );
-                        // combines the two hashmaps into one of string
-                        let mut features = feature_usize
-                            .iter().map(|(k, v)| {
-                                (k.clone(), v.to_string())
-                            }).collect::<HashMap<String, String>>();
-                        features.extend(
-                            feature_f64.iter().map(|(k, v)| {
-                                (k.clone(), v.to_string())
-                            }).collect::<HashMap<String, String>>()
-                        );
-                        features
-                    }
-                    Pipeline::ChunkStartBytesEmbedding => {
-                        let features = generate_chunk_start_bytes_sample(
-                            graph_embedding, *chn_addr
-                        ).iter().map(|(k, v)| {
-                            (k.clone(), v.to_string())
-                        }).collect::<HashMap<String, String>>();
-                        features
-                    }
-                    _ => {
-                        panic!("🚩 {:?} not supported for graph generation with embedding comments",
-                            params::ARGV.graph_comment_embedding_type
-                        );
-                    }
-                };
+                let node_embedding = generate_chunk_node_embedding(graph_embedding, *chn_addr);
 
                 node_embeddings.push((chn_addr, node_embedding, chn.start_data_bytes_entropy));
             },
@@ -154,7 +163,7 @@ fn generate_base_nodes_embedding(
 
 fn convert_nodes_embedding_to_comment_hashmap(
     graph_embedding: &GraphEmbedding,
-    node_embeddings: &Vec<(&u64, HashMap<String, String>, f64)>,
+    node_embeddings: &Vec<(&u64, Vec<SampleValue>, f64)>,
     header_embedding_length: usize,
 ) -> HashMap<u64, String> {
     let mut node_addr_to_embedding_comment = HashMap::new();
@@ -163,7 +172,7 @@ fn convert_nodes_embedding_to_comment_hashmap(
 
         // convert the embedding to a string
         node_embedding_str.push_str(
-            &node_embedding.values().cloned().collect::<Vec<String>>().join(",")
+            &node_embedding.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",")
         );
 
         // add entropy as additional field
@@ -198,21 +207,44 @@ fn convert_nodes_embedding_to_comment_hashmap(
     node_addr_to_embedding_comment
 }
 
-/// Generate a graph to dot file for the given file.
-pub fn gen_and_save_memory_graph_with_embedding_comments(
-    output_file_path: PathBuf, 
+/// Convert the node embeddings to typed node attributes, for the GraphML and GEXF formats.
+/// NOTE: The entropy is already an attribute of the chunk nodes.
+fn convert_nodes_embedding_to_extra_node_attributes(
     graph_embedding: &GraphEmbedding,
-) -> usize {
-    let graph =  &graph_embedding.graph_annotate.graph_data;
+    node_embeddings: Vec<(&u64, Vec<SampleValue>, f64)>,
+    used_embedding_type: &str,
+) -> ExtraNodeAttributes {
+    let mut columns = get_embedding_columns(graph_embedding);
+    if graph_embedding.is_filtering_active() {
+        columns.push("filtered", ColumnType::Int);
+    }
 
-    // generate the node embeddings
-    let node_embeddings = generate_base_nodes_embedding(graph_embedding);
+    let mut node_addr_to_values = HashMap::new();
+    for (node_addr, mut node_embedding, _) in node_embeddings {
+        if graph_embedding.is_filtering_active() {
+            node_embedding.push(SampleValue::Int(graph_embedding.is_filtered_addr(node_addr) as usize));
+        }
+        node_addr_to_values.insert(*node_addr, node_embedding);
+    }
+
+    ExtraNodeAttributes {
+        graph_attributes: vec![("embedding_type".to_string(), used_embedding_type.to_string())],
+        columns,
+        node_addr_to_values,
+    }
+}
 
+/// Generate the dot graph, with the embedding of each node in its comment.
+fn generate_dot_with_embedding_comments(
+    graph_embedding: &GraphEmbedding,
+    node_embeddings: &Vec<(&u64, Vec<SampleValue>, f64)>,
+    used_embedding_type: &str,
+) -> String {
     // generate the header of the embedding, and its length
     let header_embedding_fields = format!("[{}]",
         generate_embedding_header(
             graph_embedding, 
-            &node_embeddings
+            &get_embedding_columns(graph_embedding)
         )
     );
     let header_embedding_length = get_len_of_str_list(&header_embedding_fields);
@@ -220,36 +252,60 @@ pub fn gen_and_save_memory_graph_with_embedding_comments(
     // convert the node embeddings to a hashmap of comments
     let node_addr_to_embedding_comment = convert_nodes_embedding_to_comment_hashmap(
         graph_embedding, 
-        &node_embeddings, 
+        node_embeddings, 
         header_embedding_length
     );
 
-    // save the graph to dot file
-    let mut dot_file = File::create(output_file_path).unwrap();
-    
+    let graph_comment = format!(
+        "{{ 'embedding-type': '{}', 'embedding-fields': {} }}", 
+        used_embedding_type, header_embedding_fields
+    );
+    graph_embedding.graph_annotate.graph_data.stringify_with_comment_hashmap( 
+        graph_comment, 
+        &node_addr_to_embedding_comment
+    )
+}
+
+/// Generate a graph to dot file for the given file.
+/// NOTE: With the GraphML and GEXF formats, the embedding fields are typed attributes of the nodes.
+pub fn gen_and_save_memory_graph_with_embedding_comments(
+    output_file_path: PathBuf, 
+    graph_embedding: &GraphEmbedding,
+) -> usize {
+    let graph =  &graph_embedding.graph_annotate.graph_data;
+
+    // generate the node embeddings
+    let node_embeddings = generate_base_nodes_embedding(graph_embedding);
+
     // WARN: Using the char '-' in the comment field is not supported by the dot format (tested on sfdp)
-    let used_embedding_type = match params::ARGV.graph_comment_embedding_type {
+    let used_embedding_type = match graph_embedding.get_graph_comment_embedding_type() {
         Pipeline::ChunkSemanticEmbedding => "chunk-semantic-embedding",
         Pipeline::ChunkStatisticEmbedding => "chunk-statistic-embedding",
         Pipeline::ChunkStartBytesEmbedding => "chunk-start-bytes-embedding",
+        Pipeline::ChunkByteHistogramEmbedding => "chunk-byte-histogram-embedding",
+        Pipeline::ChunkEntropyProfileEmbedding => "chunk-entropy-profile-embedding",
+        Pipeline::ChunkLayoutEmbedding => "chunk-layout-embedding",
         _ => {
             panic!("🚩 {:?} not supported for graph generation with embedding comments",
-                params::ARGV.graph_comment_embedding_type
+                graph_embedding.get_graph_comment_embedding_type()
             );
         }
     };
-    let graph_comment = format!(
-        "{{ 'embedding-type': '{}', 'embedding-fields': {} }}", 
-        used_embedding_type, header_embedding_fields
-    );
-    let dot_graph_with_comments = format!("{}", 
-        graph.stringify_with_comment_hashmap( 
-            graph_comment, 
-            &node_addr_to_embedding_comment
-        )
-    );
 
-    dot_file.write_all(
-        dot_graph_with_comments.as_bytes()).unwrap(); // using the custom formatter
+    let graph_str = match graph_embedding.get_graph_format() {
+        GraphFormat::Dot => generate_dot_with_embedding_comments(
+            graph_embedding, &node_embeddings, used_embedding_type
+        ),
+        GraphFormat::Graphml => graph.to_graphml(Some(
+            &convert_nodes_embedding_to_extra_node_attributes(graph_embedding, node_embeddings, used_embedding_type)
+        )),
+        GraphFormat::Gexf => graph.to_gexf(Some(
+            &convert_nodes_embedding_to_extra_node_attributes(graph_embedding, node_embeddings, used_embedding_type)
+        )),
+    };
+
+    // save the graph to file
+    let mut graph_file = File::create(output_file_path).unwrap();
+    graph_file.write_all(graph_str.as_bytes()).unwrap();
     return 0; // no samples, only the graph
 }
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::samples::SampleValue;
+use crate::graph_structs::EdgeType;
+use crate::params::argv::SelectAnnotationLocation;
+use super::graph_generation_with_embedding::{get_embedding_columns, generate_chunk_node_embedding};
+use super::npy::{NpyDtype, npy_values_bytes, npy_str_bytes, save_npz};
+
+/// share of the chunks in the validation mask (the other ones are in the train mask)
+pub const GRAPH_TENSORS_VAL_RATIO: f64 = 0.2;
+
+/// names of the edge types, by their value in the edge_type array
+const EDGE_TYPE_NAMES: [&str; 2] = ["chunk", "ptr"];
+
+fn get_edge_type_index(edge_type: &EdgeType) -> u8 {
+    match edge_type {
+        EdgeType::ChunkEdge => 0,
+        EdgeType::PointerEdge => 1,
+    }
+}
+
+/// deterministic pseudo random value in [0, 1) of an address (splitmix64), for the train/val split
+/// NOTE: A chunk is always in the same mask, whatever the other chunks of the heap dump.
+fn get_split_value(addr: u64) -> f64 {
+    let mut z = addr.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Tensors of the graph of the chunks of a heap dump, for the GNN libraries (PyTorch Geometric, DGL).
+/// The nodes are the chunks, with dense indices (in the order of the chunks in the heap).
+/// The edges of the value and pointer nodes are moved to their chunk, and merged (their weights are summed).
+/// NOTE: The chunk edges inside a chunk are removed, the pointers from a chunk to itself are kept.
+pub struct GraphTensors {
+    /// names of the node features
+    pub feature_names: Vec<String>,
+    /// address of the chunk of each node
+    pub node_addrs: Vec<u64>,
+    /// features of the nodes, row by row, in the order of the feature names
+    pub features: Vec<SampleValue>,
+    pub labels: Vec<usize>,
+    /// (source index, target index, edge type, weight), sorted
+    pub edges: Vec<(usize, usize, u8, usize)>,
+    /// NOTE: The filtered chunks are neither in the train mask nor in the val mask.
+    pub train_mask: Vec<bool>,
+    pub val_mask: Vec<bool>,
+}
+
+impl GraphTensors {
+    pub fn from_graph_embedding(graph_embedding: &GraphEmbedding) -> GraphTensors {
+        if graph_embedding.graph_annotate.annotation != SelectAnnotationLocation::ChunkHeaderNode {
+            panic!("🚩 For the graph tensors, the annotation must be ChunkHeaderNode");
+        }
+        let graph_data = &graph_embedding.graph_annotate.graph_data;
+
+        // nodes
+        let node_addrs = graph_data.chn_addrs.clone();
+
+        let feature_names = get_embedding_columns(graph_embedding).names().to_vec();
+        let mut features = Vec::with_capacity(node_addrs.len() * feature_names.len());
+        let mut labels = Vec::with_capacity(node_addrs.len());
+        let mut train_mask = Vec::with_capacity(node_addrs.len());
+        let mut val_mask = Vec::with_capacity(node_addrs.len());
+        for chn_addr in node_addrs.iter() {
+            let node_features = generate_chunk_node_embedding(graph_embedding, *chn_addr);
+            assert_eq!(node_features.len(), feature_names.len(), "Headers mismatch between samples!");
+            features.extend(node_features);
+            labels.push(graph_embedding.get_node_label(*chn_addr));
+
+            let is_kept = !graph_embedding.is_filtered_addr(chn_addr);
+            let is_val = get_split_value(*chn_addr) < GRAPH_TENSORS_VAL_RATIO;
+            train_mask.push(is_kept && !is_val);
+            val_mask.push(is_kept && is_val);
+        }
+
+        // edges, moved to the chunks
+        let pointer_edge_type = get_edge_type_index(&EdgeType::PointerEdge);
+        let edges = graph_data.get_chunk_pointer_edges(&graph_data.get_chn_addr_to_index())
+            .into_iter()
+            .map(|((from_index, to_index), weight)| (from_index, to_index, pointer_edge_type, weight))
+            .collect();
+
+        GraphTensors {
+            feature_names,
+            node_addrs,
+            features,
+            labels,
+            edges,
+            train_mask,
+            val_mask,
+        }
+    }
+
+    /// Save the tensors in a NPZ archive, with the arrays:
+    ///     - x: node features (nb nodes, nb features), f64
+    ///     - edge_index: (2, nb edges), i64 (COO format)
+    ///     - edge_type: (nb edges), u8 (see edge_type_names)
+    ///     - edge_weight: (nb edges), f64
+    ///     - y: labels of the nodes, i64
+    ///     - train_mask, val_mask: bool
+    ///     - node_addr: address of the chunk of each node, u64
+    ///     - feature_names, edge_type_names: strings
+    pub fn save_npz(&self, npz_path: &Path) {
+        let nb_nodes = self.node_addrs.len();
+        let nb_edges = self.edges.len();
+
+        let features: Vec<u64> = self.features.iter()
+            .map(|value| value.as_f64().expect("🚩 The node features must be numeric").to_bits())
+            .collect();
+        let mut edge_index: Vec<u64> = self.edges.iter().map(|(from_index, _, _, _)| *from_index as u64).collect();
+        edge_index.extend(self.edges.iter().map(|(_, to_index, _, _)| *to_index as u64));
+        let edge_type: Vec<u64> = self.edges.iter().map(|(_, _, edge_type, _)| *edge_type as u64).collect();
+        let edge_weight: Vec<u64> = self.edges.iter().map(|(_, _, _, weight)| (*weight as f64).to_bits()).collect();
+        let labels: Vec<u64> = self.labels.iter().map(|label| *label as u64).collect();
+        let train_mask: Vec<u64> = self.train_mask.iter().map(|is_train| *is_train as u64).collect();
+        let val_mask: Vec<u64> = self.val_mask.iter().map(|is_val| *is_val as u64).collect();
+
+        let npy_files = [
+            ("x", npy_values_bytes(NpyDtype::F64, &[nb_nodes, self.feature_names.len()], &features, true)),
+            ("edge_index", npy_values_bytes(NpyDtype::I64, &[2, nb_edges], &edge_index, false)),
+            ("edge_type", npy_values_bytes(NpyDtype::U8, &[nb_edges], &edge_type, false)),
+            ("edge_weight", npy_values_bytes(NpyDtype::F64, &[nb_edges], &edge_weight, true)),
+            ("y", npy_values_bytes(NpyDtype::I64, &[nb_nodes], &labels, false)),
+            ("train_mask", npy_values_bytes(NpyDtype::Bool, &[nb_nodes], &train_mask, false)),
+            ("val_mask", npy_values_bytes(NpyDtype::Bool, &[nb_nodes], &val_mask, false)),
+            ("node_addr", npy_values_bytes(NpyDtype::U64, &[nb_nodes], &self.node_addrs, false)),
+            ("feature_names", npy_str_bytes(&self.feature_names)),
+            ("edge_type_names", npy_str_bytes(&EDGE_TYPE_NAMES.map(|name| name.to_string()))),
+        ];
+        save_npz(npz_path, npy_files.iter().map(|(array_name, npy_file)| (*array_name, npy_file.as_slice())).collect())
+            .unwrap_or_else(|err| panic!("🚩 {}", err));
+    }
+}
+
+/// Generate the graph tensors of the chunks of the given file, and save them to a NPZ archive.
+/// NOTE: The node features are the embedding given by the graph comment embedding type.
+pub fn gen_and_save_graph_tensors(
+    output_file_path: PathBuf,
+    graph_embedding: &GraphEmbedding,
+) -> usize {
+    let graph_tensors = GraphTensors::from_graph_embedding(graph_embedding);
+    graph_tensors.save_npz(&output_file_path);
+    graph_tensors.node_addrs.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+
+    #[test]
+    fn test_get_split_value() {
+        let addrs: Vec<u64> = (0..1000).map(|i| 0x558343d1a008 + 16 * i).collect();
+        let nb_val = addrs.iter().filter(|addr| get_split_value(**addr) < GRAPH_TENSORS_VAL_RATIO).count();
+        assert!(nb_val > 150 && nb_val < 250, "{}", nb_val);
+        assert_eq!(get_split_value(addrs[0]), get_split_value(addrs[0]));
+    }
+
+    #[test]
+    fn test_graph_tensors() {
+        crate::tests::setup();
+
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+        let graph_tensors = GraphTensors::from_graph_embedding(&graph_embedding);
+
+        let nb_nodes = graph_embedding.graph_annotate.graph_data.chn_addrs.len();
+        assert_eq!(graph_tensors.node_addrs.len(), nb_nodes);
+        assert_eq!(graph_tensors.features.len(), nb_nodes * graph_tensors.feature_names.len());
+        assert_eq!(graph_tensors.labels.len(), nb_nodes);
+        assert!(graph_tensors.labels.iter().any(|label| *label != 0));
+
+        // only pointers between chunks, no chunk edge left
+        assert!(!graph_tensors.edges.is_empty());
+        for (from_index, to_index, edge_type, weight) in graph_tensors.edges.iter() {
+            assert!(*from_index < nb_nodes && *to_index < nb_nodes);
+            assert_eq!(EDGE_TYPE_NAMES[*edge_type as usize], "ptr");
+            assert!(*weight > 0);
+        }
+        let pointer_weight: usize = graph_embedding.graph_annotate.graph_data.graph.all_edges()
+            .filter(|(_, _, edge)| matches!(edge.edge_type, EdgeType::PointerEdge))
+            .map(|(_, _, edge)| edge.weight)
+            .sum();
+        assert_eq!(graph_tensors.edges.iter().map(|(_, _, _, weight)| weight).sum::<usize>(), pointer_weight);
+
+        // a node is in at most one mask
+        for (is_train, is_val) in graph_tensors.train_mask.iter().zip(graph_tensors.val_mask.iter()) {
+            assert!(!(*is_train && *is_val));
+        }
+        assert!(graph_tensors.val_mask.iter().any(|is_val| *is_val));
+    }
+}
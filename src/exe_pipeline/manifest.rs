@@ -0,0 +1,229 @@
+use std::{path::{Path, PathBuf}, fs::{File, OpenOptions}, io::{Read, Write}, collections::HashMap, sync::Mutex};
+use serde_derive::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+/// name of the run manifest, in each output folder
+pub const RUN_MANIFEST_FILE_NAME: &str = "run_manifest.jsonl";
+
+/// Outcome of the processing of an input file, for a given output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunStatus {
+    /// the output has been saved
+    Done,
+    /// the graph couldn't be built (ex: missing JSON key), nothing has been saved
+    Failed,
+}
+
+/// Record of the processing of an input file, for a given output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifestEntry {
+    /// name of the output file, in the output folder
+    pub output_file_name: String,
+    pub input_file_path: PathBuf,
+    /// SHA-256 of the input files (heap dump, and its JSON file if the graph is annotated)
+    pub input_hash: String,
+    /// parameters of the run configuration
+    pub parameters: serde_json::Value,
+    pub status: RunStatus,
+    pub nb_samples: usize,
+    pub duration_ms: u64,
+}
+
+/// Run manifest of an output folder.
+/// One JSON entry per line, appended as soon as an output is done,
+/// so that an interrupted run can be resumed.
+/// NOTE: When an output is recorded several times, the last entry is the valid one.
+pub struct RunManifest {
+    entries: HashMap<String, RunManifestEntry>,
+    file: Mutex<File>,
+}
+
+impl RunManifest {
+    /// Open the run manifest of the given output folder (created if needed).
+    /// NOTE: A truncated last line (interrupted write) is ignored.
+    /// :ignore_previous_entries: if true, the previous entries are not loaded (everything is redone)
+    pub fn open(output_folder: &Path, ignore_previous_entries: bool) -> RunManifest {
+        let manifest_path = output_folder.join(RUN_MANIFEST_FILE_NAME);
+
+        let content = match manifest_path.exists() {
+            true => std::fs::read_to_string(&manifest_path).unwrap_or_else(
+                |_| panic!("Cannot read run manifest: {:?}", manifest_path)
+            ),
+            false => String::new(),
+        };
+        let mut entries: HashMap<String, RunManifestEntry> = HashMap::new();
+        if !ignore_previous_entries {
+            for line in content.lines() {
+                match serde_json::from_str::<RunManifestEntry>(line) {
+                    Ok(entry) => {
+                        entries.insert(entry.output_file_name.clone(), entry);
+                    },
+                    Err(_) => log::warn!(" 🟠 Invalid run manifest entry ignored, in: {:?}", manifest_path),
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&manifest_path).unwrap_or_else(
+            |_| panic!("Cannot create run manifest: {:?}", manifest_path)
+        );
+        // end the truncated line, if any
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n").unwrap();
+        }
+        RunManifest { entries, file: Mutex::new(file) }
+    }
+
+    /// true if the output has been done from the same input files with the same parameters
+    /// NOTE: The output file must still exist.
+    pub fn is_done(&self, output_file_path: &Path, input_hash: &str, parameters: &serde_json::Value) -> bool {
+        let output_file_name = output_file_path.file_name().unwrap().to_str().unwrap();
+        match self.entries.get(output_file_name) {
+            Some(entry) => {
+                entry.status == RunStatus::Done
+                    && entry.input_hash == input_hash
+                    && &entry.parameters == parameters
+                    && output_file_path.exists()
+            },
+            None => false,
+        }
+    }
+
+    /// append the entry to the manifest file
+    /// NOTE: The loaded entries are not updated (they are the ones of the previous runs).
+    pub fn record(&self, entry: &RunManifestEntry) {
+        let mut line = serde_json::to_string(entry).unwrap();
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(line.as_bytes()).unwrap();
+        file.flush().unwrap();
+    }
+}
+
+/// SHA-256 (hex) of the content of the given files
+pub fn compute_files_hash(file_paths: &[PathBuf]) -> String {
+    match compute_files_prefix_hashes(file_paths).pop() {
+        Some(hash) => hash,
+        None => hex::encode(Sha256::new().finalize()),
+    }
+}
+
+/// SHA-256 (hex) of the content of the first 1, 2, ... n given files, 
+/// reading each file once (the hash of the first k files is the one of compute_files_hash)
+/// ex: [heap dump, JSON] -> [hash of the heap dump, hash of the heap dump and the JSON]
+pub fn compute_files_prefix_hashes(file_paths: &[PathBuf]) -> Vec<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut hashes = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths.iter() {
+        let mut file = File::open(file_path).unwrap_or_else(
+            |_| panic!("Cannot open file to hash: {:?}", file_path)
+        );
+        loop {
+            let nb_read_bytes = file.read(&mut buffer).unwrap();
+            if nb_read_bytes == 0 {
+                break;
+            }
+            hasher.update(&buffer[..nb_read_bytes]);
+        }
+        hashes.push(hex::encode(hasher.clone().finalize()));
+    }
+    hashes
+}
+
+/// path of the temporary file of an output, renamed to the output once fully written
+/// (ex: "output.csv" -> "output.csv.tmp")
+pub fn get_tmp_file_path(output_file_path: &Path) -> PathBuf {
+    let mut tmp_file_name = output_file_path.file_name().unwrap().to_os_string();
+    tmp_file_name.push(".tmp");
+    output_file_path.with_file_name(tmp_file_name)
+}
+
+/// rename the temporary file of an output (see get_tmp_file_path) to the output, once fully written
+pub fn rename_tmp_file(output_file_path: &Path) {
+    let tmp_file_path = get_tmp_file_path(output_file_path);
+    std::fs::rename(&tmp_file_path, output_file_path).unwrap_or_else(
+        |_| panic!("Cannot rename {:?} to {:?}", tmp_file_path, output_file_path)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_entry(output_file_name: &str, status: RunStatus) -> RunManifestEntry {
+        RunManifestEntry {
+            output_file_name: output_file_name.to_string(),
+            input_file_path: PathBuf::from("input-heap.raw"),
+            input_hash: "hash".to_string(),
+            parameters: serde_json::json!({"pipeline": "graph"}),
+            status,
+            nb_samples: 0,
+            duration_ms: 1,
+        }
+    }
+
+    #[test]
+    fn test_run_manifest_resume() {
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_run_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+        let output_file_path = output_folder.join("done.csv");
+        std::fs::write(&output_file_path, "a,label\n1,0\n").unwrap();
+        let parameters = serde_json::json!({"pipeline": "graph"});
+
+        let manifest = RunManifest::open(&output_folder, false);
+        assert!(!manifest.is_done(&output_file_path, "hash", &parameters));
+        manifest.record(&new_entry("failed.csv", RunStatus::Failed));
+        manifest.record(&new_entry("done.csv", RunStatus::Done));
+        drop(manifest);
+
+        // interrupted write of the last entry
+        let mut manifest_file = OpenOptions::new().append(true).open(output_folder.join(RUN_MANIFEST_FILE_NAME)).unwrap();
+        manifest_file.write_all(b"{\"output_file_name\": \"trunc").unwrap();
+        drop(manifest_file);
+
+        let manifest = RunManifest::open(&output_folder, false);
+        assert!(manifest.is_done(&output_file_path, "hash", &parameters));
+        manifest.record(&new_entry("after_truncated.csv", RunStatus::Done));
+        drop(manifest);
+
+        let manifest = RunManifest::open(&output_folder, false);
+        assert!(manifest.is_done(&output_file_path, "hash", &parameters));
+        assert!(manifest.entries.contains_key("after_truncated.csv"));
+        assert!(!manifest.is_done(&output_folder.join("failed.csv"), "hash", &parameters));
+        // changed input or parameters
+        assert!(!manifest.is_done(&output_file_path, "other_hash", &parameters));
+        assert!(!manifest.is_done(&output_file_path, "hash", &serde_json::json!({"pipeline": "chunk-extraction"})));
+
+        // forced run
+        let manifest = RunManifest::open(&output_folder, true);
+        assert!(!manifest.is_done(&output_file_path, "hash", &parameters));
+
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+
+    #[test]
+    fn test_compute_files_hash() {
+        crate::tests::setup();
+
+        let file_path = crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone();
+        let hash = compute_files_hash(&[file_path.clone()]);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, compute_files_hash(&[file_path.clone()]));
+        assert_ne!(hash, compute_files_hash(&[file_path.clone(), file_path.clone()]));
+
+        let json_file_path = crate::tests::TEST_HEAP_JSON_FILE_PATH.clone();
+        let hashes = compute_files_prefix_hashes(&[file_path.clone(), json_file_path.clone()]);
+        assert_eq!(hashes, vec![hash, compute_files_hash(&[file_path, json_file_path])]);
+        assert_eq!(compute_files_hash(&[]).len(), 64);
+    }
+
+    #[test]
+    fn test_get_tmp_file_path() {
+        assert_eq!(
+            get_tmp_file_path(Path::new("out/Graph_file-heap.raw_dot.gv")),
+            PathBuf::from("out/Graph_file-heap.raw_dot.gv.tmp")
+        );
+    }
+}
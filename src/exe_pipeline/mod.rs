@@ -1,14 +1,28 @@
-use std::{path::PathBuf, collections::HashMap};
-use csv::Writer;
+use std::path::PathBuf;
 use walkdir::WalkDir;
 
+use crate::graph_embedding::samples::{Samples, SamplesIter};
+use self::sink::{SamplesSink, CsvSink};
+
 pub mod pipeline;
+pub mod manifest;
+pub mod sink;
+pub mod aggregate;
+pub mod npy;
 pub mod value_embedding;
 pub mod graph_generation;
 pub mod graph_generation_with_embedding;
+pub mod graph_tensors;
 pub mod chunk_semantic_embedding;
+pub mod chunk_wl_subtree_embedding;
+pub mod chunk_centrality_embedding;
+pub mod random_walk_corpus;
+pub mod chunk_random_walk_embedding;
 pub mod chunk_statistic_embedding;
 pub mod chunk_top_vn_semantic_embedding;
+pub mod chunk_byte_histogram_embedding;
+pub mod chunk_entropy_profile_embedding;
+pub mod chunk_layout_embedding;
 pub mod chunk_start_bytes_embedding;
 pub mod chunk_extract;
 
@@ -38,122 +52,33 @@ fn get_raw_file_or_files_from_path(path: PathBuf) -> Vec<PathBuf> {
     return raw_file_paths;
 }
 
-/// Save the samples and labels to a CSV file.
-pub fn save_embedding(samples: Vec<HashMap<String, usize>>, labels: Vec<usize>, csv_path: PathBuf) {
-    assert!(!samples.is_empty(), "Samples cannot be empty for CSV header extraction.");
-
-    let csv_error_message = format!("Cannot create csv file: {:?}, no such file.", csv_path);
-    let mut csv_writer = Writer::from_path(&csv_path).unwrap_or_else(
-        |_| panic!("{}", csv_error_message)
-    );
-
-    // Assuming all HashMaps have the same set of keys
-    let mut headers: Vec<String> = samples[0].keys().cloned().collect();
-    headers.sort();
-    
-    let mut all_headers = headers.clone();
-    all_headers.push("label".to_string());
-
-    csv_writer.write_record(&all_headers).unwrap();
-
-    // save samples and labels to CSV
-    for (sample, &label) in samples.iter().zip(&labels) {
-        // Check if all headers are present in the current sample
-        if headers.iter().any(|h| !sample.contains_key(h)) {
-            panic!("Headers mismatch between samples!");
-        }
-
-        let mut row: Vec<String> = headers.iter()
-            .map(|header| sample.get(header).unwrap().to_string()) // unwrap is safe here since we've checked keys
-            .collect();
-
-        row.push(label.to_string());
-
-        csv_writer.write_record(&row).unwrap();
+/// Write the samples and labels to the sink, in the order of their columns.
+pub fn write_embedding(samples: &Samples, sink: &mut dyn SamplesSink) {
+    sink.write_columns(samples.columns());
+    for (values, label) in samples.iter() {
+        sink.write_sample(&values, label);
     }
-
-    csv_writer.flush().unwrap();
+    sink.flush();
 }
 
-/// Save the samples and labels to a CSV file.
-pub fn save_embedding_with_string(samples: Vec<HashMap<String, String>>, labels: Vec<usize>, csv_path: PathBuf) {
-    assert!(!samples.is_empty(), "Samples cannot be empty for CSV header extraction.");
-
-    let csv_error_message = format!("Cannot create csv file: {:?}, no such file.", csv_path);
-    let mut csv_writer = Writer::from_path(&csv_path).unwrap_or_else(
-        |_| panic!("{}", csv_error_message)
-    );
-
-    // Assuming all HashMaps have the same set of keys
-    let mut headers: Vec<String> = samples[0].keys().cloned().collect();
-    headers.sort();
-    
-    let mut all_headers = headers.clone();
-    all_headers.push("label".to_string());
-
-    csv_writer.write_record(&all_headers).unwrap();
-
-    // save samples and labels to CSV
-    for (sample, &label) in samples.iter().zip(&labels) {
-        // Check if all headers are present in the current sample
-        if headers.iter().any(|h| !sample.contains_key(h)) {
-            panic!("Headers mismatch between samples!");
-        }
-
-        let mut row: Vec<String> = headers.iter()
-            .map(|header| sample.get(header).unwrap().clone()) // unwrap is safe here since we've checked keys
-            .collect();
-
-        row.push(label.to_string());
-
-        csv_writer.write_record(&row).unwrap();
+/// Write the samples and labels to the sink, one by one, while they are generated.
+/// Only one sample is kept in memory at a time.
+/// Return the number of samples written.
+pub fn stream_embedding(samples: SamplesIter, sink: &mut dyn SamplesSink) -> usize {
+    let columns = samples.columns().clone();
+    sink.write_columns(&columns);
+    let mut nb_samples = 0;
+    for (values, label) in samples {
+        assert_eq!(values.len(), columns.len(), "Headers mismatch between samples!");
+        sink.write_sample(&values, label);
+        nb_samples += 1;
     }
-
-    csv_writer.flush().unwrap();
+    sink.flush();
+    nb_samples
 }
 
-
 /// Save the samples and labels to a CSV file.
-pub fn save_embedding_with_f64(
-    samples: Vec<(HashMap<String, usize>, HashMap<String, f64>)>, 
-    labels: Vec<usize>, 
-    csv_path: PathBuf
-) {
-    assert!(!samples.is_empty(), "Samples cannot be empty for CSV header extraction.");
-
-    let csv_error_message = format!("Cannot create csv file: {:?}, no such file.", csv_path);
-    let mut csv_writer = Writer::from_path(&csv_path).unwrap_or_else(
-        |_| panic!("{}", csv_error_message)
-    );
-
-    // Assuming all HashMaps have the same set of keys
-    let mut usize_headers: Vec<String> = samples[0].0.keys().cloned().collect();
-    usize_headers.sort();
-    let mut f64_headers: Vec<String> = samples[0].1.keys().cloned().collect();
-    f64_headers.sort();
-    
-    let mut all_headers = usize_headers.clone();
-    all_headers.extend(f64_headers.iter().cloned());
-    all_headers.push("label".to_string());
-
-    csv_writer.write_record(&all_headers).unwrap();
-
-    // Save samples and labels to CSV
-    for ((usize_sample, f64_sample), &label) in samples.iter().zip(&labels) {
-        // Check if all headers are present in the current sample
-        if usize_headers.iter().any(|h| !usize_sample.contains_key(h)) || f64_headers.iter().any(|h| !f64_sample.contains_key(h)) {
-            panic!("Headers mismatch between samples!");
-        }
-
-        let mut row: Vec<String> = usize_headers.iter()
-            .map(|header| usize_sample.get(header).unwrap().to_string()) // unwrap is safe here since we've checked keys
-            .chain(f64_headers.iter().map(|header| f64_sample.get(header).unwrap().to_string())) // similarly safe unwrap
-            .collect();
-
-        row.push(label.to_string());
-
-        csv_writer.write_record(&row).unwrap();
-    }
-
-    csv_writer.flush().unwrap();
+/// NOTE: Without sample, only the header is saved.
+pub fn save_embedding(samples: &Samples, csv_path: PathBuf) {
+    write_embedding(samples, &mut CsvSink::create(csv_path));
 }
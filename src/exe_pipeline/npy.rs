@@ -0,0 +1,690 @@
+use std::{path::{Path, PathBuf}, fs::File, io::{Read, Write, Seek, SeekFrom, BufReader, BufWriter}, collections::BTreeMap};
+use miniz_oxide::deflate::core::{CompressorOxide, TDEFLFlush, TDEFLStatus, compress_to_output, create_comp_flags_from_zip_params};
+use serde_derive::Serialize;
+
+use crate::graph_structs::annotations::AnnotationSet;
+use crate::params::argv::OutputFormat;
+use crate::graph_embedding::samples::{SampleValue, Columns, ColumnType};
+use crate::utils::ErrorKind;
+use super::sink::SamplesSink;
+
+/// Element type of a NPY array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpyDtype {
+    U8,
+    U16,
+    U32,
+    U64,
+    F64,
+    /// NOTE: Only for the arrays of the graph tensors (ex: edge_index)
+    I64,
+    Bool,
+}
+
+impl NpyDtype {
+    /// smallest unsigned integer type holding the given value
+    pub fn for_max_int(max_value: u64) -> NpyDtype {
+        if max_value <= u8::MAX as u64 {
+            NpyDtype::U8
+        } else if max_value <= u16::MAX as u64 {
+            NpyDtype::U16
+        } else if max_value <= u32::MAX as u64 {
+            NpyDtype::U32
+        } else {
+            NpyDtype::U64
+        }
+    }
+
+    /// type of the NPY header (little endian)
+    pub fn descr(&self) -> &'static str {
+        match self {
+            NpyDtype::U8 => "|u1",
+            NpyDtype::U16 => "<u2",
+            NpyDtype::U32 => "<u4",
+            NpyDtype::U64 => "<u8",
+            NpyDtype::F64 => "<f8",
+            NpyDtype::I64 => "<i8",
+            NpyDtype::Bool => "|b1",
+        }
+    }
+
+    /// append the little endian bytes of the value
+    /// :is_float: if true, the value holds the bits of a f64
+    fn push_bytes(&self, value: u64, is_float: bool, bytes: &mut Vec<u8>) {
+        match self {
+            NpyDtype::U8 => bytes.push(value as u8),
+            NpyDtype::U16 => bytes.extend_from_slice(&(value as u16).to_le_bytes()),
+            NpyDtype::U32 => bytes.extend_from_slice(&(value as u32).to_le_bytes()),
+            NpyDtype::U64 => bytes.extend_from_slice(&value.to_le_bytes()),
+            NpyDtype::F64 => match is_float {
+                true => bytes.extend_from_slice(&value.to_le_bytes()),
+                false => bytes.extend_from_slice(&(value as f64).to_le_bytes()),
+            },
+            NpyDtype::I64 => bytes.extend_from_slice(&(value as i64).to_le_bytes()),
+            NpyDtype::Bool => bytes.push((value != 0) as u8),
+        }
+    }
+}
+
+/// NPY file (version 1.0) of an array in C order.
+/// :data: little endian bytes of the elements
+pub fn npy_bytes(dtype: NpyDtype, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    npy_bytes_with_descr(dtype.descr(), shape, data)
+}
+
+/// NPY file of an array of values, in C order.
+/// :is_float: if true, the values hold the bits of f64 (see NpyDtype::push_bytes)
+pub fn npy_values_bytes(dtype: NpyDtype, shape: &[usize], values: &[u64], is_float: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(values.len() * 8);
+    for value in values.iter() {
+        dtype.push_bytes(*value, is_float, &mut data);
+    }
+    npy_bytes(dtype, shape, &data)
+}
+
+/// NPY file of a 1D array of strings (numpy unicode type, ex: "<U12")
+/// NOTE: Can be loaded without allow_pickle.
+pub fn npy_str_bytes(values: &[String]) -> Vec<u8> {
+    let max_len = values.iter().map(|value| value.chars().count()).max().unwrap_or(0).max(1);
+    let mut data = Vec::with_capacity(values.len() * max_len * 4);
+    for value in values.iter() {
+        let mut nb_chars = 0;
+        for c in value.chars() {
+            data.extend_from_slice(&(c as u32).to_le_bytes());
+            nb_chars += 1;
+        }
+        data.extend(std::iter::repeat_n(0u8, (max_len - nb_chars) * 4));
+    }
+    npy_bytes_with_descr(&format!("<U{}", max_len), &[values.len()], &data)
+}
+
+fn npy_bytes_with_descr(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let mut bytes = npy_header_bytes(descr, shape, 0);
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// header of a NPY file, from its magic string to its newline.
+/// The header is padded with spaces, so that the data is aligned on 64 bytes,
+/// and it is at least min_byte_size long.
+fn npy_header_bytes(descr: &str, shape: &[usize], min_byte_size: usize) -> Vec<u8> {
+    let shape_str = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.iter().map(|dim| dim.to_string()).collect::<Vec<String>>().join(", ")),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape_str
+    );
+    // magic string, version and header length (10 bytes), then the header, ended by a newline
+    let byte_size = (10 + header.len() + 1).div_ceil(64).max(min_byte_size.div_ceil(64)) * 64;
+    header.push_str(&" ".repeat(byte_size - (10 + header.len() + 1)));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(byte_size);
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes
+}
+
+/// byte size of the header of a NPY file written row by row
+/// NOTE: Enough for any shape of 2 dimensions, so that the header is rewritten in place (as numpy does).
+const NPY_STREAMED_HEADER_BYTE_SIZE: usize = 128;
+
+/// NPY file of an array, written row by row.
+/// The number of rows is written in the header when finished.
+struct NpyRowWriter {
+    writer: BufWriter<File>,
+    dtype: NpyDtype,
+    /// shape of a row (empty for a 1D array)
+    row_shape: Vec<usize>,
+    nb_rows: usize,
+}
+
+impl NpyRowWriter {
+    fn create(file_path: &Path, dtype: NpyDtype, row_shape: Vec<usize>) -> NpyRowWriter {
+        let file = File::create(file_path).unwrap_or_else(
+            |_| panic!("Cannot create npy file: {:?}, no such file.", file_path)
+        );
+        let mut npy_row_writer = NpyRowWriter {
+            writer: BufWriter::new(file),
+            dtype,
+            row_shape,
+            nb_rows: 0,
+        };
+        let header = npy_row_writer.header_bytes();
+        npy_row_writer.writer.write_all(&header).unwrap();
+        npy_row_writer
+    }
+
+    fn header_bytes(&self) -> Vec<u8> {
+        let mut shape = vec![self.nb_rows];
+        shape.extend_from_slice(&self.row_shape);
+        let header = npy_header_bytes(self.dtype.descr(), &shape, NPY_STREAMED_HEADER_BYTE_SIZE);
+        assert_eq!(header.len(), NPY_STREAMED_HEADER_BYTE_SIZE, "NPY header too large to be rewritten in place.");
+        header
+    }
+
+    /// :row: little endian bytes of the elements of the row
+    fn write_row(&mut self, row: &[u8]) {
+        self.writer.write_all(row).unwrap();
+        self.nb_rows += 1;
+    }
+
+    /// rewrite the header with the number of rows, and close the file
+    fn finish(mut self) -> std::io::Result<()> {
+        let header = self.header_bytes();
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&header)?;
+        self.writer.flush()
+    }
+}
+
+/// CRC-32 (ISO-HDLC, as in ZIP files), of data given by parts
+struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        let mut table = [0u32; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut crc = i as u32;
+            for _ in 0..8 {
+                crc = match crc & 1 {
+                    1 => 0xEDB8_8320 ^ (crc >> 1),
+                    _ => crc >> 1,
+                };
+            }
+            *entry = crc;
+        }
+        Crc32 { table, crc: 0xFFFF_FFFF }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.crc = self.table[((self.crc ^ *byte as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.crc
+    }
+}
+
+/// byte size of the parts of a NPY file compressed in a NPZ archive
+const NPZ_READ_BUFFER_BYTE_SIZE: usize = 1 << 20;
+
+/// Compress the data with deflate (level 6, as numpy.savez_compressed), part by part.
+/// Return the CRC-32, the size and the compressed size of the data.
+fn deflate_by_parts(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<(u32, u64, u64)> {
+    let mut compressor = CompressorOxide::new(create_comp_flags_from_zip_params(6, 0, 0));
+    let mut crc = Crc32::new();
+    let (mut size, mut compressed_size) = (0u64, 0u64);
+    let mut buffer = vec![0u8; NPZ_READ_BUFFER_BYTE_SIZE];
+    loop {
+        let nb_read_bytes = reader.read(&mut buffer)?;
+        let flush = match nb_read_bytes {
+            0 => TDEFLFlush::Finish,
+            _ => TDEFLFlush::None,
+        };
+        crc.update(&buffer[..nb_read_bytes]);
+        size += nb_read_bytes as u64;
+
+        let mut input = &buffer[..nb_read_bytes];
+        loop {
+            let mut write_error = None;
+            let (status, nb_compressed_bytes) = compress_to_output(&mut compressor, input, flush, |output| {
+                compressed_size += output.len() as u64;
+                match writer.write_all(output) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        write_error = Some(err);
+                        false
+                    },
+                }
+            });
+            if let Some(err) = write_error {
+                return Err(err);
+            }
+            input = &input[nb_compressed_bytes..];
+            match status {
+                TDEFLStatus::Done => return Ok((crc.finish(), size, compressed_size)),
+                TDEFLStatus::Okay if input.is_empty() && flush == TDEFLFlush::None => break,
+                TDEFLStatus::Okay => {},
+                _ => panic!("🚩 Deflate compression failed: {:?}", status),
+            }
+        }
+    }
+}
+
+/// ZIP local file header (or central directory header, without its extra fields), of a deflated file
+/// NOTE: The date is 1980-01-01, at midnight.
+fn zip_file_header(signature: u32, file_name: &str, crc: u32, compressed_size: u32, size: u32) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&signature.to_le_bytes());
+    if signature == ZIP_CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+        header.extend_from_slice(&20u16.to_le_bytes()); // version made by
+    }
+    header.extend_from_slice(&20u16.to_le_bytes()); // version needed
+    header.extend_from_slice(&0u16.to_le_bytes()); // flags
+    header.extend_from_slice(&8u16.to_le_bytes()); // deflate
+    header.extend_from_slice(&0u16.to_le_bytes()); // DOS time
+    header.extend_from_slice(&0x21u16.to_le_bytes()); // DOS date
+    header.extend_from_slice(&crc.to_le_bytes());
+    header.extend_from_slice(&compressed_size.to_le_bytes());
+    header.extend_from_slice(&size.to_le_bytes()); // uncompressed size
+    header.extend_from_slice(&(file_name.len() as u16).to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+    header
+}
+
+const ZIP_LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+/// offset of the CRC-32 in a local file header, followed by the compressed size and the size
+const ZIP_LOCAL_FILE_HEADER_CRC_OFFSET: u64 = 14;
+
+/// Save the NPY files in a NPZ archive (ZIP with deflate compression, as numpy.savez_compressed).
+/// The NPY files are read and compressed part by part, so that they are never fully in memory.
+/// :npy_files: the name of each array (without ".npy"), and its NPY file
+/// NOTE: There is no ZIP64 support, so each array and the whole archive must be less than 4 GiB.
+/// A larger archive is an error, and its file is removed.
+pub fn save_npz<R: Read>(npz_path: &Path, npy_files: Vec<(&str, R)>) -> Result<(), ErrorKind> {
+    let npz_file = File::create(npz_path).unwrap_or_else(
+        |_| panic!("Cannot create npz file: {:?}, no such file.", npz_path)
+    );
+    let result = write_npz(npz_path, &mut BufWriter::new(npz_file), npy_files);
+    if result.is_err() {
+        let _ = std::fs::remove_file(npz_path);
+    }
+    result
+}
+
+fn write_npz<R: Read>(npz_path: &Path, writer: &mut BufWriter<File>, npy_files: Vec<(&str, R)>) -> Result<(), ErrorKind> {
+    let to_u32 = |size: u64| -> Result<u32, ErrorKind> {
+        u32::try_from(size).map_err(|_| ErrorKind::NpzArchiveTooLarge(npz_path.to_path_buf()))
+    };
+
+    let nb_npy_files = npy_files.len();
+    let mut central_directory: Vec<u8> = Vec::new();
+    for (array_name, mut npy_file) in npy_files {
+        let file_name = format!("{}.npy", array_name);
+        let local_header_offset = to_u32(writer.stream_position().map_err(ErrorKind::Io)?)?;
+
+        // local file header, its CRC and sizes are known after the compression
+        let mut local_header = zip_file_header(ZIP_LOCAL_FILE_HEADER_SIGNATURE, &file_name, 0, 0, 0);
+        local_header.extend_from_slice(file_name.as_bytes());
+        writer.write_all(&local_header).map_err(ErrorKind::Io)?;
+        let (crc, size, compressed_size) = deflate_by_parts(&mut npy_file, writer).map_err(ErrorKind::Io)?;
+        let (size, compressed_size) = (to_u32(size)?, to_u32(compressed_size)?);
+        let end_offset = writer.stream_position().map_err(ErrorKind::Io)?;
+        let mut crc_and_sizes = Vec::new();
+        for value in [crc, compressed_size, size] {
+            crc_and_sizes.extend_from_slice(&value.to_le_bytes());
+        }
+        writer.seek(SeekFrom::Start(local_header_offset as u64 + ZIP_LOCAL_FILE_HEADER_CRC_OFFSET)).map_err(ErrorKind::Io)?;
+        writer.write_all(&crc_and_sizes).map_err(ErrorKind::Io)?;
+        writer.seek(SeekFrom::Start(end_offset)).map_err(ErrorKind::Io)?;
+
+        // central directory header
+        central_directory.extend(zip_file_header(ZIP_CENTRAL_DIRECTORY_HEADER_SIGNATURE, &file_name, crc, compressed_size, size));
+        central_directory.extend_from_slice(&[0u8; 10]); // comment, disk, attributes
+        central_directory.extend_from_slice(&local_header_offset.to_le_bytes());
+        central_directory.extend_from_slice(file_name.as_bytes());
+    }
+
+    // end of central directory
+    let central_directory_offset = to_u32(writer.stream_position().map_err(ErrorKind::Io)?)?;
+    let central_directory_size = to_u32(central_directory.len() as u64)?;
+    to_u32(central_directory_offset as u64 + central_directory_size as u64)?;
+    let mut end_of_central_directory = Vec::new();
+    end_of_central_directory.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    end_of_central_directory.extend_from_slice(&[0u8; 4]); // disk numbers
+    end_of_central_directory.extend_from_slice(&(nb_npy_files as u16).to_le_bytes());
+    end_of_central_directory.extend_from_slice(&(nb_npy_files as u16).to_le_bytes());
+    end_of_central_directory.extend_from_slice(&central_directory_size.to_le_bytes());
+    end_of_central_directory.extend_from_slice(&central_directory_offset.to_le_bytes());
+    end_of_central_directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+    writer.write_all(&central_directory).map_err(ErrorKind::Io)?;
+    writer.write_all(&end_of_central_directory).map_err(ErrorKind::Io)?;
+    writer.flush().map_err(ErrorKind::Io)
+}
+
+/// JSON sidecar of a NPY or NPZ file, describing its arrays
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NpySidecar {
+    pub output_format: OutputFormat,
+    /// names of the columns of the samples, in order
+    /// NOTE: With the NPY format, the label is the last column
+    pub columns: Vec<String>,
+    pub nb_samples: usize,
+    /// NPY type of the samples (ex: "<u8")
+    pub dtype: String,
+    /// NPY type of the labels
+    pub label_dtype: String,
+    /// bit of each annotation in a label (0: no annotation)
+    pub label_encoding: BTreeMap<String, u8>,
+}
+
+/// Save the samples and labels to a NPY file (with a last "label" column),
+/// or to a NPZ archive (with a "samples" and a "labels" array).
+/// The samples are written row by row while they are generated, with the type of the columns:
+/// u64 if all the columns are integers, f64 if there is a float column.
+/// NOTE: With the NPZ format, the arrays are written to temporary NPY files, then compressed in the archive.
+pub struct NpySink {
+    file_path: PathBuf,
+    output_format: OutputFormat,
+    columns: Columns,
+    /// type of the samples (see NpySink::get_dtype)
+    dtype: NpyDtype,
+    label_dtype: NpyDtype,
+    /// NOTE: Created with the columns.
+    samples_writer: Option<NpyRowWriter>,
+    /// NOTE: Only for the NPZ format (with the NPY format, the label is in the samples).
+    labels_writer: Option<NpyRowWriter>,
+    /// bytes of the current row
+    row: Vec<u8>,
+}
+
+impl NpySink {
+    pub fn create(file_path: PathBuf, output_format: OutputFormat) -> NpySink {
+        assert!(output_format != OutputFormat::Csv, "NpySink is only for the NPY formats.");
+        // the largest label has all the bits of the label encoding
+        let max_label = AnnotationSet::label_encoding().into_iter().map(|(_, bit)| 1u64 << bit).sum();
+        NpySink {
+            file_path,
+            output_format,
+            columns: Columns::new(),
+            dtype: NpyDtype::U64,
+            label_dtype: NpyDtype::for_max_int(max_label),
+            samples_writer: None,
+            labels_writer: None,
+            row: Vec::new(),
+        }
+    }
+
+    /// type of the samples: f64 if there is a float column, u64 otherwise
+    fn get_dtype(columns: &Columns) -> NpyDtype {
+        let mut dtype = NpyDtype::U64;
+        for column_type in columns.types() {
+            match column_type {
+                ColumnType::Int => {},
+                ColumnType::Float => dtype = NpyDtype::F64,
+                ColumnType::Text => panic!("🚩 Text samples can't be saved as NPY, use the CSV output format"),
+            }
+        }
+        dtype
+    }
+
+    /// temporary NPY file of an array of the NPZ archive
+    fn get_npz_array_file_path(&self, array_name: &str) -> PathBuf {
+        let mut file_name = self.file_path.file_name().unwrap().to_os_string();
+        file_name.push(format!(".{}.npy.tmp", array_name));
+        self.file_path.with_file_name(file_name)
+    }
+
+    /// Save the samples and labels, and the JSON sidecar of their columns.
+    /// NOTE: A NPZ archive of more than 4 GiB is an error (see save_npz).
+    pub fn save(mut self, sidecar_path: &Path) -> Result<NpySidecar, ErrorKind> {
+        let samples_writer = self.samples_writer.take().expect("NpySink::save called before write_columns.");
+        let nb_samples = samples_writer.nb_rows;
+        samples_writer.finish().map_err(ErrorKind::Io)?;
+
+        let (columns, label_dtype) = match self.output_format {
+            OutputFormat::Npy => {
+                let mut columns = self.columns.names().to_vec();
+                columns.push("label".to_string());
+                (columns, self.dtype)
+            },
+            _ => {
+                let npy_file_paths = ["samples", "labels"].map(
+                    |array_name| (array_name, self.get_npz_array_file_path(array_name))
+                );
+                self.labels_writer.take().unwrap().finish().map_err(ErrorKind::Io)?;
+                let mut npy_files = Vec::new();
+                for (array_name, npy_file_path) in npy_file_paths.iter() {
+                    npy_files.push((*array_name, BufReader::new(File::open(npy_file_path).map_err(ErrorKind::Io)?)));
+                }
+                let result = save_npz(&self.file_path, npy_files);
+                for (_, npy_file_path) in npy_file_paths.iter() {
+                    std::fs::remove_file(npy_file_path).map_err(ErrorKind::Io)?;
+                }
+                result?;
+                (self.columns.names().to_vec(), self.label_dtype)
+            },
+        };
+
+        let sidecar = NpySidecar {
+            output_format: self.output_format,
+            columns,
+            nb_samples,
+            dtype: self.dtype.descr().to_string(),
+            label_dtype: label_dtype.descr().to_string(),
+            label_encoding: AnnotationSet::label_encoding().into_iter()
+                .map(|(name, bit)| (name.to_string(), bit))
+                .collect(),
+        };
+        let sidecar_file = File::create(sidecar_path).unwrap_or_else(
+            |_| panic!("Cannot create sidecar file: {:?}, no such file.", sidecar_path)
+        );
+        serde_json::to_writer_pretty(sidecar_file, &sidecar).unwrap();
+        Ok(sidecar)
+    }
+}
+
+impl SamplesSink for NpySink {
+    fn write_columns(&mut self, columns: &Columns) {
+        self.columns = columns.clone();
+        self.dtype = NpySink::get_dtype(columns);
+        match self.output_format {
+            OutputFormat::Npy => {
+                // the label is the last column
+                self.samples_writer = Some(NpyRowWriter::create(&self.file_path, self.dtype, vec![columns.len() + 1]));
+            },
+            _ => {
+                self.samples_writer = Some(NpyRowWriter::create(
+                    &self.get_npz_array_file_path("samples"), self.dtype, vec![columns.len()]
+                ));
+                self.labels_writer = Some(NpyRowWriter::create(
+                    &self.get_npz_array_file_path("labels"), self.label_dtype, Vec::new()
+                ));
+            },
+        }
+    }
+
+    fn write_sample(&mut self, values: &[SampleValue], label: usize) {
+        if values.len() != self.columns.len() {
+            panic!("Headers mismatch between samples!");
+        }
+        self.row.clear();
+        for (value, column_type) in values.iter().zip(self.columns.types().iter()) {
+            match (value, column_type) {
+                (SampleValue::Int(value), ColumnType::Int) => self.dtype.push_bytes(*value as u64, false, &mut self.row),
+                (SampleValue::Float(value), ColumnType::Float) => self.dtype.push_bytes(value.to_bits(), true, &mut self.row),
+                _ => panic!("Column types mismatch between samples!"),
+            }
+        }
+        match self.labels_writer.as_mut() {
+            Some(labels_writer) => {
+                let mut label_bytes = Vec::new();
+                self.label_dtype.push_bytes(label as u64, false, &mut label_bytes);
+                labels_writer.write_row(&label_bytes);
+            },
+            None => self.dtype.push_bytes(label as u64, false, &mut self.row),
+        }
+        self.samples_writer.as_mut().expect("NpySink::write_sample called before write_columns.").write_row(&self.row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_bytes() {
+        let npy_file = npy_bytes(NpyDtype::U16, &[1, 2], &[1, 0, 2, 0]);
+        assert_eq!(&npy_file[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([npy_file[8], npy_file[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&npy_file[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<u2', 'fortran_order': False, 'shape': (1, 2), }"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(&npy_file[10 + header_len..], &[1, 0, 2, 0]);
+
+        let npy_file = npy_bytes(NpyDtype::U8, &[3], &[0, 1, 0]);
+        assert!(String::from_utf8_lossy(&npy_file).contains("'shape': (3,), "));
+    }
+
+    #[test]
+    fn test_npy_dtype() {
+        assert_eq!(NpyDtype::for_max_int(255), NpyDtype::U8);
+        assert_eq!(NpyDtype::for_max_int(256), NpyDtype::U16);
+        assert_eq!(NpyDtype::for_max_int(0x55a6d2b6c000), NpyDtype::U64);
+
+        let mut bytes = Vec::new();
+        NpyDtype::F64.push_bytes(3, false, &mut bytes);
+        NpyDtype::F64.push_bytes(0.5f64.to_bits(), true, &mut bytes);
+        assert_eq!(bytes[..8], 3.0f64.to_le_bytes());
+        assert_eq!(bytes[8..], 0.5f64.to_le_bytes());
+
+        let npy_file = npy_values_bytes(NpyDtype::I64, &[2], &[1, 2], false);
+        assert!(String::from_utf8_lossy(&npy_file).contains("'descr': '<i8'"));
+        assert_eq!(npy_file[npy_file.len() - 16..npy_file.len() - 8], 1i64.to_le_bytes());
+        let npy_file = npy_values_bytes(NpyDtype::Bool, &[3], &[1, 0, 2], false);
+        assert_eq!(npy_file[npy_file.len() - 3..], [1, 0, 1]);
+    }
+
+    #[test]
+    fn test_npy_str_bytes() {
+        let npy_file = npy_str_bytes(&["ab".to_string(), "c".to_string()]);
+        assert!(String::from_utf8_lossy(&npy_file).contains("'descr': '<U2', 'fortran_order': False, 'shape': (2,), "));
+        let data = &npy_file[npy_file.len() - 16..];
+        assert_eq!(data, &[b'a', 0, 0, 0, b'b', 0, 0, 0, b'c', 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(Crc32::new().finish(), 0);
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_deflate_by_parts() {
+        // several parts, compressible or not
+        let data: Vec<u8> = (0..3 * NPZ_READ_BUFFER_BYTE_SIZE + 5)
+            .map(|i| if i < NPZ_READ_BUFFER_BYTE_SIZE { (i * i % 251) as u8 } else { (i / 64) as u8 })
+            .collect();
+        let mut compressed_data = Vec::new();
+        let (crc, size, compressed_size) = deflate_by_parts(&mut data.as_slice(), &mut compressed_data).unwrap();
+        let mut expected_crc = Crc32::new();
+        expected_crc.update(&data);
+        assert_eq!(crc, expected_crc.finish());
+        assert_eq!(size, data.len() as u64);
+        assert_eq!(compressed_size, compressed_data.len() as u64);
+        assert_eq!(miniz_oxide::inflate::decompress_to_vec(&compressed_data).unwrap(), data);
+    }
+
+    /// NPY files of a NPZ archive, by name (checking the CRC and sizes of their local file headers)
+    fn read_npz(npz_file: &[u8]) -> BTreeMap<String, Vec<u8>> {
+        let read_u32 = |offset: usize| u32::from_le_bytes(npz_file[offset..offset + 4].try_into().unwrap()) as usize;
+        let mut npy_files = BTreeMap::new();
+        let mut offset = 0;
+        while read_u32(offset) == ZIP_LOCAL_FILE_HEADER_SIGNATURE as usize {
+            let (crc, compressed_size, size) = (read_u32(offset + 14), read_u32(offset + 18), read_u32(offset + 22));
+            let file_name_len = u16::from_le_bytes([npz_file[offset + 26], npz_file[offset + 27]]) as usize;
+            let file_name = std::str::from_utf8(&npz_file[offset + 30..offset + 30 + file_name_len]).unwrap();
+            let data_offset = offset + 30 + file_name_len;
+            let npy_file = miniz_oxide::inflate::decompress_to_vec(&npz_file[data_offset..data_offset + compressed_size]).unwrap();
+            let mut npy_file_crc = Crc32::new();
+            npy_file_crc.update(&npy_file);
+            assert_eq!((npy_file.len(), npy_file_crc.finish() as usize), (size, crc));
+            npy_files.insert(file_name.to_string(), npy_file);
+            offset = data_offset + compressed_size;
+        }
+        assert_eq!(read_u32(offset), ZIP_CENTRAL_DIRECTORY_HEADER_SIGNATURE as usize);
+        assert_eq!(&npz_file[npz_file.len() - 22..npz_file.len() - 18], b"PK\x05\x06");
+        assert_eq!(read_u32(npz_file.len() - 6), offset);
+        npy_files
+    }
+
+    /// header and data of a NPY file
+    fn split_npy(npy_file: &[u8]) -> (&str, &[u8]) {
+        let data_offset = 10 + u16::from_le_bytes([npy_file[8], npy_file[9]]) as usize;
+        (std::str::from_utf8(&npy_file[10..data_offset]).unwrap(), &npy_file[data_offset..])
+    }
+
+    #[test]
+    fn test_npy_sink() {
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_npy_sink_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+
+        // integer columns, saved with the label
+        let mut npy_sink = NpySink::create(output_folder.join("samples.npy"), OutputFormat::Npy);
+        npy_sink.write_columns(&Columns::of_type(["a", "b"], ColumnType::Int));
+        npy_sink.write_sample(&[SampleValue::Int(1), SampleValue::Int(300)], 0);
+        npy_sink.write_sample(&[SampleValue::Int(2), SampleValue::Int(0)], 1);
+        let sidecar = npy_sink.save(&output_folder.join("samples.columns.json")).unwrap();
+        assert_eq!(sidecar.columns, vec!["a", "b", "label"]);
+        assert_eq!(sidecar.dtype, "<u8");
+        assert_eq!(sidecar.label_dtype, "<u8");
+        assert_eq!(sidecar.nb_samples, 2);
+        assert_eq!(sidecar.label_encoding.get("key"), Some(&1));
+        let npy_file = std::fs::read(output_folder.join("samples.npy")).unwrap();
+        let (header, data) = split_npy(&npy_file);
+        // the header is rewritten in place, with the number of samples
+        assert_eq!(10 + header.len(), NPY_STREAMED_HEADER_BYTE_SIZE);
+        assert!(header.starts_with("{'descr': '<u8', 'fortran_order': False, 'shape': (2, 3), }"));
+        let values: Vec<u64> = data.chunks(8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())).collect();
+        assert_eq!(values, vec![1, 300, 0, 2, 0, 1]);
+        let sidecar_json: serde_json::Value = serde_json::from_reader(
+            File::open(output_folder.join("samples.columns.json")).unwrap()
+        ).unwrap();
+        assert_eq!(sidecar_json["output_format"], "npy");
+
+        // an integer and a float column, saved in an archive
+        let mut columns = Columns::new();
+        columns.push("a", ColumnType::Int);
+        columns.push("b", ColumnType::Float);
+        let mut npy_sink = NpySink::create(output_folder.join("samples.npz"), OutputFormat::Npz);
+        npy_sink.write_columns(&columns);
+        npy_sink.write_sample(&[SampleValue::Int(1), SampleValue::Float(0.5)], 1);
+        npy_sink.write_sample(&[SampleValue::Int(3), SampleValue::Float(2.0)], 0);
+        let sidecar = npy_sink.save(&output_folder.join("samples.npz.columns.json")).unwrap();
+        assert_eq!(sidecar.columns, vec!["a", "b"]);
+        assert_eq!(sidecar.dtype, "<f8");
+        assert_eq!(sidecar.label_dtype, "|u1");
+        let npy_files = read_npz(&std::fs::read(output_folder.join("samples.npz")).unwrap());
+        assert_eq!(npy_files.keys().collect::<Vec<_>>(), vec!["labels.npy", "samples.npy"]);
+        let (header, data) = split_npy(&npy_files["samples.npy"]);
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 2), }"));
+        let values: Vec<f64> = data.chunks(8).map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap())).collect();
+        assert_eq!(values, vec![1.0, 0.5, 3.0, 2.0]);
+        let (header, data) = split_npy(&npy_files["labels.npy"]);
+        assert!(header.starts_with("{'descr': '|u1', 'fortran_order': False, 'shape': (2,), }"));
+        assert_eq!(data, &[1, 0]);
+        // the temporary NPY files of the archive are removed
+        let mut file_names: Vec<String> = std::fs::read_dir(&output_folder).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names, vec!["samples.columns.json", "samples.npy", "samples.npz", "samples.npz.columns.json"]);
+
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Column types mismatch between samples!")]
+    fn test_npy_sink_column_types_mismatch() {
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_npy_sink_mismatch_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+        let mut npy_sink = NpySink::create(output_folder.join("samples.npy"), OutputFormat::Npy);
+        npy_sink.write_columns(&Columns::of_type(["a"], ColumnType::Int));
+        std::fs::remove_dir_all(&output_folder).unwrap();
+        npy_sink.write_sample(&[SampleValue::Float(0.5)], 0);
+    }
+}
@@ -1,35 +1,119 @@
 use rayon::prelude::*;
 use std::{time::Instant, path::PathBuf};
 
-use crate::{graph_embedding::GraphEmbedding, params::{argv::{SelectAnnotationLocation, EntropyFilter, ChunkByteSizeFilter}, ARGV}, utils::truncate_path_to_last_n_dirs};
+use crate::{graph_embedding::{GraphEmbedding, builder::GraphEmbeddingBuilder}, params::{argv::{SelectAnnotationLocation, Pipeline, OutputFormat, GraphFormat}, config::RunConfig}, utils::{truncate_path_to_last_n_dirs, heap_dump_path_to_json_path}};
+use crate::graph_annotate::report::{AnnotationReportSummary, FileAnnotationOutcome, UnannotatedFile};
 use super::get_raw_file_or_files_from_path;
+use super::manifest::{RunManifest, RunManifestEntry, RunStatus, compute_files_prefix_hashes, get_tmp_file_path, rename_tmp_file};
+use super::aggregate::{ShardedDataset, ProvenanceSink, HeapDumpProvenance};
+use super::sink::SamplesSink;
+use super::npy::NpySink;
+use super::value_embedding::{
+    gen_and_save_value_node_embedding, gen_and_write_value_node_embedding,
+    gen_and_save_value_node_window_embedding, gen_and_write_value_node_window_embedding,
+};
+use super::graph_generation::gen_and_save_memory_graph;
+use super::graph_generation_with_embedding::gen_and_save_memory_graph_with_embedding_comments;
+use super::graph_tensors::gen_and_save_graph_tensors;
+use super::chunk_semantic_embedding::{gen_and_save_chunk_semantic_embedding, gen_and_write_chunk_semantic_embedding};
+use super::chunk_wl_subtree_embedding::{gen_and_save_chunk_wl_subtree_embedding, gen_and_write_chunk_wl_subtree_embedding};
+use super::chunk_centrality_embedding::{gen_and_save_chunk_centrality_embedding, gen_and_write_chunk_centrality_embedding};
+use super::random_walk_corpus::gen_and_save_random_walk_corpus;
+use super::chunk_random_walk_embedding::{gen_and_save_chunk_random_walk_embedding, gen_and_write_chunk_random_walk_embedding};
+use super::chunk_statistic_embedding::{gen_and_save_chunk_statistic_embedding, gen_and_write_chunk_statistic_embedding};
+use super::chunk_top_vn_semantic_embedding::{gen_and_save_chunk_top_vn_semantic_embedding, gen_and_write_chunk_top_vn_semantic_embedding};
+use super::chunk_byte_histogram_embedding::{gen_and_save_chunk_byte_histogram_embedding, gen_and_write_chunk_byte_histogram_embedding};
+use super::chunk_entropy_profile_embedding::{gen_and_save_chunk_entropy_profile_embedding, gen_and_write_chunk_entropy_profile_embedding};
+use super::chunk_layout_embedding::{gen_and_save_chunk_layout_embedding, gen_and_write_chunk_layout_embedding};
+use super::chunk_start_bytes_embedding::{gen_and_save_chunk_start_bytes_embedding, gen_and_write_chunk_start_bytes_embedding};
+use super::chunk_extract::{gen_and_save_chunk_extract, gen_and_write_chunk_extract};
 
-/// Wrapper for the embedding pipeline, with the CSV saving.
-pub fn embedding_pipeline_to_csv(
-    path: PathBuf, 
-    output_folder: PathBuf, 
-    annotation : SelectAnnotationLocation, 
-    entropy_filter : EntropyFilter,
-    chunk_byte_size_filter : ChunkByteSizeFilter,
-    no_value_node : bool,
-    gen_and_save_embedding: fn(PathBuf, &GraphEmbedding) -> usize,
-) {
-    embedding_pipeline(
-        path, 
-        output_folder, 
-        annotation, 
-        entropy_filter, 
-        chunk_byte_size_filter,
-        no_value_node,
-        gen_and_save_embedding,
-        ".csv"
-    );
+type GenAndSaveEmbedding = fn(PathBuf, &GraphEmbedding) -> usize;
+type GenAndWriteSamples = fn(&GraphEmbedding, &mut dyn SamplesSink) -> usize;
+
+/// Function doing the embedding of the given pipeline and saving it, with the extension of the saved file.
+pub fn get_gen_and_save_embedding(pipeline: Pipeline) -> (GenAndSaveEmbedding, &'static str) {
+    match pipeline {
+        Pipeline::ValueNodeEmbedding => (gen_and_save_value_node_embedding, ".csv"),
+        Pipeline::ValueNodeWindowEmbedding => (gen_and_save_value_node_window_embedding, ".csv"),
+        Pipeline::Graph => (gen_and_save_memory_graph, "dot.gv"),
+        Pipeline::GraphWithEmbeddingComments => (gen_and_save_memory_graph_with_embedding_comments, "dot.gv"),
+        Pipeline::GraphTensors => (gen_and_save_graph_tensors, ".npz"),
+        Pipeline::ChunkSemanticEmbedding => (gen_and_save_chunk_semantic_embedding, ".csv"),
+        Pipeline::ChunkWlSubtreeEmbedding => (gen_and_save_chunk_wl_subtree_embedding, ".csv"),
+        Pipeline::ChunkCentralityEmbedding => (gen_and_save_chunk_centrality_embedding, ".csv"),
+        Pipeline::RandomWalkCorpus => (gen_and_save_random_walk_corpus, "walks.txt"),
+        Pipeline::ChunkRandomWalkEmbedding => (gen_and_save_chunk_random_walk_embedding, ".csv"),
+        Pipeline::ChunkStatisticEmbedding => (gen_and_save_chunk_statistic_embedding, ".csv"),
+        Pipeline::ChunkTopVnSemanticEmbedding => (gen_and_save_chunk_top_vn_semantic_embedding, ".csv"),
+        Pipeline::ChunkByteHistogramEmbedding => (gen_and_save_chunk_byte_histogram_embedding, ".csv"),
+        Pipeline::ChunkEntropyProfileEmbedding => (gen_and_save_chunk_entropy_profile_embedding, ".csv"),
+        Pipeline::ChunkLayoutEmbedding => (gen_and_save_chunk_layout_embedding, ".csv"),
+        Pipeline::ChunkStartBytesEmbedding => (gen_and_save_chunk_start_bytes_embedding, ".csv"),
+        Pipeline::ChunkExtraction => (gen_and_save_chunk_extract, ".csv"),
+    }
+}
+
+/// Function doing the embedding of the given pipeline and writing its samples to a sink.
+/// NOTE: None for the pipelines that produce a graph or a random walk corpus instead of samples.
+pub fn get_gen_and_write_samples(pipeline: Pipeline) -> Option<GenAndWriteSamples> {
+    match pipeline {
+        Pipeline::ValueNodeEmbedding => Some(gen_and_write_value_node_embedding),
+        Pipeline::ValueNodeWindowEmbedding => Some(gen_and_write_value_node_window_embedding),
+        Pipeline::Graph | Pipeline::GraphWithEmbeddingComments | Pipeline::GraphTensors | Pipeline::RandomWalkCorpus => None,
+        Pipeline::ChunkSemanticEmbedding => Some(gen_and_write_chunk_semantic_embedding),
+        Pipeline::ChunkWlSubtreeEmbedding => Some(gen_and_write_chunk_wl_subtree_embedding),
+        Pipeline::ChunkCentralityEmbedding => Some(gen_and_write_chunk_centrality_embedding),
+        Pipeline::ChunkRandomWalkEmbedding => Some(gen_and_write_chunk_random_walk_embedding),
+        Pipeline::ChunkStatisticEmbedding => Some(gen_and_write_chunk_statistic_embedding),
+        Pipeline::ChunkTopVnSemanticEmbedding => Some(gen_and_write_chunk_top_vn_semantic_embedding),
+        Pipeline::ChunkByteHistogramEmbedding => Some(gen_and_write_chunk_byte_histogram_embedding),
+        Pipeline::ChunkEntropyProfileEmbedding => Some(gen_and_write_chunk_entropy_profile_embedding),
+        Pipeline::ChunkLayoutEmbedding => Some(gen_and_write_chunk_layout_embedding),
+        Pipeline::ChunkStartBytesEmbedding => Some(gen_and_write_chunk_start_bytes_embedding),
+        Pipeline::ChunkExtraction => Some(gen_and_write_chunk_extract),
+    }
+}
+
+/// Extension of the file saved for each heap dump, by the given run configuration.
+fn get_output_file_extension(run_config: &RunConfig) -> &'static str {
+    if matches!(run_config.pipeline, Pipeline::GraphTensors | Pipeline::RandomWalkCorpus) {
+        return get_gen_and_save_embedding(run_config.pipeline).1;
+    }
+    if get_gen_and_write_samples(run_config.pipeline).is_none() {
+        return match run_config.graph_format {
+            GraphFormat::Dot => get_gen_and_save_embedding(run_config.pipeline).1,
+            GraphFormat::Graphml => ".graphml",
+            GraphFormat::Gexf => ".gexf",
+        };
+    }
+    match run_config.output_format {
+        OutputFormat::Csv => get_gen_and_save_embedding(run_config.pipeline).1,
+        OutputFormat::Npy => ".npy",
+        OutputFormat::Npz => ".npz",
+    }
+}
+
+/// Group the run configurations (by index) that share the same graph,
+/// so that the graph is built only once per file for each group.
+fn group_by_graph(graph_embedding_builders: &[GraphEmbeddingBuilder]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, graph_embedding_builder) in graph_embedding_builders.iter().enumerate() {
+        let group = groups.iter_mut().find(
+            |group| graph_embedding_builders[group[0]].has_same_graph_as(graph_embedding_builder)
+        );
+        match group {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    groups
 }
 
 /// Generic pipeline function for embedding generation.
 /// 
 /// > Prepare the data:
-///     Takes a directory or a file
+///     Takes the directories or files of all the inputs
 ///     If directory then list all files in that directory 
 ///     and its subdirectories that are of type "-heap.raw",
 ///     with their corresponding ".json" files.
@@ -42,35 +126,99 @@ pub fn embedding_pipeline_to_csv(
 ///     NOTE: The saving of the embedding is done by the provided
 ///     function.
 /// 
-/// :gen_and_save_embedding: This function is responsible for 
-/// doing the embedding on a given file, and saving it.
-/// + It takes as input the output folder and the graph embedding.
-/// + It returns the number of samples generated.
+/// :run_configs: The pipeline variants to run (see RunConfig::resolve_variants).
+/// The embedding of each variant is done and saved by the function
+/// of its pipeline (see get_gen_and_save_embedding).
+/// 
+/// NOTE: The graph of each file is built once for all the variants that
+/// share the same graph (they can differ by pipeline, annotation location and filters).
+/// 
+/// NOTE: The graph embedding of each variant is built with the parameters
+/// of its run configuration, which is saved next to each output.
+/// When there are several variants, each one is saved in its own subfolder.
+/// 
+/// NOTE: Each output, and the files saved next to it, are written to temporary files,
+/// renamed once complete, and recorded in the run manifest of its output folder (see RunManifest).
+/// An output is skipped only if the run manifest records it as done,
+/// from the same input files and with the same parameters.
+/// :force: if true, the run manifest is ignored and all the outputs are redone.
+/// 
+/// NOTE: With the aggregate option, the samples of all the files are written,
+/// as they are produced (in batches), in a single dataset split in shards, with provenance columns
+/// (see ShardedDataset). Such a dataset is always fully redone (no run manifest).
+/// NOTE: All the inputs are given at once, so that their files go in the same dataset.
+/// 
+/// NOTE: With the NPY output formats, the columns of the samples and the label encoding
+/// are saved in a JSON sidecar next to each output (see NpySink).
+/// 
+/// NOTE: When the graph is annotated, the annotation report of each file
+/// is saved next to its output, and a summary of all the reports
+/// is saved in the output folder, with the skipped and failed files.
 pub fn embedding_pipeline(
-    path: PathBuf, 
+    paths: Vec<PathBuf>, 
     output_folder: PathBuf, 
-    annotation : SelectAnnotationLocation, 
-    entropy_filter : EntropyFilter,
-    chunk_byte_size_filter : ChunkByteSizeFilter,
-    no_value_node : bool,
-    gen_and_save_embedding: fn(PathBuf, &GraphEmbedding) -> usize,
-    save_file_extension: &str,
+    run_configs: &[RunConfig],
+    force: bool,
 ) {
     // start timer
     let start_time = Instant::now();
 
     // |> Prepare the data:
     // --> Step 1: Getting the files
-    let heap_dump_raw_file_paths: Vec<PathBuf> = get_raw_file_or_files_from_path(path.clone());
+    let mut heap_dump_raw_file_paths: Vec<PathBuf> = Vec::new();
+    for path in paths.iter() {
+        let raw_file_paths = get_raw_file_or_files_from_path(path.clone());
+        if raw_file_paths.is_empty() {
+            panic!(
+                "The file doesn't exist or the directory doesn't contain any .raw file: {}", 
+                path.to_str().unwrap()
+            );
+        }
+        heap_dump_raw_file_paths.extend(raw_file_paths);
+    }
     let nb_files = heap_dump_raw_file_paths.len();
-    if nb_files == 0 {
-        panic!(
-            "The file doesn't exist or the directory doesn't contain any .raw file: {}", 
-            path.to_str().unwrap()
-        );
-    } 
 
-    let csv_pipeline_prefix = format!("{:?}", ARGV.pipeline);
+    // --> Step 2: Preparing the variants
+    let variant_output_folders: Vec<PathBuf> = run_configs.iter()
+        .map(|run_config| run_config.variant_output_folder(&output_folder))
+        .collect();
+    for variant_output_folder in variant_output_folders.iter() {
+        std::fs::create_dir_all(variant_output_folder).unwrap_or_else(
+            |_| panic!("Cannot create output folder: {:?}", variant_output_folder)
+        );
+    }
+    let graph_embedding_builders: Vec<GraphEmbeddingBuilder> = run_configs.iter()
+        .map(|run_config| run_config.graph_embedding_builder())
+        .collect();
+    let graph_groups = group_by_graph(&graph_embedding_builders);
+    // NOTE: the names of all the inputs, so that the dataset and the summary cover all of them
+    let input_name = paths.iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<&str>>()
+        .join("_");
+    let run_manifests: Vec<Option<RunManifest>> = run_configs.iter()
+        .zip(variant_output_folders.iter())
+        .map(|(run_config, variant_output_folder)| match run_config.aggregate {
+            true => None,
+            false => Some(RunManifest::open(variant_output_folder, force)),
+        })
+        .collect();
+    let datasets: Vec<Option<ShardedDataset>> = run_configs.iter()
+        .zip(variant_output_folders.iter())
+        .map(|(run_config, variant_output_folder)| match run_config.aggregate {
+            true => Some(ShardedDataset::create(
+                variant_output_folder.clone(),
+                format!("{:?}_{}_dataset", run_config.pipeline, input_name),
+                run_config.aggregate_shard_nb_samples,
+            )),
+            false => None,
+        })
+        .collect();
+    let parameters: Vec<serde_json::Value> = run_configs.iter()
+        .map(|run_config| run_config.parameters())
+        .collect();
+    let is_any_annotated = run_configs.iter()
+        .any(|run_config| run_config.annotation != SelectAnnotationLocation::None);
 
     // |> File per file processing:
     // Create a thread pool with named threads
@@ -80,11 +228,12 @@ pub fn embedding_pipeline(
         .unwrap();
 
     // generate samples and labels, file per file (parallelized)
-    let _ : Vec<_> = pool.install(|| {
+    // NOTE: the annotation outcomes are associated to the index of their variant
+    let annotation_outcomes : Vec<(usize, FileAnnotationOutcome)> = pool.install(|| {
         heap_dump_raw_file_paths
             .par_iter()
             .enumerate()
-            .map(|(i, heap_dump_raw_file_path)| 
+            .flat_map_iter(|(i, heap_dump_raw_file_path)| 
         {
             let current_thread = std::thread::current();
             let thread_name = current_thread.name().unwrap_or("<unnamed>");
@@ -93,87 +242,316 @@ pub fn embedding_pipeline(
             let dir_path_ = heap_dump_raw_file_path.clone();
             let dir_path_end = truncate_path_to_last_n_dirs(&dir_path_, 5);
             let dir_path_end_str = dir_path_end.to_str().unwrap();
-
-            // check if CSV file already exists, in that case skip
             let file_name = heap_dump_raw_file_path.file_name().unwrap().to_str().unwrap();
-            let csv_file_name = format!(
-                "{}_{}_{}_{}", 
-                csv_pipeline_prefix, 
-                dir_path_end_str.replace("/", "_"),
-                file_name,
-                save_file_extension
-            );
-            let output_file_path = output_folder.clone().join(csv_file_name.clone());
-            if output_file_path.exists() {
-                log::info!(" 🔵 [N°{} / {} files] already saved (csv: {}).", 
-                    i,
-                    nb_files,
-                    output_file_path.to_str().unwrap()
-                );
-                return (); // skip
+            let file_name_id = file_name.replace("-heap.raw", "");
+
+            let mut file_annotation_outcomes: Vec<(usize, FileAnnotationOutcome)> = Vec::new();
+            let unannotated_file = |reason: String| UnannotatedFile {
+                heap_dump_raw_file_path: heap_dump_raw_file_path.clone(),
+                reason,
+            };
+            // hash of the input files, once for all the graph groups: 
+            // the heap dump, and the heap dump with its JSON file (only used by an annotated graph)
+            let mut input_file_paths = vec![heap_dump_raw_file_path.clone()];
+            let json_file_path = heap_dump_path_to_json_path(heap_dump_raw_file_path);
+            if is_any_annotated && json_file_path.exists() {
+                input_file_paths.push(json_file_path);
             }
+            let input_hashes = compute_files_prefix_hashes(&input_file_paths);
 
-            // make and check the memory graph
-            let graph_embedding = GraphEmbedding::new(
-                heap_dump_raw_file_path.clone(),
-                crate::params::BLOCK_BYTE_SIZE,
-                *crate::params::EMBEDDING_DEPTH,
-                entropy_filter,
-                chunk_byte_size_filter,
-                annotation,
-                no_value_node,
-            );
-            match graph_embedding {
-                Ok(_) => {},
-                Err(err) => match err {
-                    crate::utils::ErrorKind::MissingJsonKeyError(key) => {
-                        log::warn!(
-                            " 🔴 [t: {}] [N°{} / {} files] [fid: {}]    Missing JSON key: {}", 
-                            thread_name, 
-                            i, 
-                            nb_files, 
-                            heap_dump_raw_file_path.file_name().unwrap().to_str().unwrap(),
-                            key
-                        );
-                        return (); // skip
-                    },
-                    crate::utils::ErrorKind::JsonFileNotFound(json_file_path) => {
-                        log::warn!(" 🟣 [t: {}] [N°{} / {} files] [fid: {}]    JSON file not found: {:?}", 
-                            thread_name, 
-                            i, 
-                            nb_files, 
-                            heap_dump_raw_file_path.file_name().unwrap().to_str().unwrap(), 
-                            json_file_path
+            for graph_group in graph_groups.iter() {
+                let input_hash = match run_configs[graph_group[0]].annotation {
+                    SelectAnnotationLocation::None => &input_hashes[0],
+                    _ => input_hashes.last().unwrap(),
+                };
+                let new_run_manifest_entry = |variant_index: usize, output_file_path: &PathBuf, status, nb_samples, duration_ms| RunManifestEntry {
+                    output_file_name: output_file_path.file_name().unwrap().to_str().unwrap().to_string(),
+                    input_file_path: heap_dump_raw_file_path.clone(),
+                    input_hash: input_hash.to_string(),
+                    parameters: parameters[variant_index].clone(),
+                    status,
+                    nb_samples,
+                    duration_ms,
+                };
+
+                // check if the outputs are already done, in that case skip
+                // NOTE: the aggregated variants have no output per file (None)
+                let mut variants_to_do: Vec<(usize, Option<PathBuf>)> = Vec::new();
+                for variant_index in graph_group.iter() {
+                    let run_config = &run_configs[*variant_index];
+                    let run_manifest = match &run_manifests[*variant_index] {
+                        Some(run_manifest) => run_manifest,
+                        None => {
+                            variants_to_do.push((*variant_index, None));
+                            continue;
+                        },
+                    };
+                    let save_file_extension = get_output_file_extension(run_config);
+                    let csv_file_name = format!(
+                        "{:?}_{}_{}_{}", 
+                        run_config.pipeline, 
+                        dir_path_end_str.replace("/", "_"),
+                        file_name,
+                        save_file_extension
+                    );
+                    let output_file_path = variant_output_folders[*variant_index].join(csv_file_name);
+                    if run_manifest.is_done(&output_file_path, input_hash, &parameters[*variant_index]) {
+                        log::info!(" 🔵 [N°{} / {} files] already saved (csv: {}).", 
+                            i,
+                            nb_files,
+                            output_file_path.to_str().unwrap()
                         );
-                        return (); // skip
-                    },
-                    _ => {
-                        panic!("Other unexpected graph embedding error: {}", err);
+                        if run_config.annotation != SelectAnnotationLocation::None {
+                            file_annotation_outcomes.push((*variant_index, FileAnnotationOutcome::Skipped(
+                                unannotated_file("already done in the run manifest".to_string())
+                            )));
+                        }
+                        continue; // skip
                     }
+                    variants_to_do.push((*variant_index, Some(output_file_path)));
+                }
+                if variants_to_do.is_empty() {
+                    continue;
                 }
-            }
-            let graph_embedding = graph_embedding.unwrap();
 
-            // generate the value embedding and save it
-            let nb_of_samples = gen_and_save_embedding(output_file_path, &graph_embedding);
+                // make and check the memory graph, once for the whole group
+                let mut variant_start_time = Instant::now();
+                let record_failed = |variants_to_do: &Vec<(usize, Option<PathBuf>)>| {
+                    for (variant_index, output_file_path) in variants_to_do.iter() {
+                        if let (Some(run_manifest), Some(output_file_path)) = (&run_manifests[*variant_index], output_file_path) {
+                            run_manifest.record(&new_run_manifest_entry(
+                                *variant_index, output_file_path, RunStatus::Failed, 0, variant_start_time.elapsed().as_millis() as u64
+                            ));
+                        }
+                    }
+                };
+                let graph_embedding = graph_embedding_builders[variants_to_do[0].0].build(heap_dump_raw_file_path.clone());
+                let mut graph_annotate = match graph_embedding {
+                    Ok(graph_embedding) => graph_embedding.into_graph_annotate(),
+                    Err(err) => {
+                        match &err {
+                            crate::utils::ErrorKind::MissingJsonKeyError(key) => {
+                                log::warn!(
+                                    " 🔴 [t: {}] [N°{} / {} files] [fid: {}]    Missing JSON key: {}", 
+                                    thread_name, 
+                                    i, 
+                                    nb_files, 
+                                    file_name,
+                                    key
+                                );
+                            },
+                            crate::utils::ErrorKind::JsonFileNotFound(json_file_path) => {
+                                log::warn!(" 🟣 [t: {}] [N°{} / {} files] [fid: {}]    JSON file not found: {:?}", 
+                                    thread_name, 
+                                    i, 
+                                    nb_files, 
+                                    file_name, 
+                                    json_file_path
+                                );
+                            },
+                            crate::utils::ErrorKind::InvalidKeyPattern(_, _) => {
+                                log::warn!(" 🔴 [t: {}] [N°{} / {} files] [fid: {}]    {}", 
+                                    thread_name, 
+                                    i, 
+                                    nb_files, 
+                                    file_name, 
+                                    err
+                                );
+                            },
+                            _ => {
+                                panic!("Other unexpected graph embedding error: {}", err);
+                            }
+                        }
+                        record_failed(&variants_to_do);
+                        for (variant_index, _) in variants_to_do.iter() {
+                            if run_configs[*variant_index].annotation != SelectAnnotationLocation::None {
+                                file_annotation_outcomes.push((*variant_index, FileAnnotationOutcome::Failed(
+                                    unannotated_file(err.to_string())
+                                )));
+                            }
+                        }
+                        continue; // skip
+                    }
+                };
 
-            let file_name_id = heap_dump_raw_file_path.file_name().unwrap().to_str().unwrap().replace("-heap.raw", "");
-            log::info!(
-                " 🟢 [t: {}] [N°{} / {} files] [fid: {}]    (Nb samples: {})", 
-                thread_name, 
-                i, 
-                nb_files, 
-                file_name_id, 
-                nb_of_samples
-            );
+                for (variant_index, output_file_path) in variants_to_do {
+                    let run_config = &run_configs[variant_index];
+                    let graph_embedding = graph_embedding_builders[variant_index].build_from_graph_annotate(graph_annotate);
+
+                    let variant_name = match &run_config.name {
+                        Some(name) => format!(" [{}]", name),
+                        None => String::new(),
+                    };
+                    let output_file_path = match output_file_path {
+                        Some(output_file_path) => output_file_path,
+                        None => {
+                            // write the samples to the dataset, with their provenance
+                            let dataset = datasets[variant_index].as_ref().unwrap();
+                            let gen_and_write_samples = get_gen_and_write_samples(run_config.pipeline).unwrap();
+                            let provenance = HeapDumpProvenance::from_graph_embedding(&graph_embedding);
+                            let nb_of_samples = gen_and_write_samples(&graph_embedding, &mut ProvenanceSink::new(dataset, &provenance));
+                            log::info!(
+                                " 🟢 [t: {}] [N°{} / {} files] [fid: {}]{}    (Nb samples: {}, aggregated)", 
+                                thread_name, 
+                                i, 
+                                nb_files, 
+                                file_name_id, 
+                                variant_name,
+                                nb_of_samples
+                            );
+                            if run_config.annotation != SelectAnnotationLocation::None {
+                                file_annotation_outcomes.push((variant_index, FileAnnotationOutcome::Annotated(
+                                    graph_embedding.graph_annotate.annotation_report.clone()
+                                )));
+                            }
+                            graph_annotate = graph_embedding.into_graph_annotate();
+                            variant_start_time = Instant::now();
+                            continue;
+                        },
+                    };
+
+                    // generate the embedding and save it, in a temporary file
+                    let tmp_file_path = get_tmp_file_path(&output_file_path);
+                    let nb_of_samples = match run_config.output_format {
+                        OutputFormat::Csv => {
+                            let (gen_and_save_embedding, _) = get_gen_and_save_embedding(run_config.pipeline);
+                            gen_and_save_embedding(tmp_file_path.clone(), &graph_embedding)
+                        },
+                        OutputFormat::Npy | OutputFormat::Npz => {
+                            let gen_and_write_samples = get_gen_and_write_samples(run_config.pipeline).unwrap();
+                            let mut npy_sink = NpySink::create(tmp_file_path.clone(), run_config.output_format);
+                            let nb_of_samples = gen_and_write_samples(&graph_embedding, &mut npy_sink);
+                            match npy_sink.save(&get_tmp_file_path(&output_file_path.with_extension("columns.json"))) {
+                                Ok(_) => nb_of_samples,
+                                Err(err) => {
+                                    // ex: NPZ archive too large, the partial archive is already removed
+                                    log::warn!(" 🔴 [t: {}] [N°{} / {} files] [fid: {}]{}    {}", 
+                                        thread_name, 
+                                        i, 
+                                        nb_files, 
+                                        file_name_id, 
+                                        variant_name,
+                                        err
+                                    );
+                                    if run_config.annotation != SelectAnnotationLocation::None {
+                                        file_annotation_outcomes.push((variant_index, FileAnnotationOutcome::Annotated(
+                                            graph_embedding.graph_annotate.annotation_report.clone()
+                                        )));
+                                    }
+                                    run_manifests[variant_index].as_ref().unwrap().record(&new_run_manifest_entry(
+                                        variant_index, &output_file_path, RunStatus::Failed, 0, variant_start_time.elapsed().as_millis() as u64
+                                    ));
+                                    graph_annotate = graph_embedding.into_graph_annotate();
+                                    variant_start_time = Instant::now();
+                                    continue;
+                                },
+                            }
+                        },
+                    };
+
+                    log::info!(
+                        " 🟢 [t: {}] [N°{} / {} files] [fid: {}]{}    (Nb samples: {})", 
+                        thread_name, 
+                        i, 
+                        nb_files, 
+                        file_name_id, 
+                        variant_name,
+                        nb_of_samples
+                    );
+
+                    // the files saved next to the output, in temporary files too
+                    let mut side_file_paths = vec![output_file_path.with_extension("config.toml")];
+                    if run_config.output_format != OutputFormat::Csv {
+                        side_file_paths.push(output_file_path.with_extension("columns.json"));
+                    }
+
+                    // save the run configuration next to the output
+                    run_config.save(get_tmp_file_path(&side_file_paths[0]));
+
+                    // save the annotation report next to the output
+                    if run_config.annotation != SelectAnnotationLocation::None {
+                        let annotation_report = graph_embedding.graph_annotate.annotation_report.clone();
+                        let annotation_report_path = output_file_path.with_extension("annotation_report.json");
+                        annotation_report.save(get_tmp_file_path(&annotation_report_path));
+                        side_file_paths.push(annotation_report_path);
+                        file_annotation_outcomes.push((variant_index, FileAnnotationOutcome::Annotated(annotation_report)));
+                    }
 
+                    // the output is complete: the output is renamed last, and only then recorded
+                    // NOTE: an interrupted run leaves no complete output without its side files
+                    for side_file_path in side_file_paths.iter() {
+                        rename_tmp_file(side_file_path);
+                    }
+                    rename_tmp_file(&output_file_path);
+                    run_manifests[variant_index].as_ref().unwrap().record(&new_run_manifest_entry(
+                        variant_index, &output_file_path, RunStatus::Done, nb_of_samples, variant_start_time.elapsed().as_millis() as u64
+                    ));
+                    variant_start_time = Instant::now();
+
+                    graph_annotate = graph_embedding.into_graph_annotate();
+                }
+            }
+            file_annotation_outcomes
         }).collect()
     });
 
+    // finish the aggregated datasets
+    for (dataset, run_config) in datasets.into_iter().zip(run_configs.iter()) {
+        if let Some(dataset) = dataset {
+            run_config.save(dataset.get_dataset_file_path(".config.toml"));
+            let shard_file_paths = dataset.finish();
+            log::info!(" 📦 aggregated dataset saved in {} shards (first: {:?})", shard_file_paths.len(), shard_file_paths.first());
+        }
+    }
+
+    // summarise the annotation reports, per variant
+    for (variant_index, run_config) in run_configs.iter().enumerate() {
+        let mut annotation_report_summary = AnnotationReportSummary::default();
+        for (_, annotation_outcome) in annotation_outcomes.iter().filter(|(index, _)| *index == variant_index) {
+            annotation_report_summary.add_outcome(annotation_outcome);
+        }
+        if annotation_report_summary.nb_total_files() == 0 {
+            continue;
+        }
+
+        let variant_name = match &run_config.name {
+            Some(name) => format!(" [{}]", name),
+            None => String::new(),
+        };
+        log::info!(
+            " 🏷️ {} annotations: {} found / {} expected ({} bytes mismatch, {} node not found, {} wrong node type) in {} files",
+            variant_name,
+            annotation_report_summary.nb_found,
+            annotation_report_summary.nb_expected_annotations,
+            annotation_report_summary.nb_bytes_mismatch,
+            annotation_report_summary.nb_node_not_found,
+            annotation_report_summary.nb_wrong_node_type,
+            annotation_report_summary.nb_files
+        );
+        if annotation_report_summary.nb_skipped_files + annotation_report_summary.nb_failed_files > 0 {
+            log::info!(
+                " 🏷️ {} files not annotated: {} skipped (already done), {} failed",
+                variant_name,
+                annotation_report_summary.nb_skipped_files,
+                annotation_report_summary.nb_failed_files
+            );
+        }
+        if annotation_report_summary.nb_repaired_pointer_nodes > 0 {
+            log::info!(
+                " 🔧{} {} key PointerNodes converted back to ValueNodes",
+                variant_name,
+                annotation_report_summary.nb_repaired_pointer_nodes
+            );
+        }
+        annotation_report_summary.save(variant_output_folders[variant_index].join(
+            format!("{:?}_{}_annotation_report_summary.json", run_config.pipeline, input_name)
+        ));
+    }
+
     // log time
     let total_duration = start_time.elapsed();
     log::info!(
         " ⏱️  total pipeline time: {:.2?}]",
         total_duration
     );
-}
\ No newline at end of file
+}
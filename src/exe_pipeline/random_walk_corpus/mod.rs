@@ -0,0 +1,31 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::graph_embedding::GraphEmbedding;
+
+/// Random walk corpus, for a given file.
+/// Save the walks to a text file, one walk per line, as the node addresses (decimal) separated by spaces,
+/// so that it can be used directly by an external word2vec (ex: gensim).
+/// Return the number of walks.
+pub fn gen_and_save_random_walk_corpus(
+    output_file_path: PathBuf,
+    graph_embedding: &GraphEmbedding,
+) -> usize {
+    let corpus = graph_embedding.generate_random_walk_corpus();
+
+    let file = File::create(&output_file_path).unwrap_or_else(
+        |_| panic!("Cannot create random walk corpus file: {:?}", output_file_path)
+    );
+    let mut writer = BufWriter::new(file);
+    for walk in corpus.walks.iter() {
+        let line = walk.iter()
+            .map(|index| corpus.node_addrs[*index].to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(writer, "{}", line).unwrap();
+    }
+    writer.flush().unwrap();
+
+    corpus.walks.len()
+}
@@ -0,0 +1,50 @@
+use std::{path::PathBuf, fs::File};
+use csv::Writer;
+
+use crate::graph_embedding::samples::{SampleValue, Columns};
+
+/// Destination of the samples of an embedding, written one by one.
+pub trait SamplesSink {
+    /// names and types of the columns of the samples (without the label)
+    /// NOTE: Called once, before the first sample (even if there is no sample).
+    fn write_columns(&mut self, columns: &Columns);
+
+    /// values of a sample, in the order of the columns, and its label
+    fn write_sample(&mut self, values: &[SampleValue], label: usize);
+
+    /// NOTE: Called once, after the last sample.
+    fn flush(&mut self) {}
+}
+
+/// Save the samples and labels to a CSV file, with a last "label" column.
+pub struct CsvSink {
+    csv_writer: Writer<File>,
+}
+
+impl CsvSink {
+    pub fn create(csv_path: PathBuf) -> CsvSink {
+        let csv_error_message = format!("Cannot create csv file: {:?}, no such file.", csv_path);
+        let csv_writer = Writer::from_path(&csv_path).unwrap_or_else(
+            |_| panic!("{}", csv_error_message)
+        );
+        CsvSink { csv_writer }
+    }
+}
+
+impl SamplesSink for CsvSink {
+    fn write_columns(&mut self, columns: &Columns) {
+        let mut all_headers = columns.names().to_vec();
+        all_headers.push("label".to_string());
+        self.csv_writer.write_record(&all_headers).unwrap();
+    }
+
+    fn write_sample(&mut self, values: &[SampleValue], label: usize) {
+        let mut row: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        row.push(label.to_string());
+        self.csv_writer.write_record(&row).unwrap();
+    }
+
+    fn flush(&mut self) {
+        self.csv_writer.flush().unwrap();
+    }
+}
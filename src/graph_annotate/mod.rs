@@ -1,11 +1,22 @@
-use crate::{graph_data::GraphData, utils::div_round_up, params::argv::SelectAnnotationLocation};
-use crate::graph_structs::annotations::{NodeAnnotation, KeyAnnotation, AnnotationSet};
+use crate::{graph_data::GraphData, utils::{div_round_up, convert_pointer_to_block}, params::argv::{SelectAnnotationLocation, KeyAnnotationSource}};
+use crate::graph_structs::annotations::{NodeAnnotation, KeyAnnotation, AnnotationSet, KeyDataJSON};
 use crate::graph_structs::Node;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use self::report::{AnnotationReport, AnnotationStatus};
+
+pub mod report;
+pub mod saved_graph;
 
 pub struct GraphAnnotate {
     pub graph_data: GraphData,
     pub annotation: SelectAnnotationLocation,
+    pub key_annotation_source: KeyAnnotationSource,
+    /// report of the expected annotations (found or not), filled during the annotation
+    pub annotation_report: AnnotationReport,
+    /// PointerNodes that hold key bytes (random bytes that happened to fall in the heap range)
+    pub key_pointer_node_addrs: Vec<u64>,
 }
 
 impl GraphAnnotate {
@@ -13,12 +24,25 @@ impl GraphAnnotate {
         heap_dump_raw_file_path: PathBuf, 
         pointer_byte_size: usize,
         annotation : SelectAnnotationLocation,
+        key_annotation_source : KeyAnnotationSource,
+        key_patterns_file : Option<&Path>,
         without_value_nodes : bool,
+        chunk_nb_of_start_bytes_for_chunk_entropy : usize,
     ) -> Result<GraphAnnotate, crate::utils::ErrorKind> {
-        let graph_data = GraphData::new(heap_dump_raw_file_path, pointer_byte_size, annotation != SelectAnnotationLocation::None, without_value_nodes)?;
+        let annotation_report = AnnotationReport::new(heap_dump_raw_file_path.clone());
+        let graph_data = GraphData::new(
+            heap_dump_raw_file_path, 
+            pointer_byte_size, 
+            annotation != SelectAnnotationLocation::None, 
+            key_annotation_source,
+            key_patterns_file,
+            without_value_nodes,
+            chunk_nb_of_start_bytes_for_chunk_entropy,
+        )?;
         
         let mut graph_annotate = GraphAnnotate {
-            graph_data, annotation
+            graph_data, annotation, key_annotation_source, annotation_report,
+            key_pointer_node_addrs: Vec::new(),
         };
         if annotation != SelectAnnotationLocation::None {
             graph_annotate.annotate(annotation);
@@ -26,10 +50,55 @@ impl GraphAnnotate {
         Ok(graph_annotate)
     }
 
+    /// Replace the annotations of the graph by the ones of the given annotation location,
+    /// so that the same graph can be reused for several annotation locations.
+    /// NOTE: The repaired PointerNodes are kept in the new annotation report.
+    /// WARN: The graph must have been built with an annotation (the JSON data is needed),
+    /// unless the new annotation is SelectAnnotationLocation::None.
+    pub fn reannotate(&mut self, annotation : SelectAnnotationLocation) {
+        if annotation == self.annotation {
+            return;
+        }
+
+        let repaired_pointer_nodes = std::mem::take(&mut self.annotation_report.repaired_pointer_nodes);
+        self.annotation_report = AnnotationReport::new(self.annotation_report.heap_dump_raw_file_path.clone());
+        self.annotation_report.repaired_pointer_nodes = repaired_pointer_nodes;
+        self.graph_data.node_addr_to_annotations.clear();
+        self.key_pointer_node_addrs.clear();
+
+        self.annotation = annotation;
+        if annotation != SelectAnnotationLocation::None {
+            self.annotate(annotation);
+        }
+    }
+
+    /// Rebuild an annotated graph from a dot file of the graph pipelines (see GraphData::from_dot_file).
+    /// The annotation location is the one of the annotated nodes (None without annotated node).
+    /// WARN: The heap dump and its JSON file are not loaded: the graph can't be annotated again.
+    pub fn from_dot_file(dot_file_path: &Path) -> Result<GraphAnnotate, crate::utils::ErrorKind> {
+        let graph_data = GraphData::from_dot_file(dot_file_path)?;
+        let annotation = match graph_data.node_addr_to_annotations.keys().next() {
+            Some(addr) if graph_data.addr_to_node.get(addr).unwrap().is_chn() => SelectAnnotationLocation::ChunkHeaderNode,
+            Some(_) => SelectAnnotationLocation::ValueNode,
+            None => SelectAnnotationLocation::None,
+        };
+
+        Ok(GraphAnnotate {
+            graph_data,
+            annotation,
+            key_annotation_source: KeyAnnotationSource::JsonAddress,
+            annotation_report: AnnotationReport::new(dot_file_path.to_path_buf()),
+            key_pointer_node_addrs: Vec::new(),
+        })
+    }
+
     /// Annotate the graph with data from the JSON file
     /// stored in heap_dump_data
     fn annotate(&mut self, annotation : SelectAnnotationLocation) {
-        self.annotate_graph_with_key_data(annotation);
+        match self.key_annotation_source {
+            KeyAnnotationSource::JsonAddress => self.annotate_graph_with_key_data(annotation),
+            KeyAnnotationSource::BytePatternSearch => self.annotate_graph_with_key_patterns(annotation),
+        }
         self.annotate_graph_with_ssh_struct(annotation);
     }
 
@@ -47,24 +116,37 @@ impl GraphAnnotate {
 
                     if node.is_none() {
                         log::warn!("🟠 {} not found for addr: {}", $name, $addr);
+                        self.annotation_report.add($name, Some($addr), AnnotationStatus::NodeNotFound, Vec::new());
                     }else{
                         let node = node.unwrap();
                         
-                        if !node.is_chn() {
+                        let annotated_addr = if !node.is_chn() {
                             if annotation == SelectAnnotationLocation::ChunkHeaderNode {
-                                self.annotate_node(NodeAnnotation::$annotation(node.get_parent_chn_addr().unwrap()));
-                            } else if annotation == SelectAnnotationLocation::ValueNode {
-                                self.annotate_node(NodeAnnotation::$annotation($addr));
+                                node.get_parent_chn_addr()
+                            } else {
+                                Some($addr)
                             }
                         } else {
                             if annotation == SelectAnnotationLocation::ChunkHeaderNode {
-                                self.annotate_node(NodeAnnotation::$annotation($addr));
-                            } else if annotation == SelectAnnotationLocation::ValueNode {
+                                Some($addr)
+                            } else {
                                 // NOTE: The case when we annotate a ChunkHeaderNode with a SessionStateNodeAnnotation
                                 // should never happen, since SessionStateNodeAnnotation are always pointing
                                 // to the first data block of a chunk (verified by scripts)
-                                log::warn!("🟠 {} cannot be annotated on a non-value node: {}", $name, $addr)
+                                log::warn!("🟠 {} cannot be annotated on a non-value node: {}", $name, $addr);
+                                None
                             }
+                        };
+
+                        match annotated_addr {
+                            Some(annotated_addr) => {
+                                let annotated_node = self.graph_data.addr_to_node.get(&annotated_addr).unwrap().str_addr_and_type();
+                                self.annotation_report.add($name, Some($addr), AnnotationStatus::Found, vec![annotated_node]);
+                                self.annotate_node(NodeAnnotation::$annotation(annotated_addr));
+                            },
+                            None => {
+                                self.annotation_report.add($name, Some($addr), AnnotationStatus::WrongNodeType, Vec::new());
+                            },
                         }
                     }
                 }
@@ -84,6 +166,64 @@ impl GraphAnnotate {
         }
     }
 
+    /// bytes of the key at the given address, read from the nodes of the graph
+    /// NOTE: The PointerNodes are converted back to the bytes they have been parsed from,
+    /// in the heap dump byte order (see convert_pointer_to_block), not in the big endian
+    /// order of the pointer value, so that a key block misread as a pointer still matches the key.
+    fn get_key_bytes_from_graph(&self, addr: u64, key_data: &KeyDataJSON) -> Vec<u8> {
+        let mut aggregated_key: Vec<u8> = Vec::new();
+
+        // get all the ValueNodes that are part of the key
+        // WARN: when the key lenght is not a multiple of the block size,
+        //      we need to crop the aggregated_key to the real key length
+        // WARN: Need to round up the division that determines the number of blocks needed.
+        //      Otherwise, we will miss the last block, since it is possible that we only need a fraction of it.
+        let block_size = self.graph_data.heap_dump_data.as_ref().unwrap().block_size;
+        for i in 0..div_round_up(key_data.len, block_size) {
+            let current_key_block_addr = addr + (i * block_size) as u64;
+            let current_key_block_node: Option<&Node> = self.graph_data.addr_to_node.get(&current_key_block_addr);
+            if let Some(current_node) = current_key_block_node {
+                // WARN: it is possible that one the block has been identified as a PointerNode
+                // since we are doing the annotation, we know that it should be a ValueNode.
+                // Do NOT modify the graph, since we are at the annotation stage.
+                // Instead, we just get the value of the ValueNode, and convert the pointer to a row byte array
+                match current_node {
+                    Node::ValueNode(_) => {
+                        aggregated_key.extend_from_slice(&current_node.get_value().unwrap());
+                    },
+                    Node::PointerNode(_) => {
+                        let pointer_value = current_node.points_to().unwrap();
+                        aggregated_key.extend_from_slice(&convert_pointer_to_block(pointer_value));
+                    },
+                    _ => {
+                        // log warning
+                        log::warn!(
+                            "current_key_block_node is not a ValueNode nor a PointerNode for addr: {}, for key {}", 
+                            current_key_block_addr, key_data.name
+                        );
+                        break;
+                    },
+                }
+
+                
+            } else {
+                // log warning
+                log::warn!(
+                    "current_key_block_node not found for addr: {}, for key {}", 
+                    current_key_block_addr, key_data.name
+                );
+                break;
+            }
+        }
+
+        // crop key to real key length
+        if aggregated_key.len() > key_data.len {
+            aggregated_key.truncate(key_data.len);
+        }
+
+        aggregated_key
+    }
+
     /// annotate graph with key data from json file
     /// - aggreagte the value node of the key into a key node
     /// - annotate the key node created as keynode (if we genrate a graph without value nodes, annotate the chn node as keynode)
@@ -95,86 +235,42 @@ impl GraphAnnotate {
         for (addr, key_data) in addr_to_key_data{
             // get the node at the key_data's address
             let node: Option<&Node> = self.graph_data.addr_to_node.get(addr);
+            match node {
+                None => {
+                    self.annotation_report.add(&key_data.name, Some(*addr), AnnotationStatus::NodeNotFound, Vec::new());
+                },
+                Some(node) if !node.is_value() => {
+                    self.annotation_report.add(&key_data.name, Some(*addr), AnnotationStatus::WrongNodeType, Vec::new());
+                },
+                _ => {},
+            }
             if node.is_some() && node.unwrap().is_value() {
 
                 // if the node is a ValueNode, then we can annotate it
                 // i.e. we create a KeyNode from the Node and its key_data
-                let mut aggregated_key: Vec<u8> = Vec::new();
-
-                // get all the ValueNodes that are part of the key
-                // WARN: when the key lenght is not a multiple of the block size,
-                //      we need to crop the aggregated_key to the real key length
-                // WARN: Need to round up the division that determines the number of blocks needed.
-                //      Otherwise, we will miss the last block, since it is possible that we only need a fraction of it.
-                let block_size = self.graph_data.heap_dump_data.as_ref().unwrap().block_size;
-                for i in 0..div_round_up(key_data.len, block_size) {
-                    let current_key_block_addr = addr + (i * block_size) as u64;
-                    let current_key_block_node: Option<&Node> = self.graph_data.addr_to_node.get(&current_key_block_addr);
-                    if current_key_block_node.is_some() {
-                        let current_node = current_key_block_node.unwrap();
-                        // WARN: it is possible that one the block has been identified as a PointerNode
-                        // since we are doing the annotation, we know that it should be a ValueNode.
-                        // Do NOT modify the graph, since we are at the annotation stage.
-                        // Instead, we just get the value of the ValueNode, and convert the pointer to a row byte array
-                        match current_node {
-                            Node::ValueNode(_) => {
-                                aggregated_key.extend_from_slice(&current_node.get_value().unwrap());
-                            },
-                            Node::PointerNode(_) => {
-                                let pointer_value = current_node.points_to().unwrap();
-                                aggregated_key.extend_from_slice(&pointer_value.to_be_bytes());
-                            },
-                            _ => {
-                                // log warning
-                                log::warn!(
-                                    "current_key_block_node is not a ValueNode nor a PointerNode for addr: {}, for key {}", 
-                                    current_key_block_addr, key_data.name
-                                );
-                                break;
-                            },
-                        }
-
-                        
-                    } else {
-                        // log warning
-                        log::warn!(
-                            "current_key_block_node not found for addr: {}, for key {}", 
-                            current_key_block_addr, key_data.name
-                        );
-                        break;
-                    }
-                }
-
-                // crop key to real key length
-                if aggregated_key.len() > key_data.len {
-                    aggregated_key.truncate(key_data.len);
-                }
+                let aggregated_key = self.get_key_bytes_from_graph(*addr, key_data);
                 
                 // annotate if the key found in the heap dump is the same as the key found in the json file
                 if aggregated_key == key_data.key {
-                    let chn_addr = node.unwrap().get_parent_chn_addr().unwrap();
-
-                    // annotate the chn with the key node annotation
-                    match which_annotation {
-                        SelectAnnotationLocation::ChunkHeaderNode => {
-                            let key_annotation = NodeAnnotation::KeyAnnotation(KeyAnnotation {
-                                addr: chn_addr, // addr of first block of key
-                                key: aggregated_key, // found in heap dump, full key (not just the first block)
-                                key_data: key_data.clone(), // found in heap dump, key data
-                            });
-                            annotations.push(key_annotation);
-                        },
-                        SelectAnnotationLocation::ValueNode => {
-                            let key_annotation = NodeAnnotation::KeyAnnotation(KeyAnnotation {
-                                addr: *addr, // addr of first block of key
-                                key: aggregated_key, // found in heap dump, full key (not just the first block)
-                                key_data: key_data.clone(), // found in heap dump, key data
-                            });
-                            annotations.push(key_annotation);
-                        },
-                        _ => {
-                            panic!("Cannot annotate graph with key data if annotation is None")
-                        }
+                    // annotate the chn, the first block or all the blocks of the key with the key node annotation
+                    let annotated_addrs = self.get_key_annotated_addrs(which_annotation, *addr, key_data.len);
+                    self.annotation_report.add(
+                        &key_data.name, 
+                        Some(*addr), 
+                        AnnotationStatus::Found, 
+                        annotated_addrs.iter().map(
+                            |annotated_addr| self.graph_data.addr_to_node.get(annotated_addr).unwrap().str_addr_and_type()
+                        ).collect()
+                    );
+
+                    self.key_pointer_node_addrs.extend(self.get_key_pointer_node_addrs(*addr, key_data.len));
+                    for annotated_addr in annotated_addrs {
+                        let key_annotation = NodeAnnotation::KeyAnnotation(KeyAnnotation {
+                            addr: annotated_addr, // addr of annotated node
+                            key: aggregated_key.clone(), // found in heap dump, full key (not just the first block)
+                            key_data: key_data.clone(), // found in heap dump, key data
+                        });
+                        annotations.push(key_annotation);
                     }
 
                 } else {
@@ -184,10 +280,104 @@ impl GraphAnnotate {
                         expected key_data.key: {:?}", 
                         key_data.name, aggregated_key, key_data.key
                     );
+                    self.annotation_report.add_bytes_mismatch(
+                        &key_data.name, Some(*addr), &key_data.key, &aggregated_key
+                    );
+                }
+
+            }
+        }
+
+        // annotate the graph with the key node annotations
+        for annotation in annotations {
+            self.annotate_node(annotation);
+        }
+    }
+
+    /// annotate graph with key bytes from json file, without using the key addresses
+    /// - search all the keys in the whole heap dump at once (see HeapDumpData::key_pattern_searcher)
+    /// - annotate every occurrence of a key, including copies that the JSON address misses
+    /// 
+    /// NOTE: The annotated node is the block containing the first byte of the key occurrence.
+    fn annotate_graph_with_key_patterns(&mut self, which_annotation : SelectAnnotationLocation) {
+        let mut annotations = Vec::new();
+
+        let heap_dump_data = self.graph_data.heap_dump_data.as_ref().unwrap();
+        let key_patterns = &heap_dump_data.key_patterns;
+        let searcher = match &heap_dump_data.key_pattern_searcher {
+            Some(searcher) => searcher,
+            None => {
+                log::warn!("🟠 no key pattern to search in the heap dump");
+                return;
+            }
+        };
+        let mut key_name_to_annotated_nodes: HashMap<&String, Vec<String>> = HashMap::new();
+        let mut wrong_node_key_names = HashSet::new();
+
+        let block_size = heap_dump_data.block_size;
+
+        // the blocks are contiguous, searched in place
+        for found in searcher.find_overlapping_iter(heap_dump_data.blocks.as_flattened()) {
+            let key_pattern = &key_patterns[found.pattern().as_usize()];
+            let key_addr = heap_dump_data.min_addr + found.start() as u64;
+            let block_addr = heap_dump_data.index_to_addr_wrapper(found.start() / block_size);
+            if found.start() % block_size != 0 {
+                log::debug!(
+                    "key ({}) found at unaligned addr: {:#x}, annotating block at addr: {:#x}", 
+                    key_pattern.name, key_addr, block_addr
+                );
+            }
+
+            // the key must be in the user data of a chunk
+            let node: Option<&Node> = self.graph_data.addr_to_node.get(&block_addr);
+            match node {
+                Some(node) if node.is_value() || node.is_pointer() => {},
+                _ => {
+                    log::warn!(
+                        "🟠 key ({}) found at addr: {:#x}, but not in a ValueNode nor a PointerNode", 
+                        key_pattern.name, key_addr
+                    );
+                    wrong_node_key_names.insert(&key_pattern.name);
+                    continue;
                 }
+            };
+
+            let key_data = KeyDataJSON {
+                name: key_pattern.name.clone(),
+                key: key_pattern.key.clone(),
+                addr: key_addr,
+                len: key_pattern.key.len(),
+                real_len: key_pattern.key.len(),
+            };
+            self.key_pointer_node_addrs.extend(self.get_key_pointer_node_addrs(key_addr, key_pattern.key.len()));
+            for annotated_addr in self.get_key_annotated_addrs(which_annotation, key_addr, key_pattern.key.len()) {
+                annotations.push(NodeAnnotation::KeyAnnotation(KeyAnnotation {
+                    addr: annotated_addr,
+                    key: key_pattern.key.clone(),
+                    key_data: key_data.clone(),
+                }));
+                key_name_to_annotated_nodes.entry(&key_pattern.name).or_default().push(
+                    self.graph_data.addr_to_node.get(&annotated_addr).unwrap().str_addr_and_type()
+                );
+            }
+        }
 
+        // report the keys, and log the ones that have not been found
+        for key_pattern in key_patterns.iter() {
+            match key_name_to_annotated_nodes.remove(&key_pattern.name) {
+                Some(annotated_nodes) => {
+                    self.annotation_report.add(&key_pattern.name, None, AnnotationStatus::Found, annotated_nodes);
+                },
+                None if wrong_node_key_names.contains(&key_pattern.name) => {
+                    self.annotation_report.add(&key_pattern.name, None, AnnotationStatus::WrongNodeType, Vec::new());
+                },
+                None => {
+                    log::warn!("🟠 key ({}) not found in heap dump", key_pattern.name);
+                    self.annotation_report.add(&key_pattern.name, None, AnnotationStatus::NodeNotFound, Vec::new());
+                },
             }
         }
+        log::debug!("Number of key occurrences found in heap dump: {}", annotations.len());
 
         // annotate the graph with the key node annotations
         for annotation in annotations {
@@ -195,6 +385,94 @@ impl GraphAnnotate {
         }
     }
 
+    /// get the addresses of all the blocks covering the bytes of a key
+    /// NOTE: the key address can be unaligned, the first block is the one containing the first byte of the key
+    fn get_key_block_addrs(&self, key_addr: u64, key_len: usize) -> Vec<u64> {
+        let heap_dump_data = self.graph_data.heap_dump_data.as_ref().unwrap();
+        let block_size = heap_dump_data.block_size as u64;
+        let first_block_addr = key_addr - (key_addr - heap_dump_data.min_addr) % block_size;
+        let key_end_addr = key_addr + key_len.max(1) as u64; // excluded
+
+        (first_block_addr..key_end_addr).step_by(block_size as usize).collect()
+    }
+
+    /// get the addresses of the PointerNodes holding the bytes of a key
+    fn get_key_pointer_node_addrs(&self, key_addr: u64, key_len: usize) -> Vec<u64> {
+        self.get_key_block_addrs(key_addr, key_len).into_iter()
+            .filter(|block_addr| {
+                self.graph_data.addr_to_node.get(block_addr).is_some_and(|node| node.is_pointer())
+            })
+            .collect()
+    }
+
+    /// get the addresses of the nodes to annotate for a key, given the annotation location
+    /// - ChunkHeaderNode: the chn of the chunk containing the key
+    /// - ValueNode: the block containing the first byte of the key
+    /// - KeySpan: every ValueNode or PointerNode covering the key bytes
+    /// 
+    /// WARN: The block containing the first byte of the key must be a node of the user data of a chunk.
+    fn get_key_annotated_addrs(
+        &self, 
+        which_annotation : SelectAnnotationLocation, 
+        key_addr: u64, 
+        key_len: usize,
+    ) -> Vec<u64> {
+        let key_block_addrs = self.get_key_block_addrs(key_addr, key_len);
+        
+        match which_annotation {
+            SelectAnnotationLocation::ChunkHeaderNode => {
+                let first_block_node = self.graph_data.addr_to_node.get(&key_block_addrs[0]).unwrap();
+                vec![first_block_node.get_parent_chn_addr().unwrap()]
+            },
+            SelectAnnotationLocation::ValueNode => vec![key_block_addrs[0]],
+            SelectAnnotationLocation::KeySpan => {
+                let mut annotated_addrs = Vec::new();
+                for block_addr in key_block_addrs {
+                    match self.graph_data.addr_to_node.get(&block_addr) {
+                        Some(node) if node.is_value() || node.is_pointer() => {
+                            annotated_addrs.push(block_addr);
+                        },
+                        _ => {
+                            log::warn!(
+                                "🟠 block at addr: {:#x} of the key at addr: {:#x} is not a ValueNode nor a PointerNode", 
+                                block_addr, key_addr
+                            );
+                        },
+                    }
+                }
+                annotated_addrs
+            },
+            SelectAnnotationLocation::None => {
+                panic!("Cannot annotate graph with key data if annotation is None")
+            },
+        }
+    }
+
+    /// Repair the graph after the annotation: the PointerNodes holding key bytes
+    /// are converted back to ValueNodes, and their spurious pointer edges are removed.
+    /// The repaired nodes are added to the annotation report.
+    /// 
+    /// :return: The number of repaired nodes.
+    pub fn repair_key_pointer_nodes(&mut self) -> usize {
+        self.key_pointer_node_addrs.sort();
+        self.key_pointer_node_addrs.dedup();
+
+        let mut nb_repaired_nodes = 0;
+        for addr in self.key_pointer_node_addrs.iter() {
+            if self.graph_data.convert_pointer_node_to_value_node(*addr) {
+                self.annotation_report.repaired_pointer_nodes.push(
+                    format!("PN({:#x})", addr)
+                );
+                nb_repaired_nodes += 1;
+            }
+        }
+
+        if nb_repaired_nodes > 0 {
+            log::info!(" 🔧 {} key PointerNodes converted back to ValueNodes", nb_repaired_nodes);
+        }
+        nb_repaired_nodes
+    }
+
     /// annote a node
     fn annotate_node(&mut self, annotation : NodeAnnotation) {
         let addr = annotation.get_address();
@@ -224,10 +502,13 @@ mod tests {
         crate::tests::setup();
 
         let graph_annotate = GraphAnnotate::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
             params::BLOCK_BYTE_SIZE,
             SelectAnnotationLocation::ValueNode,
-            false
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
         ).unwrap();
 
         // check that there is the SshStructNodeAnnotation
@@ -252,10 +533,13 @@ mod tests {
         crate::tests::setup();
 
         let graph_annotate = GraphAnnotate::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
             params::BLOCK_BYTE_SIZE,
             SelectAnnotationLocation::ValueNode,
-            false
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
         ).unwrap();
 
         // check that there is at least one KeyNode
@@ -269,6 +553,196 @@ mod tests {
         assert!(found_key_node);
     }
 
+    #[test]
+    fn test_annotation_report() {
+        crate::tests::setup();
+
+        let graph_annotate = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::ValueNode,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        let report = &graph_annotate.annotation_report;
+
+        // 6 keys, from A to F, the ssh struct and the session state
+        assert_eq!(report.expected_annotations.len(), 8);
+        let key_f_report = report.expected_annotations.iter()
+            .find(|expected| expected.name == *crate::tests::TEST_KEY_F_NAME)
+            .unwrap();
+        assert_eq!(key_f_report.status, AnnotationStatus::Found);
+        assert_eq!(key_f_report.expected_addr, Some(*crate::tests::TEST_KEY_F_ADDR));
+        assert_eq!(
+            key_f_report.annotated_nodes, 
+            vec![graph_annotate.graph_data.addr_to_node.get(&*crate::tests::TEST_KEY_F_ADDR).unwrap().str_addr_and_type()]
+        );
+    }
+
+    #[test]
+    fn test_key_span_annotation() {
+        crate::tests::setup();
+
+        let graph_annotate = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::KeySpan,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+
+        // every block of key F is annotated, not just the first one
+        let nb_key_f_blocks = div_round_up(*crate::tests::TEST_KEY_F_LEN, params::BLOCK_BYTE_SIZE);
+        for i in 0..nb_key_f_blocks {
+            let key_f_block_addr = *crate::tests::TEST_KEY_F_ADDR + (i * params::BLOCK_BYTE_SIZE) as u64;
+            let key_f_block_annotation = graph_annotate.graph_data.node_addr_to_annotations.get(&key_f_block_addr);
+            assert!(key_f_block_annotation.is_some());
+            assert!(key_f_block_annotation.unwrap().is_key_subclass());
+        }
+
+        // the block after the key is not annotated
+        let after_key_f_addr = *crate::tests::TEST_KEY_F_ADDR + (nb_key_f_blocks * params::BLOCK_BYTE_SIZE) as u64;
+        assert!(graph_annotate.graph_data.node_addr_to_annotations.get(&after_key_f_addr).is_none());
+    }
+
+    #[test]
+    fn test_repair_key_pointer_nodes() {
+        crate::tests::setup();
+
+        let mut graph_annotate = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::KeySpan,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        let key_pointer_node_addrs = graph_annotate.key_pointer_node_addrs.clone();
+        for addr in key_pointer_node_addrs.iter() {
+            assert!(graph_annotate.graph_data.addr_to_node.get(addr).unwrap().is_pointer());
+        }
+
+        let nb_repaired_nodes = graph_annotate.repair_key_pointer_nodes();
+        assert_eq!(nb_repaired_nodes, graph_annotate.annotation_report.repaired_pointer_nodes.len());
+        for addr in key_pointer_node_addrs.iter() {
+            let node = graph_annotate.graph_data.addr_to_node.get(addr).unwrap();
+            assert!(node.is_value());
+            // the annotation of the key is kept
+            assert!(graph_annotate.graph_data.node_addr_to_annotations.get(addr).unwrap().is_key_subclass());
+        }
+
+        // nothing left to repair
+        assert_eq!(graph_annotate.repair_key_pointer_nodes(), 0);
+    }
+
+    #[test]
+    fn test_reannotate() {
+        crate::tests::setup();
+
+        let new_graph_annotate = |annotation| GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(),
+            params::BLOCK_BYTE_SIZE,
+            annotation,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        let annotated_addrs = |graph_annotate: &GraphAnnotate| {
+            let mut addrs: Vec<u64> = graph_annotate.graph_data.node_addr_to_annotations.keys().cloned().collect();
+            addrs.sort();
+            addrs
+        };
+
+        let expected = new_graph_annotate(SelectAnnotationLocation::ChunkHeaderNode);
+        let mut graph_annotate = new_graph_annotate(SelectAnnotationLocation::ValueNode);
+        graph_annotate.reannotate(SelectAnnotationLocation::ChunkHeaderNode);
+
+        assert_eq!(graph_annotate.annotation, SelectAnnotationLocation::ChunkHeaderNode);
+        assert_eq!(annotated_addrs(&graph_annotate), annotated_addrs(&expected));
+        assert_eq!(
+            graph_annotate.annotation_report.expected_annotations.len(),
+            expected.annotation_report.expected_annotations.len()
+        );
+
+        graph_annotate.reannotate(SelectAnnotationLocation::None);
+        assert!(graph_annotate.graph_data.node_addr_to_annotations.is_empty());
+        assert!(graph_annotate.annotation_report.expected_annotations.is_empty());
+    }
+
+    #[test]
+    fn test_get_key_bytes_from_graph() {
+        crate::tests::setup();
+
+        let graph_annotate = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::None,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        assert!(graph_annotate.graph_data.addr_to_node.get(&*crate::tests::TEST_PTR_1_ADDR).unwrap().is_pointer());
+
+        // a key block parsed as a pointer gives back the bytes of the heap dump (little endian pointer),
+        // not the big endian bytes of the pointer value
+        let key_data = KeyDataJSON {
+            name: "KEY_OVER_POINTER".to_string(),
+            key: crate::tests::TEST_PTR_1_VALUE_BYTES.to_vec(),
+            addr: *crate::tests::TEST_PTR_1_ADDR,
+            len: 6,
+            real_len: 6,
+        };
+        let key_bytes = graph_annotate.get_key_bytes_from_graph(key_data.addr, &key_data);
+        assert_eq!(key_bytes, vec![0xd0, 0x61, 0xd2, 0x43, 0x83, 0x55]);
+        assert_ne!(key_bytes, crate::tests::TEST_PTR_1_VALUE.to_be_bytes()[..6].to_vec());
+    }
+
+    #[test]
+    fn test_key_pattern_annotation() {
+        crate::tests::setup();
+
+        let graph_annotate = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::ValueNode,
+            KeyAnnotationSource::BytePatternSearch,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+
+        // key F is found without using its address
+        let key_f_annotation = graph_annotate.graph_data.node_addr_to_annotations.get(
+            &*crate::tests::TEST_KEY_F_ADDR
+        );
+        assert!(key_f_annotation.is_some());
+        assert!(key_f_annotation.unwrap().is_key_subclass());
+
+        // at least as many keys as when using the json addresses
+        let graph_annotate_json_address = GraphAnnotate::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            SelectAnnotationLocation::ValueNode,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        let count_key_nodes = |graph_annotate: &GraphAnnotate| {
+            graph_annotate.graph_data.node_addr_to_annotations.values()
+                .filter(|annotation_set| annotation_set.is_key_subclass())
+                .count()
+        };
+        assert!(count_key_nodes(&graph_annotate) >= count_key_nodes(&graph_annotate_json_address));
+    }
+
     #[test]
     fn test_graph_generation_to_dot() {
         crate::tests::setup();
@@ -278,10 +752,13 @@ mod tests {
         use std::io::Write;
         
         let graph_annotate = GraphAnnotate::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
             params::BLOCK_BYTE_SIZE,
             SelectAnnotationLocation::ValueNode,
-            false
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
         ).unwrap();
 
         // save the graph to a file as a dot file (graphviz)
@@ -0,0 +1,253 @@
+use std::{path::PathBuf, fs::File, io::Write};
+use serde_derive::{Serialize, Deserialize};
+
+/// Outcome of the annotation of an expected annotation (key, ssh struct, session state)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnnotationStatus {
+    /// the annotation has been put on the graph
+    Found,
+    /// the bytes found in the heap dump are not the ones expected from the json file
+    BytesMismatch,
+    /// there is no node at the expected address, or the expected bytes are nowhere in the heap dump
+    NodeNotFound,
+    /// a node exists at the expected address, but it cannot hold the annotation
+    WrongNodeType,
+}
+
+/// A byte that differs between the expected annotation and the heap dump
+/// NOTE: found is None when the heap dump bytes are shorter than the expected ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteDiff {
+    pub offset: usize,
+    pub expected: u8,
+    pub found: Option<u8>,
+}
+
+/// Report of a single expected annotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedAnnotationReport {
+    /// name of the annotation in the json file (ex: KEY_A, SSH_STRUCT_ADDR)
+    pub name: String,
+    /// address given by the json file (None if the annotation has been searched without address)
+    pub expected_addr: Option<u64>,
+    pub status: AnnotationStatus,
+    /// nodes on which the annotation landed (ex: VN(0x558343d20e90))
+    pub annotated_nodes: Vec<String>,
+    /// byte diff, only for a BytesMismatch
+    pub byte_diff: Vec<ByteDiff>,
+}
+
+/// Annotation report of a given heap dump file.
+/// Allows to measure the label noise of a dataset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationReport {
+    pub heap_dump_raw_file_path: PathBuf,
+    pub expected_annotations: Vec<ExpectedAnnotationReport>,
+    /// PointerNodes holding key bytes, converted back to ValueNodes (ex: PN(0x558343d20e90))
+    pub repaired_pointer_nodes: Vec<String>,
+}
+
+impl AnnotationReport {
+    pub fn new(heap_dump_raw_file_path: PathBuf) -> AnnotationReport {
+        AnnotationReport {
+            heap_dump_raw_file_path,
+            expected_annotations: Vec::new(),
+            repaired_pointer_nodes: Vec::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        expected_addr: Option<u64>,
+        status: AnnotationStatus,
+        annotated_nodes: Vec<String>,
+    ) {
+        self.expected_annotations.push(ExpectedAnnotationReport {
+            name: name.to_string(),
+            expected_addr,
+            status,
+            annotated_nodes,
+            byte_diff: Vec::new(),
+        });
+    }
+
+    /// add a BytesMismatch, with the diff between the expected and the found bytes
+    pub fn add_bytes_mismatch(
+        &mut self,
+        name: &str,
+        expected_addr: Option<u64>,
+        expected: &[u8],
+        found: &[u8],
+    ) {
+        self.expected_annotations.push(ExpectedAnnotationReport {
+            name: name.to_string(),
+            expected_addr,
+            status: AnnotationStatus::BytesMismatch,
+            annotated_nodes: Vec::new(),
+            byte_diff: compute_byte_diff(expected, found),
+        });
+    }
+
+    /// count the expected annotations with the given status
+    pub fn count(&self, status: AnnotationStatus) -> usize {
+        self.expected_annotations.iter()
+            .filter(|expected_annotation| expected_annotation.status == status)
+            .count()
+    }
+
+    /// save the report as a json file
+    pub fn save(&self, report_path: PathBuf) {
+        let mut report_file = File::create(&report_path).unwrap_or_else(
+            |_| panic!("Cannot create annotation report file: {:?}", report_path)
+        );
+        report_file.write_all(
+            serde_json::to_string_pretty(self).unwrap().as_bytes()
+        ).unwrap();
+    }
+}
+
+/// A file of a pipeline run that has not been annotated, and why
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnannotatedFile {
+    pub heap_dump_raw_file_path: PathBuf,
+    pub reason: String,
+}
+
+/// Outcome of the annotation of a file, for a pipeline variant
+#[derive(Debug, Clone)]
+pub enum FileAnnotationOutcome {
+    Annotated(AnnotationReport),
+    /// the outputs are already done (see RunManifest), the file is not processed again
+    Skipped(UnannotatedFile),
+    /// the graph could not be built (ex: missing JSON file or key)
+    Failed(UnannotatedFile),
+}
+
+/// Summary of the annotation reports over a pipeline run
+/// NOTE: The annotation counts are only over the annotated files.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AnnotationReportSummary {
+    /// number of annotated files
+    pub nb_files: usize,
+    pub nb_skipped_files: usize,
+    pub nb_failed_files: usize,
+    pub nb_expected_annotations: usize,
+    pub nb_found: usize,
+    pub nb_bytes_mismatch: usize,
+    pub nb_node_not_found: usize,
+    pub nb_wrong_node_type: usize,
+    pub nb_repaired_pointer_nodes: usize,
+    /// files with at least one expected annotation that is not Found
+    pub files_with_issues: Vec<PathBuf>,
+    pub skipped_files: Vec<UnannotatedFile>,
+    pub failed_files: Vec<UnannotatedFile>,
+}
+
+impl AnnotationReportSummary {
+    pub fn add_outcome(&mut self, outcome: &FileAnnotationOutcome) {
+        match outcome {
+            FileAnnotationOutcome::Annotated(report) => self.add_report(report),
+            FileAnnotationOutcome::Skipped(skipped_file) => {
+                self.nb_skipped_files += 1;
+                self.skipped_files.push(skipped_file.clone());
+            },
+            FileAnnotationOutcome::Failed(failed_file) => {
+                self.nb_failed_files += 1;
+                self.failed_files.push(failed_file.clone());
+            },
+        }
+    }
+
+    /// total number of files of the run: annotated, skipped and failed
+    pub fn nb_total_files(&self) -> usize {
+        self.nb_files + self.nb_skipped_files + self.nb_failed_files
+    }
+
+    pub fn add_report(&mut self, report: &AnnotationReport) {
+        self.nb_files += 1;
+        self.nb_expected_annotations += report.expected_annotations.len();
+        self.nb_found += report.count(AnnotationStatus::Found);
+        self.nb_bytes_mismatch += report.count(AnnotationStatus::BytesMismatch);
+        self.nb_node_not_found += report.count(AnnotationStatus::NodeNotFound);
+        self.nb_wrong_node_type += report.count(AnnotationStatus::WrongNodeType);
+        self.nb_repaired_pointer_nodes += report.repaired_pointer_nodes.len();
+
+        if report.count(AnnotationStatus::Found) != report.expected_annotations.len() {
+            self.files_with_issues.push(report.heap_dump_raw_file_path.clone());
+        }
+    }
+
+    /// save the summary as a json file
+    pub fn save(&self, summary_path: PathBuf) {
+        let mut summary_file = File::create(&summary_path).unwrap_or_else(
+            |_| panic!("Cannot create annotation report summary file: {:?}", summary_path)
+        );
+        summary_file.write_all(
+            serde_json::to_string_pretty(self).unwrap().as_bytes()
+        ).unwrap();
+    }
+}
+
+/// compute the list of differing bytes between the expected and the found bytes
+pub fn compute_byte_diff(expected: &[u8], found: &[u8]) -> Vec<ByteDiff> {
+    let mut byte_diff = Vec::new();
+    for (offset, expected_byte) in expected.iter().enumerate() {
+        let found_byte = found.get(offset).copied();
+        if found_byte != Some(*expected_byte) {
+            byte_diff.push(ByteDiff {
+                offset,
+                expected: *expected_byte,
+                found: found_byte,
+            });
+        }
+    }
+    byte_diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_byte_diff() {
+        let byte_diff = compute_byte_diff(&[0x01, 0x02, 0x03, 0x04], &[0x01, 0xff, 0x03]);
+
+        assert_eq!(byte_diff, vec![
+            ByteDiff { offset: 1, expected: 0x02, found: Some(0xff) },
+            ByteDiff { offset: 3, expected: 0x04, found: None },
+        ]);
+        assert!(compute_byte_diff(&[0x01, 0x02], &[0x01, 0x02]).is_empty());
+    }
+
+    #[test]
+    fn test_annotation_report_summary() {
+        let mut report = AnnotationReport::new(PathBuf::from("test/17016-1643962152-heap.raw"));
+        report.add("KEY_A", Some(0x10), AnnotationStatus::Found, vec!["VN(0x10)".to_string()]);
+        report.add_bytes_mismatch("KEY_B", Some(0x20), &[0x01], &[0x02]);
+
+        let mut summary = AnnotationReportSummary::default();
+        summary.add_report(&report);
+
+        assert_eq!(summary.nb_files, 1);
+        assert_eq!(summary.nb_expected_annotations, 2);
+        assert_eq!(summary.nb_found, 1);
+        assert_eq!(summary.nb_bytes_mismatch, 1);
+        assert_eq!(summary.files_with_issues.len(), 1);
+
+        // the skipped and failed files are counted apart from the annotated ones
+        let unannotated_file = |file_name: &str, reason: &str| UnannotatedFile {
+            heap_dump_raw_file_path: PathBuf::from(file_name),
+            reason: reason.to_string(),
+        };
+        summary.add_outcome(&FileAnnotationOutcome::Skipped(unannotated_file("skipped-heap.raw", "already done")));
+        summary.add_outcome(&FileAnnotationOutcome::Failed(unannotated_file("failed-heap.raw", "JSON file not found")));
+        summary.add_outcome(&FileAnnotationOutcome::Annotated(report));
+
+        assert_eq!((summary.nb_files, summary.nb_skipped_files, summary.nb_failed_files), (2, 1, 1));
+        assert_eq!(summary.nb_total_files(), 4);
+        assert_eq!(summary.nb_expected_annotations, 4);
+        assert_eq!(summary.skipped_files[0].heap_dump_raw_file_path, PathBuf::from("skipped-heap.raw"));
+        assert_eq!(summary.failed_files[0].reason, "JSON file not found");
+    }
+}
@@ -0,0 +1,120 @@
+use serde_derive::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::graph_data::GraphData;
+use crate::graph_data::saved_graph::{SavedGraphData, SAVED_GRAPH_FORMAT_VERSION};
+use crate::params::argv::{SelectAnnotationLocation, KeyAnnotationSource};
+use crate::utils::ErrorKind;
+use super::GraphAnnotate;
+use super::report::AnnotationReport;
+
+/// Serializable form of a fully built and annotated graph (see GraphAnnotate::save)
+#[derive(Serialize, Deserialize)]
+pub struct SavedGraph {
+    pub format_version: u32,
+    pub annotation: SelectAnnotationLocation,
+    pub key_annotation_source: KeyAnnotationSource,
+    pub annotation_report: AnnotationReport,
+    pub key_pointer_node_addrs: Vec<u64>,
+    pub graph_data: SavedGraphData,
+}
+
+impl GraphAnnotate {
+    /// Save the graph and its annotations to a JSON file (see SAVED_GRAPH_FILE_EXTENSION),
+    /// so that it can be reloaded without the heap dump.
+    pub fn save(&self, saved_graph_file_path: &Path) -> Result<(), ErrorKind> {
+        let saved_graph = SavedGraph {
+            format_version: SAVED_GRAPH_FORMAT_VERSION,
+            annotation: self.annotation,
+            key_annotation_source: self.key_annotation_source,
+            annotation_report: self.annotation_report.clone(),
+            key_pointer_node_addrs: self.key_pointer_node_addrs.clone(),
+            graph_data: self.graph_data.to_saved_graph_data(),
+        };
+
+        let mut writer = BufWriter::new(File::create(saved_graph_file_path).map_err(ErrorKind::Io)?);
+        serde_json::to_writer(&mut writer, &saved_graph).map_err(ErrorKind::Json)?;
+        writer.flush().map_err(ErrorKind::Io)?;
+        Ok(())
+    }
+
+    /// Load a graph saved by GraphAnnotate::save.
+    /// WARN: The heap dump and its JSON file are not loaded: the graph can't be annotated again.
+    pub fn load(saved_graph_file_path: &Path) -> Result<GraphAnnotate, ErrorKind> {
+        let reader = BufReader::new(File::open(saved_graph_file_path).map_err(ErrorKind::Io)?);
+        let saved_graph: SavedGraph = serde_json::from_reader(reader).map_err(ErrorKind::Json)?;
+        if saved_graph.format_version != SAVED_GRAPH_FORMAT_VERSION {
+            return Err(ErrorKind::InvalidSavedGraph(
+                saved_graph_file_path.to_path_buf(),
+                format!(
+                    "format version {} instead of {}",
+                    saved_graph.format_version, SAVED_GRAPH_FORMAT_VERSION
+                ),
+            ));
+        }
+
+        Ok(GraphAnnotate {
+            graph_data: GraphData::from_saved_graph_data(saved_graph.graph_data),
+            annotation: saved_graph.annotation,
+            key_annotation_source: saved_graph.key_annotation_source,
+            annotation_report: saved_graph.annotation_report,
+            key_pointer_node_addrs: saved_graph.key_pointer_node_addrs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+
+    #[test]
+    fn test_saved_graph_round_trip() {
+        crate::tests::setup();
+
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_saved_graph_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+        let saved_graph_file_path = output_folder.join("graph.json");
+
+        let builder = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .repair_key_pointer_nodes(true);
+        let graph_embedding = builder.build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone()).unwrap();
+        graph_embedding.graph_annotate.save(&saved_graph_file_path).unwrap();
+
+        let loaded_graph_embedding = builder.build_from_saved_graph(&saved_graph_file_path).unwrap();
+        let graph_data = &graph_embedding.graph_annotate.graph_data;
+        let loaded_graph_data = &loaded_graph_embedding.graph_annotate.graph_data;
+
+        // same graph, in the same order
+        assert_eq!(format!("{}", graph_data), format!("{}", loaded_graph_data));
+        assert!(graph_data.addr_to_node == loaded_graph_data.addr_to_node);
+        assert!(graph_data.node_addr_to_annotations == loaded_graph_data.node_addr_to_annotations);
+        assert_eq!(graph_data.chn_addrs, loaded_graph_data.chn_addrs);
+        assert_eq!(
+            graph_embedding.graph_annotate.annotation_report.repaired_pointer_nodes,
+            loaded_graph_embedding.graph_annotate.annotation_report.repaired_pointer_nodes
+        );
+        assert_eq!(
+            graph_data.heap_dump_data.as_ref().unwrap().min_addr,
+            loaded_graph_data.heap_dump_data.as_ref().unwrap().min_addr
+        );
+
+        // same embedding
+        assert_eq!(
+            graph_embedding.generate_chunk_semantic_embedding(),
+            loaded_graph_embedding.generate_chunk_semantic_embedding()
+        );
+
+        // the annotation location can't be changed
+        let other_builder = GraphEmbeddingBuilder::new().annotation(SelectAnnotationLocation::ValueNode);
+        assert!(matches!(
+            other_builder.build_from_saved_graph(&saved_graph_file_path),
+            Err(ErrorKind::InvalidSavedGraph(_, _))
+        ));
+
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+}
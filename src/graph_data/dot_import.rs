@@ -0,0 +1,448 @@
+use petgraph::graphmap::DiGraphMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::graph_structs::{Node, ChunkHeaderNode, ValueNode, PointerNode, FooterNode, HeaderFlags, Edge, EdgeType};
+use crate::graph_structs::annotations::{AnnotationSet, NodeAnnotation, KeyAnnotation, KeyDataJSON};
+use crate::params::BLOCK_BYTE_SIZE;
+use crate::utils::ErrorKind;
+use super::GraphData;
+use super::heap_dump_data::HeapDumpData;
+
+/// A node of a dot file, as written by GraphData::generate_gv_str
+struct DotNode {
+    /// ex: "CHN", "VN"
+    node_type: String,
+    addr: u64,
+    label: String,
+    color: String,
+    /// the annotated nodes are filled
+    is_filled: bool,
+    /// values of the embedding comment (graph-with-embedding-comments), in the order of the embedding fields
+    comment: Option<Vec<String>>,
+}
+
+/// An edge of a dot file, as written by GraphData::generate_gv_str
+struct DotEdge {
+    from: u64,
+    to: u64,
+    edge_type: EdgeType,
+    weight: usize,
+}
+
+/// Content of a dot file, in the order of the file
+struct DotFile {
+    /// fields of the embedding comments of the nodes (empty without embedding comments)
+    embedding_fields: Vec<String>,
+    nodes: Vec<DotNode>,
+    edges: Vec<DotEdge>,
+}
+
+/// Parse a node id of the dot files (ex: "VN(0x558343d1a010)") into its type and address
+fn parse_dot_node_id(node_id: &str) -> Result<(String, u64), String> {
+    let (node_type, addr) = node_id.strip_suffix(')')
+        .and_then(|node_id| node_id.split_once("(0x"))
+        .ok_or_else(|| format!("invalid node id: {}", node_id))?;
+    let addr = u64::from_str_radix(addr, 16)
+        .map_err(|_| format!("invalid node address: {}", node_id))?;
+    Ok((node_type.to_string(), addr))
+}
+
+/// Parse a quoted string at the start of the given string.
+/// Return the string without the quotes, and the rest after the closing quote.
+/// NOTE: The dot files of mem2graph don't escape the quotes.
+fn parse_quoted(str: &str) -> Result<(&str, &str), String> {
+    let str = str.strip_prefix('"').ok_or_else(|| format!("missing quote: {}", str))?;
+    let end = str.find('"').ok_or_else(|| format!("unclosed quote: {}", str))?;
+    Ok((&str[..end], &str[end + 1..]))
+}
+
+/// Parse the dot attributes of a node or an edge (ex: [label="VN" color="grey"])
+/// NOTE: An attribute without value (ex: style=filled) keeps its bare value.
+fn parse_dot_attributes(str: &str) -> Result<HashMap<String, String>, String> {
+    let str = str.trim();
+    let mut rest = str.strip_prefix('[')
+        .and_then(|str| str.strip_suffix(']'))
+        .ok_or_else(|| format!("invalid attributes: {}", str))?
+        .trim_start();
+
+    let mut attributes = HashMap::new();
+    while !rest.is_empty() {
+        let (key, value_and_rest) = rest.split_once('=')
+            .ok_or_else(|| format!("invalid attribute: {}", rest))?;
+        let (value, next) = if value_and_rest.starts_with('"') {
+            parse_quoted(value_and_rest)?
+        } else {
+            value_and_rest.split_once(' ').unwrap_or((value_and_rest, ""))
+        };
+        attributes.insert(key.trim().to_string(), value.to_string());
+        rest = next.trim_start();
+    }
+    Ok(attributes)
+}
+
+/// Parse an embedding comment (ex: "[94022266888200,0,592,2.58]") into its values
+fn parse_dot_list(list: &str) -> Result<Vec<String>, String> {
+    let list = list.trim().strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+        .ok_or_else(|| format!("invalid list: {}", list))?;
+    Ok(list.split(',')
+        .map(|value| value.trim().trim_matches('\'').to_string())
+        .filter(|value| !value.is_empty())
+        .collect())
+}
+
+/// Parse the edge label of the dot files (ex: "ptr(1)", "chunk(1)") into the type of the edge
+fn parse_dot_edge_type(label: &str) -> Result<EdgeType, String> {
+    match label.split_once('(') {
+        Some(("ptr", _)) => Ok(EdgeType::PointerEdge),
+        Some(("chunk", _)) => Ok(EdgeType::ChunkEdge),
+        _ => Err(format!("invalid edge label: {}", label)),
+    }
+}
+
+/// Rebuild the annotations of a node from its label and color (see AnnotationSet::annotate_dot_attributes).
+/// The color gives the annotation classes, the label gives the name of the key.
+/// NOTE: The key bytes are not in the dot files, the key annotations only keep the name of the key.
+fn parse_dot_annotations(addr: u64, label: &str, color: &str) -> Result<AnnotationSet, String> {
+    let (is_key, is_ssh_struct, is_session_state) = match color {
+        "green" => (true, false, false),
+        "red" => (false, true, false),
+        "blue" => (false, false, true),
+        "purple" => (false, true, true),
+        "cyan" => (true, false, true),
+        _ => return Err(format!("invalid annotation color: {}", color)),
+    };
+
+    let mut annotations = Vec::new();
+    if is_key {
+        let key_name = if is_session_state { "KEY" } else { label };
+        annotations.push(NodeAnnotation::KeyAnnotation(KeyAnnotation {
+            addr,
+            key: Vec::new(),
+            key_data: KeyDataJSON {
+                name: key_name.to_string(),
+                key: Vec::new(),
+                addr,
+                len: 0,
+                real_len: 0,
+            },
+        }));
+    }
+    if is_ssh_struct {
+        annotations.push(NodeAnnotation::SshStructNodeAnnotation(addr));
+    }
+    if is_session_state {
+        annotations.push(NodeAnnotation::SessionStateNodeAnnotation(addr));
+    }
+
+    let mut annotations = annotations.into_iter();
+    let mut annotation_set = AnnotationSet::new(annotations.next().unwrap());
+    for annotation in annotations {
+        annotation_set.add_annotation(annotation);
+    }
+    Ok(annotation_set)
+}
+
+/// Parse the lines of a dot file into its embedding fields, nodes and edges
+fn parse_dot_lines(dot_str: &str) -> Result<DotFile, String> {
+    let mut embedding_fields = Vec::new();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    for (line_index, line) in dot_str.lines().enumerate() {
+        // NOTE: The lines of the older dot files end with a ';'
+        let line = line.trim().trim_end_matches(';');
+        let add_line_number = |message: String| format!("line {}: {}", line_index + 1, message);
+
+        if line.is_empty() || line == "digraph {" || line == "}" {
+            continue;
+        } else if let Some(graph_comment) = line.strip_prefix("comment=") {
+            // ex: comment="{ 'embedding-type': '...', 'embedding-fields': ['chn_addr',...] }"
+            let (graph_comment, _) = parse_quoted(graph_comment).map_err(add_line_number)?;
+            if let Some((_, fields)) = graph_comment.split_once("'embedding-fields':") {
+                let fields = fields.trim().trim_end_matches('}');
+                embedding_fields = parse_dot_list(fields).map_err(add_line_number)?;
+            }
+        } else if line.starts_with('"') {
+            let (first_node_id, rest) = parse_quoted(line).map_err(add_line_number)?;
+            let (from_type, from) = parse_dot_node_id(first_node_id).map_err(add_line_number)?;
+
+            match rest.trim_start().strip_prefix("->") {
+                Some(rest) => {
+                    let (second_node_id, rest) = parse_quoted(rest.trim_start()).map_err(add_line_number)?;
+                    let (_, to) = parse_dot_node_id(second_node_id).map_err(add_line_number)?;
+                    let attributes = parse_dot_attributes(rest).map_err(add_line_number)?;
+
+                    let label = attributes.get("label")
+                        .ok_or_else(|| add_line_number("missing edge label".to_string()))?;
+                    let weight = attributes.get("weight")
+                        .ok_or_else(|| add_line_number("missing edge weight".to_string()))?;
+                    edges.push(DotEdge {
+                        from,
+                        to,
+                        edge_type: parse_dot_edge_type(label).map_err(add_line_number)?,
+                        weight: weight.parse().map_err(|_| add_line_number(format!("invalid edge weight: {}", weight)))?,
+                    });
+                },
+                None => {
+                    let mut attributes = parse_dot_attributes(rest).map_err(add_line_number)?;
+                    let comment = match attributes.remove("comment") {
+                        Some(comment) => Some(parse_dot_list(&comment).map_err(add_line_number)?),
+                        None => None,
+                    };
+                    nodes.push(DotNode {
+                        node_type: from_type,
+                        addr: from,
+                        label: attributes.remove("label").unwrap_or_default(),
+                        color: attributes.remove("color").unwrap_or_default(),
+                        is_filled: attributes.get("style").is_some_and(|style| style == "filled"),
+                        comment,
+                    });
+                },
+            }
+        } else {
+            return Err(add_line_number(format!("unexpected line: {}", line)));
+        }
+    }
+    Ok(DotFile { embedding_fields, nodes, edges })
+}
+
+impl GraphData {
+    /// Rebuild a GraphData from a dot file of the graph pipelines (Graph_*.gv, GraphWithEmbeddingComments_*.gv).
+    /// The chunk of each node is given by the chunk edges, and the pointed node of a pointer by its pointer edge.
+    /// The size, the number of pointers and values, the number in the heap and the entropy of the chunks
+    /// are taken from the embedding comments when there are some.
+    /// WARN: The dot files don't hold the bytes of the heap dump:
+    ///     - the values of the ValueNodes are zeros, the flags of the chunks are unset and they are not free
+    ///     - without embedding comments, the entropy of the chunks is 0
+    ///     - the PointerNodes without pointer edge point to 0
+    ///     - the key annotations only keep the name of the key
+    pub fn from_dot_file(dot_file_path: &Path) -> Result<GraphData, ErrorKind> {
+        let dot_str = std::fs::read_to_string(dot_file_path).map_err(ErrorKind::Io)?;
+        GraphData::from_dot_str(&dot_str, dot_file_path.to_path_buf())
+            .map_err(|message| ErrorKind::InvalidDotFile(dot_file_path.to_path_buf(), message))
+    }
+
+    fn from_dot_str(dot_str: &str, dot_file_path: PathBuf) -> Result<GraphData, String> {
+        let DotFile { embedding_fields, nodes: dot_nodes, edges: dot_edges } = parse_dot_lines(dot_str)?;
+
+        // chunk of each node, children of each chunk and pointed node of each pointer
+        let mut node_addr_to_chn_addr: HashMap<u64, u64> = HashMap::new();
+        let mut chn_addr_to_children: HashMap<u64, Vec<u64>> = HashMap::new();
+        let mut pointer_addr_to_pointed_addr: HashMap<u64, u64> = HashMap::new();
+        for edge in dot_edges.iter() {
+            match edge.edge_type {
+                EdgeType::ChunkEdge => {
+                    node_addr_to_chn_addr.insert(edge.to, edge.from);
+                    chn_addr_to_children.entry(edge.from).or_default().push(edge.to);
+                },
+                EdgeType::PointerEdge => {
+                    pointer_addr_to_pointed_addr.insert(edge.from, edge.to);
+                },
+            }
+        }
+        let node_addr_to_type: HashMap<u64, &str> = dot_nodes.iter()
+            .map(|dot_node| (dot_node.addr, dot_node.node_type.as_str()))
+            .collect();
+
+        // get a field of the embedding comment of a chunk
+        let get_comment_field = |dot_node: &DotNode, field: &str| -> Option<String> {
+            let field_index = embedding_fields.iter().position(|name| name == field)?;
+            dot_node.comment.as_ref()?.get(field_index).cloned()
+        };
+        let get_parent_chn_addr = |addr: u64| -> Result<u64, String> {
+            node_addr_to_chn_addr.get(&addr).copied()
+                .ok_or_else(|| format!("no chunk edge to the node {:#x}", addr))
+        };
+
+        let mut chn_byte_sizes: HashMap<u64, usize> = HashMap::new();
+        let mut nodes = Vec::with_capacity(dot_nodes.len());
+        let chn_dot_nodes = dot_nodes.iter().filter(|dot_node| dot_node.node_type == "CHN");
+        for (chunk_number_in_heap, dot_node) in chn_dot_nodes.enumerate() {
+            let children = chn_addr_to_children.get(&dot_node.addr).map(|children| children.as_slice()).unwrap_or(&[]);
+            let count_children = |node_type: &str| {
+                children.iter().filter(|addr| node_addr_to_type.get(addr) == Some(&node_type)).count()
+            };
+            let parse_comment_field = |field: &str, default: usize| -> Result<usize, String> {
+                match get_comment_field(dot_node, field) {
+                    Some(value) => value.parse().map_err(|_| format!("invalid {}: {}", field, value)),
+                    None => Ok(default),
+                }
+            };
+
+            let byte_size = parse_comment_field(
+                "chunk_byte_size",
+                if children.is_empty() { 0 } else { (children.len() + 1) * BLOCK_BYTE_SIZE },
+            )?;
+            chn_byte_sizes.insert(dot_node.addr, byte_size);
+            let entropy = match get_comment_field(dot_node, "entropy") {
+                Some(value) => value.parse().map_err(|_| format!("invalid entropy: {}", value))?,
+                None => 0.0,
+            };
+            nodes.push(Node::ChunkHeaderNode(ChunkHeaderNode {
+                addr: dot_node.addr,
+                byte_size,
+                flags: HeaderFlags { p: false, m: false, a: false },
+                is_free: false,
+                nb_pointer_nodes: parse_comment_field("chunk_ptrs", count_children("PN"))?,
+                nb_value_nodes: parse_comment_field("chunk_vns", count_children("VN"))?,
+                start_data_bytes_entropy: entropy,
+                chunk_number_in_heap: parse_comment_field("chunk_number_in_heap", chunk_number_in_heap)?,
+            }));
+        }
+        for dot_node in dot_nodes.iter().filter(|dot_node| dot_node.node_type != "CHN") {
+            let chn_addr = get_parent_chn_addr(dot_node.addr)?;
+            let node = match dot_node.node_type.as_str() {
+                "VN" => Node::ValueNode(ValueNode {
+                    addr: dot_node.addr,
+                    value: [0u8; BLOCK_BYTE_SIZE],
+                    chn_addr,
+                }),
+                "PN" => Node::PointerNode(PointerNode {
+                    addr: dot_node.addr,
+                    points_to: pointer_addr_to_pointed_addr.get(&dot_node.addr).copied().unwrap_or(0),
+                    chn_addr,
+                }),
+                "FN" => Node::FooterNode(FooterNode {
+                    addr: dot_node.addr,
+                    byte_size: chn_byte_sizes.get(&chn_addr).copied().unwrap_or(0),
+                    flags: HeaderFlags { p: false, m: false, a: false },
+                    chn_addr,
+                }),
+                node_type => return Err(format!("invalid node type: {}", node_type)),
+            };
+            nodes.push(node);
+        }
+
+        // the nodes and edges are added in the order of the file
+        let mut graph = DiGraphMap::<u64, Edge>::new();
+        let mut chn_addrs = Vec::new();
+        let mut value_node_addrs = Vec::new();
+        let mut pointer_node_addrs = Vec::new();
+        let mut node_addr_to_annotations = HashMap::new();
+        for dot_node in dot_nodes.iter() {
+            graph.add_node(dot_node.addr);
+            match dot_node.node_type.as_str() {
+                "CHN" => chn_addrs.push(dot_node.addr),
+                "VN" => value_node_addrs.push(dot_node.addr),
+                "PN" => pointer_node_addrs.push(dot_node.addr),
+                _ => {},
+            }
+            if dot_node.is_filled {
+                let annotation_set = parse_dot_annotations(dot_node.addr, &dot_node.label, &dot_node.color)?;
+                node_addr_to_annotations.insert(dot_node.addr, annotation_set);
+            }
+        }
+        for dot_edge in dot_edges {
+            if !graph.contains_node(dot_edge.from) || !graph.contains_node(dot_edge.to) {
+                return Err(format!("edge between unknown nodes: {:#x} -> {:#x}", dot_edge.from, dot_edge.to));
+            }
+            graph.add_edge(dot_edge.from, dot_edge.to, Edge {
+                from: dot_edge.from,
+                to: dot_edge.to,
+                edge_type: dot_edge.edge_type,
+                weight: dot_edge.weight,
+            });
+        }
+
+        // NOTE: The start of the heap dump is approximated by its first chunk.
+        let min_addr = chn_addrs.iter().min().copied().unwrap_or(0);
+        let max_addr = nodes.iter().map(|node| node.get_address()).max().map_or(0, |addr| addr + BLOCK_BYTE_SIZE as u64);
+
+        Ok(GraphData {
+            graph,
+            addr_to_node: nodes.into_iter().map(|node| (node.get_address(), node)).collect(),
+            chn_addrs,
+            no_value_node: value_node_addrs.is_empty() && pointer_node_addrs.is_empty(),
+            value_node_addrs,
+            pointer_node_addrs,
+            node_addr_to_annotations,
+            chunk_nb_of_start_bytes_for_chunk_entropy: crate::params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+            heap_dump_data: Some(HeapDumpData::new_without_blocks(dot_file_path, BLOCK_BYTE_SIZE, min_addr, max_addr)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_annotate::GraphAnnotate;
+    use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+    use crate::exe_pipeline::graph_generation_with_embedding::gen_and_save_memory_graph_with_embedding_comments;
+    use crate::params::argv::SelectAnnotationLocation;
+
+    #[test]
+    fn test_parse_dot_attributes() {
+        let attributes = parse_dot_attributes(
+            "[label=\"KEY_F\" color=\"green\" style=filled comment=\"[1,0,64,2.5]\"]"
+        ).unwrap();
+        assert_eq!(attributes["label"], "KEY_F");
+        assert_eq!(attributes["style"], "filled");
+        assert_eq!(parse_dot_list(&attributes["comment"]).unwrap(), vec!["1", "0", "64", "2.5"]);
+        assert_eq!(parse_dot_node_id("VN(0x558343d1a010)").unwrap(), ("VN".to_string(), 0x558343d1a010));
+        assert!(parse_dot_node_id("VN(558343d1a010)").is_err());
+    }
+
+    #[test]
+    fn test_graph_from_dot_file() {
+        crate::tests::setup();
+
+        let output_folder = std::env::temp_dir().join(format!("mem_to_graph_test_dot_import_{}", std::process::id()));
+        std::fs::create_dir_all(&output_folder).unwrap();
+
+        for annotation in [SelectAnnotationLocation::ValueNode, SelectAnnotationLocation::ChunkHeaderNode] {
+            let builder = GraphEmbeddingBuilder::new().annotation(annotation);
+            let graph_embedding = builder.build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone()).unwrap();
+            let graph_data = &graph_embedding.graph_annotate.graph_data;
+            let dot_file_path = output_folder.join("graph.gv");
+            std::fs::write(&dot_file_path, format!("{}", graph_data)).unwrap();
+
+            let graph_annotate = GraphAnnotate::from_dot_file(&dot_file_path).unwrap();
+            assert!(graph_annotate.annotation == annotation);
+            let imported_graph_data = &graph_annotate.graph_data;
+
+            // same dot file, so same nodes, edges and annotation classes, in the same order
+            assert_eq!(format!("{}", graph_data), format!("{}", imported_graph_data));
+            assert_eq!(graph_data.chn_addrs, imported_graph_data.chn_addrs);
+            assert_eq!(graph_data.value_node_addrs, imported_graph_data.value_node_addrs);
+            assert_eq!(graph_data.pointer_node_addrs, imported_graph_data.pointer_node_addrs);
+            for (addr, annotation_set) in graph_data.node_addr_to_annotations.iter() {
+                assert_eq!(
+                    annotation_set.annotation_set_embedding(),
+                    imported_graph_data.node_addr_to_annotations[addr].annotation_set_embedding()
+                );
+            }
+
+            // the structure of the chunks is rebuilt from the edges
+            for chn_addr in graph_data.chn_addrs.iter() {
+                match (&graph_data.addr_to_node[chn_addr], &imported_graph_data.addr_to_node[chn_addr]) {
+                    (Node::ChunkHeaderNode(chn), Node::ChunkHeaderNode(imported_chn)) => {
+                        assert_eq!(chn.nb_pointer_nodes, imported_chn.nb_pointer_nodes);
+                        assert_eq!(chn.nb_value_nodes, imported_chn.nb_value_nodes);
+                        assert_eq!(chn.chunk_number_in_heap, imported_chn.chunk_number_in_heap);
+                    },
+                    _ => panic!("The node should be a CHN"),
+                }
+            }
+        }
+
+        // with the embedding comments, the chunk embedding is the same
+        let builder = GraphEmbeddingBuilder::new().annotation(SelectAnnotationLocation::ChunkHeaderNode);
+        let graph_embedding = builder.build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone()).unwrap();
+        let dot_file_path = output_folder.join("graph_with_embedding_comments.gv");
+        gen_and_save_memory_graph_with_embedding_comments(dot_file_path.clone(), &graph_embedding);
+
+        let imported_graph_embedding = builder.build_from_dot_file(&dot_file_path).unwrap();
+        assert_eq!(
+            graph_embedding.generate_chunk_semantic_embedding(),
+            imported_graph_embedding.generate_chunk_semantic_embedding()
+        );
+        assert!(matches!(
+            GraphEmbeddingBuilder::new().build_from_dot_file(&dot_file_path),
+            Err(ErrorKind::InvalidDotFile(_, _))
+        ));
+
+        std::fs::remove_dir_all(&output_folder).unwrap();
+    }
+}
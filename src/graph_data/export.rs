@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use petgraph::visit::IntoEdgeReferences;
+
+use crate::graph_embedding::samples::{SampleValue, Columns, ColumnType};
+use crate::graph_structs::Node;
+use crate::utils;
+
+use super::GraphData;
+
+/// type of an attribute of the nodes or the edges
+/// NOTE: The names are the same in GraphML and GEXF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeType {
+    Boolean,
+    Long,
+    Double,
+    String,
+}
+
+impl AttributeType {
+    fn name(&self) -> &'static str {
+        match self {
+            AttributeType::Boolean => "boolean",
+            AttributeType::Long => "long",
+            AttributeType::Double => "double",
+            AttributeType::String => "string",
+        }
+    }
+}
+
+/// attributes of all the nodes, in order (see GraphData::get_node_attribute_values)
+const NODE_ATTRIBUTES: [(&str, AttributeType); 16] = [
+    ("address", AttributeType::Long),
+    ("node_type", AttributeType::String),
+    ("chn_addr", AttributeType::Long),
+    ("byte_size", AttributeType::Long),
+    ("flag_p", AttributeType::Boolean),
+    ("flag_m", AttributeType::Boolean),
+    ("flag_a", AttributeType::Boolean),
+    ("is_free", AttributeType::Boolean),
+    ("nb_pointer_nodes", AttributeType::Long),
+    ("nb_value_nodes", AttributeType::Long),
+    ("entropy", AttributeType::Double),
+    ("chunk_number_in_heap", AttributeType::Long),
+    ("points_to", AttributeType::Long),
+    ("value", AttributeType::String),
+    ("annotation_label", AttributeType::Long),
+    ("annotations", AttributeType::String),
+];
+
+/// attributes of all the edges, in order
+const EDGE_ATTRIBUTES: [(&str, AttributeType); 2] = [
+    ("edge_type", AttributeType::String),
+    ("weight", AttributeType::Long),
+];
+
+/// prefix of the names of the additional node attributes (ex: emb_chunk_ptrs)
+/// NOTE: Avoid a clash with the names of the node attributes
+pub const EXTRA_NODE_ATTRIBUTE_PREFIX: &str = "emb_";
+
+/// Additional typed attributes of some nodes, for the GraphML and GEXF exports (ex: the fields of an embedding).
+pub struct ExtraNodeAttributes {
+    /// attributes of the graph itself (name, value), ex: the embedding type
+    pub graph_attributes: Vec<(String, String)>,
+    /// names and types of the attributes, without the prefix
+    pub columns: Columns,
+    /// values of the nodes having the attributes, in the order of the columns
+    pub node_addr_to_values: HashMap<u64, Vec<SampleValue>>,
+}
+
+impl ExtraNodeAttributes {
+    /// type of each column, from the type of the embedding column
+    fn get_column_types(&self) -> Vec<AttributeType> {
+        self.columns.types().iter()
+            .map(|column_type| match column_type {
+                ColumnType::Int => AttributeType::Long,
+                ColumnType::Float => AttributeType::Double,
+                ColumnType::Text => AttributeType::String,
+            })
+            .collect()
+    }
+}
+
+/// escape a string to be used in a XML attribute or text
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// attributes (name, type) of the nodes, and their values for each node, in the same order
+type NodeAttributeTable = (Vec<(String, AttributeType)>, Vec<(u64, Vec<Option<String>>)>);
+
+impl GraphData {
+
+    /// values of the node attributes of a node, in the order of NODE_ATTRIBUTES
+    /// NOTE: None if the attribute doesn't apply to the node (ex: byte_size of a value node)
+    fn get_node_attribute_values(&self, node: &Node) -> Vec<Option<String>> {
+        let mut values: Vec<Option<String>> = vec![None; NODE_ATTRIBUTES.len()];
+        values[0] = Some(node.get_address().to_string());
+        values[2] = node.get_parent_chn_addr().map(|chn_addr| chn_addr.to_string());
+
+        match node {
+            Node::ChunkHeaderNode(chn) => {
+                values[1] = Some("CHN".to_string());
+                values[2] = Some(chn.addr.to_string());
+                values[3] = Some(chn.byte_size.to_string());
+                values[4] = Some(chn.flags.p.to_string());
+                values[5] = Some(chn.flags.m.to_string());
+                values[6] = Some(chn.flags.a.to_string());
+                values[7] = Some(chn.is_free.to_string());
+                values[8] = Some(chn.nb_pointer_nodes.to_string());
+                values[9] = Some(chn.nb_value_nodes.to_string());
+                values[10] = Some(chn.start_data_bytes_entropy.to_string());
+                values[11] = Some(chn.chunk_number_in_heap.to_string());
+            },
+            Node::ValueNode(value_node) => {
+                values[1] = Some("VN".to_string());
+                values[13] = Some(utils::bytes_to_hex_string(&value_node.value.to_vec()));
+            },
+            Node::PointerNode(pointer_node) => {
+                values[1] = Some("PN".to_string());
+                values[12] = Some(pointer_node.points_to.to_string());
+            },
+            Node::FooterNode(footer_node) => {
+                values[1] = Some("FN".to_string());
+                values[3] = Some(footer_node.byte_size.to_string());
+                values[4] = Some(footer_node.flags.p.to_string());
+                values[5] = Some(footer_node.flags.m.to_string());
+                values[6] = Some(footer_node.flags.a.to_string());
+            },
+        }
+
+        match self.node_addr_to_annotations.get(&node.get_address()) {
+            Some(annotation_set) => {
+                values[14] = Some(annotation_set.annotation_set_embedding().to_string());
+                values[15] = Some(annotation_set.get_subclass_names().join(","));
+            },
+            None => {
+                values[14] = Some("0".to_string());
+            },
+        }
+        values
+    }
+
+    /// all the node attributes (with the additional ones), and their values for each node, in the graph order
+    fn get_node_attribute_table(&self, extra_node_attributes: Option<&ExtraNodeAttributes>) -> NodeAttributeTable {
+        let mut attributes: Vec<(String, AttributeType)> = NODE_ATTRIBUTES.iter()
+            .map(|(name, attribute_type)| (name.to_string(), *attribute_type))
+            .collect();
+        if let Some(extra_node_attributes) = extra_node_attributes {
+            attributes.extend(
+                extra_node_attributes.columns.iter()
+                    .zip(extra_node_attributes.get_column_types())
+                    .map(|(column, attribute_type)| (format!("{}{}", EXTRA_NODE_ATTRIBUTE_PREFIX, column), attribute_type))
+            );
+        }
+
+        let mut nodes = Vec::with_capacity(self.graph.node_count());
+        for addr in self.graph.nodes() {
+            let node = self.addr_to_node.get(&addr).unwrap();
+            let mut values = self.get_node_attribute_values(node);
+            if let Some(extra_node_attributes) = extra_node_attributes {
+                match extra_node_attributes.node_addr_to_values.get(&addr) {
+                    Some(extra_values) => values.extend(extra_values.iter().map(|value| Some(value.to_string()))),
+                    None => values.extend(vec![None; extra_node_attributes.columns.len()]),
+                }
+            }
+            nodes.push((addr, values));
+        }
+        (attributes, nodes)
+    }
+
+    /// Generate a string following the GraphML format, with typed attributes for the nodes and the edges.
+    /// NOTE: The nodes have the same ids as in the dot format (ex: CHN(0x55...)).
+    pub fn to_graphml(&self, extra_node_attributes: Option<&ExtraNodeAttributes>) -> String {
+        let (node_attributes, nodes) = self.get_node_attribute_table(extra_node_attributes);
+        let mut graphml_str = String::new();
+
+        graphml_str.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        graphml_str.push_str(concat!(
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" ",
+            "xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ",
+            "xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n"
+        ));
+
+        // declare the attributes
+        let graph_attributes = extra_node_attributes.map(|extra| extra.graph_attributes.as_slice()).unwrap_or(&[]);
+        for (i, (name, _)) in graph_attributes.iter().enumerate() {
+            graphml_str.push_str(&format!(
+                "  <key id=\"g{}\" for=\"graph\" attr.name=\"{}\" attr.type=\"string\"/>\n", i, escape_xml(name)
+            ));
+        }
+        for (i, (name, attribute_type)) in node_attributes.iter().enumerate() {
+            graphml_str.push_str(&format!(
+                "  <key id=\"n{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"/>\n", i, escape_xml(name), attribute_type.name()
+            ));
+        }
+        for (i, (name, attribute_type)) in EDGE_ATTRIBUTES.iter().enumerate() {
+            graphml_str.push_str(&format!(
+                "  <key id=\"e{}\" for=\"edge\" attr.name=\"{}\" attr.type=\"{}\"/>\n", i, name, attribute_type.name()
+            ));
+        }
+
+        graphml_str.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+        for (i, (_, value)) in graph_attributes.iter().enumerate() {
+            graphml_str.push_str(&format!("    <data key=\"g{}\">{}</data>\n", i, escape_xml(value)));
+        }
+
+        for (addr, values) in nodes.iter() {
+            let node = self.addr_to_node.get(addr).unwrap();
+            graphml_str.push_str(&format!("    <node id=\"{}\">\n", escape_xml(&node.str_addr_and_type())));
+            for (i, value) in values.iter().enumerate() {
+                if let Some(value) = value {
+                    graphml_str.push_str(&format!("      <data key=\"n{}\">{}</data>\n", i, escape_xml(value)));
+                }
+            }
+            graphml_str.push_str("    </node>\n");
+        }
+
+        for (from_addr, to_addr, edge) in self.graph.edge_references() {
+            let from = self.addr_to_node.get(&from_addr).unwrap();
+            let to = self.addr_to_node.get(&to_addr).unwrap();
+            graphml_str.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n",
+                escape_xml(&from.str_addr_and_type()), escape_xml(&to.str_addr_and_type())
+            ));
+            graphml_str.push_str(&format!("      <data key=\"e0\">{}</data>\n", edge.edge_type));
+            graphml_str.push_str(&format!("      <data key=\"e1\">{}</data>\n", edge.weight));
+            graphml_str.push_str("    </edge>\n");
+        }
+
+        graphml_str.push_str("  </graph>\n");
+        graphml_str.push_str("</graphml>\n");
+        graphml_str
+    }
+
+    /// Generate a string following the GEXF (1.2) format, with typed attributes for the nodes and the edges.
+    /// NOTE: The nodes have the same ids as in the dot format (ex: CHN(0x55...)), and their type as label.
+    pub fn to_gexf(&self, extra_node_attributes: Option<&ExtraNodeAttributes>) -> String {
+        let (node_attributes, nodes) = self.get_node_attribute_table(extra_node_attributes);
+        let mut gexf_str = String::new();
+
+        gexf_str.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gexf_str.push_str("<gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">\n");
+
+        // the graph attributes are saved in the description of the graph
+        let graph_attributes = extra_node_attributes.map(|extra| extra.graph_attributes.as_slice()).unwrap_or(&[]);
+        if !graph_attributes.is_empty() {
+            let description = graph_attributes.iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<String>>()
+                .join("; ");
+            gexf_str.push_str(&format!("  <meta>\n    <description>{}</description>\n  </meta>\n", escape_xml(&description)));
+        }
+
+        gexf_str.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+
+        // declare the attributes
+        gexf_str.push_str("    <attributes class=\"node\">\n");
+        for (i, (name, attribute_type)) in node_attributes.iter().enumerate() {
+            gexf_str.push_str(&format!(
+                "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>\n", i, escape_xml(name), attribute_type.name()
+            ));
+        }
+        gexf_str.push_str("    </attributes>\n");
+        gexf_str.push_str("    <attributes class=\"edge\">\n");
+        gexf_str.push_str(&format!(
+            "      <attribute id=\"0\" title=\"{}\" type=\"{}\"/>\n", EDGE_ATTRIBUTES[0].0, EDGE_ATTRIBUTES[0].1.name()
+        ));
+        gexf_str.push_str("    </attributes>\n");
+
+        gexf_str.push_str("    <nodes>\n");
+        for (addr, values) in nodes.iter() {
+            let node = self.addr_to_node.get(addr).unwrap();
+            gexf_str.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\">\n        <attvalues>\n",
+                escape_xml(&node.str_addr_and_type()), values[1].as_ref().unwrap()
+            ));
+            for (i, value) in values.iter().enumerate() {
+                if let Some(value) = value {
+                    gexf_str.push_str(&format!("          <attvalue for=\"{}\" value=\"{}\"/>\n", i, escape_xml(value)));
+                }
+            }
+            gexf_str.push_str("        </attvalues>\n      </node>\n");
+        }
+        gexf_str.push_str("    </nodes>\n");
+
+        // NOTE: The weight of an edge is a native GEXF attribute
+        gexf_str.push_str("    <edges>\n");
+        for (i, (from_addr, to_addr, edge)) in self.graph.edge_references().enumerate() {
+            let from = self.addr_to_node.get(&from_addr).unwrap();
+            let to = self.addr_to_node.get(&to_addr).unwrap();
+            gexf_str.push_str(&format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\">\n",
+                i, escape_xml(&from.str_addr_and_type()), escape_xml(&to.str_addr_and_type()), edge.weight
+            ));
+            gexf_str.push_str(&format!(
+                "        <attvalues>\n          <attvalue for=\"0\" value=\"{}\"/>\n        </attvalues>\n", edge.edge_type
+            ));
+            gexf_str.push_str("      </edge>\n");
+        }
+        gexf_str.push_str("    </edges>\n");
+
+        gexf_str.push_str("  </graph>\n");
+        gexf_str.push_str("</gexf>\n");
+        gexf_str
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::argv::SelectAnnotationLocation;
+    use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+
+    /// annotated graph of the test heap dump
+    fn get_test_graph_data() -> GraphData {
+        GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ValueNode)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap()
+            .into_graph_annotate()
+            .graph_data
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("CHN(0x10)"), "CHN(0x10)");
+        assert_eq!(escape_xml("a<b & 'c'>\""), "a&lt;b &amp; &apos;c&apos;&gt;&quot;");
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        crate::tests::setup();
+        let graph_data = get_test_graph_data();
+
+        let graphml_str = graph_data.to_graphml(None);
+        assert!(graphml_str.starts_with("<?xml"));
+        assert!(graphml_str.contains("<key id=\"n0\" for=\"node\" attr.name=\"address\" attr.type=\"long\"/>"));
+        assert!(graphml_str.contains("attr.name=\"entropy\" attr.type=\"double\""));
+        assert_eq!(graphml_str.matches("<node id=").count(), graph_data.graph.node_count());
+        assert_eq!(graphml_str.matches("<edge source=").count(), graph_data.graph.edge_count());
+        assert!(graphml_str.trim_end().ends_with("</graphml>"));
+
+        // the annotated nodes have a label and their annotation classes
+        assert!(graphml_str.contains("<data key=\"n15\">key</data>"));
+    }
+
+    #[test]
+    fn test_to_gexf_with_extra_node_attributes() {
+        crate::tests::setup();
+        let graph_data = get_test_graph_data();
+        let chn_addr = graph_data.chn_addrs[0];
+
+        let extra_node_attributes = ExtraNodeAttributes {
+            graph_attributes: vec![("embedding_type".to_string(), "test".to_string())],
+            columns: {
+                let mut columns = Columns::new();
+                columns.push("chunk_ptrs", ColumnType::Int);
+                columns.push("mean", ColumnType::Float);
+                columns
+            },
+            node_addr_to_values: vec![
+                (chn_addr, vec![SampleValue::Int(3), SampleValue::Float(0.5)]),
+            ].into_iter().collect(),
+        };
+        let gexf_str = graph_data.to_gexf(Some(&extra_node_attributes));
+        assert!(gexf_str.contains("<description>embedding_type: test</description>"));
+        assert!(gexf_str.contains(&format!(
+            "<attribute id=\"{}\" title=\"emb_chunk_ptrs\" type=\"long\"/>", NODE_ATTRIBUTES.len()
+        )));
+        assert!(gexf_str.contains(&format!(
+            "<attribute id=\"{}\" title=\"emb_mean\" type=\"double\"/>", NODE_ATTRIBUTES.len() + 1
+        )));
+        assert!(gexf_str.contains(&format!("<attvalue for=\"{}\" value=\"0.5\"/>", NODE_ATTRIBUTES.len() + 1)));
+        assert_eq!(gexf_str.matches("<node id=").count(), graph_data.graph.node_count());
+        assert_eq!(gexf_str.matches("<edge id=").count(), graph_data.graph.edge_count());
+    }
+}
@@ -1,11 +1,13 @@
+use aho_corasick::AhoCorasick;
 use serde_json::Value;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 
 
-use crate::graph_structs::annotations::KeyDataJSON;
+use crate::graph_structs::annotations::{KeyDataJSON, KeyPatternJSON};
+use crate::params::argv::KeyAnnotationSource;
 use crate::utils::{self, json_value_to_addr, json_value_to_usize, json_value_for_key, ErrorKind};
 use crate::params::BLOCK_BYTE_SIZE;
 
@@ -17,6 +19,10 @@ pub struct HeapDumpData {
     pub max_addr: u64,
     pub json_data: Value,
     pub addr_to_key_data: HashMap<u64, KeyDataJSON>,
+    /// keys to search in the whole heap dump (only for byte pattern search)
+    pub key_patterns: Vec<KeyPatternJSON>,
+    /// multi-pattern search of the key patterns, built once they are loaded (None without key pattern)
+    pub key_pattern_searcher: Option<AhoCorasick>,
 
     // special addresses
     pub addr_ssh_struct: Option<u64>,
@@ -28,10 +34,14 @@ impl HeapDumpData {
     /// Constructor for HeapDumpData
     /// It contains data for a given heap dump file, 
     /// some of them are obtained from the associated json file
+    /// NOTE : With a byte pattern search, only the key bytes are loaded from the json (no KEY_X_ADDR needed),
+    /// or from the key patterns file if given (see load_key_patterns_file)
     pub fn new(
         heap_dump_raw_file_path: PathBuf,
         block_size: usize,
         annotation : bool,
+        key_annotation_source : KeyAnnotationSource,
+        key_patterns_file : Option<&Path>,
     ) -> Result<HeapDumpData, crate::utils::ErrorKind>  {
         // check if file exists
         if !heap_dump_raw_file_path.exists() {
@@ -59,13 +69,24 @@ impl HeapDumpData {
         
         
         let (min_addr, max_addr) = HeapDumpData::get_min_max_addr(&json_data, blocks.len(), block_size)?;
-        let addr_to_key_data;
+        let mut addr_to_key_data = HashMap::new();
+        let mut key_patterns = Vec::new();
         if annotation {
-            addr_to_key_data = generate_key_data_from_json(&json_data)?;
-        } else {
-            addr_to_key_data = HashMap::new();
+            match key_annotation_source {
+                KeyAnnotationSource::JsonAddress => {
+                    addr_to_key_data = generate_key_data_from_json(&json_data)?;
+                },
+                KeyAnnotationSource::BytePatternSearch => {
+                    key_patterns = match key_patterns_file {
+                        Some(key_patterns_file) => load_key_patterns_file(key_patterns_file)?,
+                        None => generate_key_patterns_from_json(&json_data)?,
+                    };
+                },
+            }
         }
 
+        let key_pattern_searcher = new_key_pattern_searcher(&key_patterns)?;
+
         // special addresses
         let addr_ssh_struct;
         let addr_session_state;
@@ -85,11 +106,36 @@ impl HeapDumpData {
             max_addr,
             json_data,
             addr_to_key_data,
+            key_patterns,
+            key_pattern_searcher,
             addr_ssh_struct,
             addr_session_state,
         })
     }
 
+    /// Constructor for the HeapDumpData of a saved graph, without the blocks nor the JSON data.
+    /// WARN: The graph can't be built nor annotated again from it.
+    pub fn new_without_blocks(
+        heap_dump_raw_file_path: PathBuf,
+        block_size: usize,
+        min_addr: u64,
+        max_addr: u64,
+    ) -> HeapDumpData {
+        HeapDumpData {
+            block_size,
+            blocks: Vec::new(),
+            heap_dump_raw_file_path,
+            min_addr,
+            max_addr,
+            json_data: Value::Null,
+            addr_to_key_data: HashMap::new(),
+            key_patterns: Vec::new(),
+            key_pattern_searcher: None,
+            addr_ssh_struct: None,
+            addr_session_state: None,
+        }
+    }
+
     #[cfg(test)]
     pub fn addr_to_index_wrapper(&self, addr: u64) -> usize {
         crate::utils::addr_to_index(addr, self.min_addr, self.block_size)
@@ -101,7 +147,7 @@ impl HeapDumpData {
 
     /// load json file
     fn get_json_data(json_file_path: &PathBuf) -> Result<Value, std::io::Error> {
-        let file = File::open(json_file_path).unwrap();
+        let file = File::open(json_file_path)?;
         let reader = BufReader::new(file);
         let res = serde_json::from_reader(reader)?;
         Ok(res)
@@ -132,6 +178,54 @@ impl HeapDumpData {
 
 
 
+}
+
+/// true if the JSON key is the bytes of a key, ex: "KEY_A", "KEY_CLIENT_IV"
+/// NOTE: The KEY_X_ADDR, KEY_X_LEN and KEY_X_REAL_LEN entries are the metadata of the key KEY_X.
+/// NOTE: Only used by the byte pattern search, the JSON address mode only reads the one-letter KEY_X entries
+/// (see generate_key_data_from_json).
+fn is_json_key_name(json_key: &str) -> bool {
+    json_key.starts_with("KEY_") && !json_key.ends_with("_ADDR") && !json_key.ends_with("_LEN")
+}
+
+/// decode the hex bytes of a key
+fn decode_key_hex(key_name: &str, key_hex: &str) -> Result<Vec<u8>, ErrorKind> {
+    hex::decode(key_hex.trim()).map_err(
+        |error| ErrorKind::InvalidKeyPattern(key_name.to_string(), format!("invalid hex {:?}: {}", key_hex, error))
+    )
+}
+
+/// hex string of a key in the JSON file
+fn json_value_to_key_hex<'a>(key_name: &str, json_value: &'a Value) -> Result<&'a str, ErrorKind> {
+    json_value.as_str().ok_or_else(
+        || ErrorKind::InvalidKeyPattern(key_name.to_string(), format!("not a hex string: {}", json_value))
+    )
+}
+
+/// create a key pattern from its hex bytes
+/// WARN: An empty pattern is an error, it would match at every offset of the heap dump.
+fn new_key_pattern(key_name: &str, key_hex: &str) -> Result<KeyPatternJSON, ErrorKind> {
+    let key = decode_key_hex(key_name, key_hex)?;
+    if key.is_empty() {
+        return Err(ErrorKind::InvalidKeyPattern(key_name.to_string(), "empty pattern".to_string()));
+    }
+    Ok(KeyPatternJSON {
+        name: key_name.to_string(),
+        key,
+    })
+}
+
+/// build the search of all the key patterns at once (Aho-Corasick), None without key pattern
+fn new_key_pattern_searcher(key_patterns: &[KeyPatternJSON]) -> Result<Option<AhoCorasick>, ErrorKind> {
+    if key_patterns.is_empty() {
+        return Ok(None);
+    }
+    AhoCorasick::new(key_patterns.iter().map(|pattern| &pattern.key))
+        .map(Some)
+        .map_err(|error| ErrorKind::InvalidKeyPattern(
+            key_patterns.iter().map(|pattern| pattern.name.as_str()).collect::<Vec<&str>>().join(", "),
+            format!("cannot build the search of the patterns: {}", error),
+        ))
 }
 
 /// Generate a dictionary of key data from the JSON file.
@@ -154,15 +248,58 @@ fn generate_key_data_from_json(
     Ok(addr_key_pairs)
 }
 
+/// Generate the list of key bytes from the JSON file.
+/// Only the KEY_X entries are needed, the addresses and lengths are ignored.
+fn generate_key_patterns_from_json(
+    json_data: &Value,
+) -> Result<Vec<KeyPatternJSON>, ErrorKind> {
+    let mut key_patterns: Vec<KeyPatternJSON> = Vec::new();
+
+    for (json_key, json_value) in json_data.as_object().unwrap().iter() {
+        if is_json_key_name(json_key) {
+            key_patterns.push(new_key_pattern(json_key, json_value_to_key_hex(json_key, json_value)?)?);
+        }
+    }
+
+    log::debug!("Number of key patterns in JSON: {}", key_patterns.len());
+
+    Ok(key_patterns)
+}
+
+/// Load the list of key bytes to search from a text file, one key per line:
+/// its hex bytes, optionally preceded by its name and a space (ex: "CLIENT_KEY 4f2a...").
+/// Empty lines and lines starting with '#' are ignored.
+/// NOTE: An unnamed key is named after its line number, ex: "PATTERN_3".
+pub fn load_key_patterns_file(key_patterns_file: &Path) -> Result<Vec<KeyPatternJSON>, ErrorKind> {
+    let content = std::fs::read_to_string(key_patterns_file).map_err(ErrorKind::Io)?;
+    let mut key_patterns: Vec<KeyPatternJSON> = Vec::new();
+
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let key_pattern = match line.split_once(char::is_whitespace) {
+            Some((key_name, key_hex)) => new_key_pattern(key_name, key_hex)?,
+            None => new_key_pattern(&format!("PATTERN_{}", line_index + 1), line)?,
+        };
+        key_patterns.push(key_pattern);
+    }
+
+    log::debug!("Number of key patterns in {:?}: {}", key_patterns_file, key_patterns.len());
+
+    Ok(key_patterns)
+}
+
 fn generate_key_data_for_a_key(
     json_data: &Value,
     key_name: &str,
 ) -> Result<KeyDataJSON, ErrorKind> {
     let key_value = json_value_for_key(&json_data, key_name.to_string())?;
-    let key_hex: &str = key_value.as_str().unwrap();
+    let key_hex: &str = json_value_to_key_hex(key_name, key_value)?;
 
     let real_key_addr = json_value_to_addr(json_value_for_key(&json_data, (key_name.to_owned() + "_ADDR").to_string())?);
-    let key_bytes: Vec<u8> = hex::decode(key_hex).unwrap();
+    let key_bytes: Vec<u8> = decode_key_hex(key_name, key_hex)?;
 
     let key_size = json_value_to_usize(json_value_for_key(&json_data, (key_name.to_owned() + "_LEN").to_string())?);
     let real_key_len = json_value_to_usize(json_value_for_key(&json_data, (key_name.to_owned() + "_REAL_LEN").to_string())?);
@@ -181,10 +318,7 @@ fn generate_key_data_for_a_key(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{params::{
-        BLOCK_BYTE_SIZE, 
-        TEST_HEAP_DUMP_FILE_PATH
-    }, tests::TEST_HEAP_START_ADDR};
+    use crate::{params::BLOCK_BYTE_SIZE, tests::{TEST_HEAP_START_ADDR, TEST_HEAP_DUMP_FILE_PATH}};
 
     #[test]
     fn test_object_creation() {
@@ -192,7 +326,9 @@ mod tests {
         let heap_dump_data: HeapDumpData = HeapDumpData::new(
             TEST_HEAP_DUMP_FILE_PATH.clone(), 
             BLOCK_BYTE_SIZE,
-            true
+            true,
+            KeyAnnotationSource::JsonAddress,
+            None,
         ).unwrap();
 
         assert_eq!(heap_dump_data.block_size, BLOCK_BYTE_SIZE);
@@ -204,7 +340,7 @@ mod tests {
         crate::tests::setup();
 
         let json_data = HeapDumpData::get_json_data(
-            &crate::params::TEST_HEAP_JSON_FILE_PATH,
+            &crate::tests::TEST_HEAP_JSON_FILE_PATH,
         ).unwrap();
 
         assert!(json_data.is_object());
@@ -228,10 +364,10 @@ mod tests {
         crate::tests::setup();
 
         let blocks = HeapDumpData::generate_blocks_from_heap_dump(
-            &crate::params::TEST_HEAP_DUMP_FILE_PATH, BLOCK_BYTE_SIZE
+            &crate::tests::TEST_HEAP_DUMP_FILE_PATH, BLOCK_BYTE_SIZE
         );
         let json_data = HeapDumpData::get_json_data(
-            &crate::params::TEST_HEAP_JSON_FILE_PATH
+            &crate::tests::TEST_HEAP_JSON_FILE_PATH
         ).unwrap();
         let (min_addr, max_addr) = HeapDumpData::get_min_max_addr(
             &json_data, blocks.len(), BLOCK_BYTE_SIZE).unwrap();
@@ -245,7 +381,9 @@ mod tests {
         let heap_dump_data = HeapDumpData::new(
             TEST_HEAP_DUMP_FILE_PATH.clone(), 
             BLOCK_BYTE_SIZE,
-            true
+            true,
+            KeyAnnotationSource::JsonAddress,
+            None,
         ).unwrap();
         let addr = heap_dump_data.min_addr + 2 * BLOCK_BYTE_SIZE as u64;
 
@@ -260,7 +398,7 @@ mod tests {
         crate::tests::setup();
         
         let json_data = HeapDumpData::get_json_data(
-            &*crate::params::TEST_HEAP_JSON_FILE_PATH
+            &*crate::tests::TEST_HEAP_JSON_FILE_PATH
         ).unwrap();
         let addr_to_key_data = generate_key_data_from_json(&json_data).unwrap();
 
@@ -270,4 +408,72 @@ mod tests {
         assert!(addr_to_key_data.get(&*crate::tests::TEST_KEY_F_ADDR).is_some());
         assert!(addr_to_key_data.get(&*crate::tests::TEST_KEY_F_ADDR).unwrap().key == *crate::tests::TEST_KEY_F_BYTES);
     }
+
+    #[test]
+    fn test_generate_key_patterns_from_json() {
+        crate::tests::setup();
+        
+        let json_data = HeapDumpData::get_json_data(
+            &crate::tests::TEST_HEAP_JSON_FILE_PATH
+        ).unwrap();
+        let key_patterns = generate_key_patterns_from_json(&json_data).unwrap();
+
+        assert_eq!(key_patterns.len(), 6); // 6 keys, from A to F
+
+        // test key F
+        let key_f = key_patterns.iter().find(|pattern| pattern.name == *crate::tests::TEST_KEY_F_NAME);
+        assert!(key_f.is_some());
+        assert!(key_f.unwrap().key == *crate::tests::TEST_KEY_F_BYTES);
+    }
+
+    #[test]
+    fn test_generate_key_patterns_from_json_errors() {
+        let json_data: Value = serde_json::from_str(r#"{"KEY_CLIENT_IV": "0a0b", "KEY_CLIENT_IV_LEN": 2}"#).unwrap();
+        let key_patterns = generate_key_patterns_from_json(&json_data).unwrap();
+        assert_eq!(key_patterns.len(), 1);
+        assert_eq!(key_patterns[0].name, "KEY_CLIENT_IV");
+        assert_eq!(key_patterns[0].key, vec![0x0a, 0x0b]);
+        // the JSON address mode only reads the one-letter keys, the other ones don't need an address
+        assert!(generate_key_data_from_json(&json_data).unwrap().is_empty());
+
+        // a single search for all the patterns
+        assert!(new_key_pattern_searcher(&[]).unwrap().is_none());
+        let searcher = new_key_pattern_searcher(&key_patterns).unwrap().unwrap();
+        let found: Vec<usize> = searcher.find_overlapping_iter(&[0x0a, 0x0a, 0x0b, 0x0a][..]).map(|found| found.start()).collect();
+        assert_eq!(found, vec![1]);
+
+        for invalid_json in [r#"{"KEY_A": "0x0g"}"#, r#"{"KEY_A": 12}"#, r#"{"KEY_A": ""}"#] {
+            let json_data: Value = serde_json::from_str(invalid_json).unwrap();
+            assert!(matches!(
+                generate_key_patterns_from_json(&json_data),
+                Err(ErrorKind::InvalidKeyPattern(key_name, _)) if key_name == "KEY_A"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_load_key_patterns_file() {
+        let key_patterns_file = std::env::temp_dir().join(
+            format!("mem_to_graph_test_key_patterns_{}.txt", std::process::id())
+        );
+
+        std::fs::write(&key_patterns_file, "# keys of the session\nCLIENT_KEY 0a0B0c\n\n  ff00  \n").unwrap();
+        let key_patterns = load_key_patterns_file(&key_patterns_file).unwrap();
+        assert_eq!(key_patterns.len(), 2);
+        assert_eq!(key_patterns[0].name, "CLIENT_KEY");
+        assert_eq!(key_patterns[0].key, vec![0x0a, 0x0b, 0x0c]);
+        assert_eq!(key_patterns[1].name, "PATTERN_4");
+        assert_eq!(key_patterns[1].key, vec![0xff, 0x00]);
+
+        // invalid hex digit, odd number of digits, space in the bytes
+        for invalid_line in ["CLIENT_KEY 0a0g", "0a0", "CLIENT_KEY 00 11"] {
+            std::fs::write(&key_patterns_file, invalid_line).unwrap();
+            assert!(matches!(
+                load_key_patterns_file(&key_patterns_file),
+                Err(ErrorKind::InvalidKeyPattern(_, _))
+            ));
+        }
+
+        std::fs::remove_file(&key_patterns_file).unwrap();
+    }
 }
@@ -1,15 +1,19 @@
 use petgraph::graphmap::DiGraphMap;
-use std::path::PathBuf;
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
 use log;
 use petgraph::visit::IntoEdgeReferences;
 
 pub mod heap_dump_data;
+pub mod export;
+pub mod saved_graph;
+pub mod dot_import;
 
 use heap_dump_data::HeapDumpData;
-use crate::graph_structs::{self, Node, ChunkHeaderNode, Edge, EdgeType, DEFAULT_CHUNK_EDGE_WEIGHT, parse_chunk_header, HeaderFlags, FooterNode};
+use crate::graph_structs::{self, Node, ChunkHeaderNode, Edge, EdgeType, DEFAULT_CHUNK_EDGE_WEIGHT, parse_chunk_header, HeaderFlags, FooterNode, ValueNode};
 use crate::graph_structs::annotations::AnnotationSet;
 use crate::params::BLOCK_BYTE_SIZE;
+use crate::params::argv::KeyAnnotationSource;
 use crate::utils;
 
 /// macro for getting the heap_dump_data field unwrapped
@@ -37,6 +41,9 @@ pub struct GraphData {
     /// if the graph doesn't contain value nodes
     pub no_value_node: bool,
 
+    /// number of bytes at the start of the user data of a chunk used to compute its entropy
+    pub chunk_nb_of_start_bytes_for_chunk_entropy: usize,
+
     pub heap_dump_data: Option<HeapDumpData>, // Some because it is an optional field, for testing purposes
 }
 
@@ -49,7 +56,10 @@ impl GraphData {
         heap_dump_raw_file_path: PathBuf, 
         pointer_byte_size: usize,
         annotation : bool,
-        without_pointer_node : bool
+        key_annotation_source : KeyAnnotationSource,
+        key_patterns_file : Option<&Path>,
+        without_pointer_node : bool,
+        chunk_nb_of_start_bytes_for_chunk_entropy : usize,
     ) -> Result<Self, crate::utils::ErrorKind> {
         let mut instance = Self {
             graph: DiGraphMap::<u64, graph_structs::Edge>::new(),
@@ -59,11 +69,14 @@ impl GraphData {
             pointer_node_addrs: Vec::new(),
             node_addr_to_annotations: HashMap::new(),
             no_value_node: without_pointer_node,
+            chunk_nb_of_start_bytes_for_chunk_entropy,
             heap_dump_data: Some(
                 HeapDumpData::new(
                     heap_dump_raw_file_path,
                     pointer_byte_size,
                     annotation,
+                    key_annotation_source,
+                    key_patterns_file,
                 )?
             ),
         };
@@ -75,7 +88,7 @@ impl GraphData {
 
     /// Constructor for an empty GraphData
     #[cfg(test)]
-    fn new_empty() -> Self {
+    pub(crate) fn new_empty() -> Self {
         Self {
             graph: DiGraphMap::<u64, graph_structs::Edge>::new(),
             addr_to_node: HashMap::new(),
@@ -84,6 +97,7 @@ impl GraphData {
             pointer_node_addrs: Vec::new(),
             node_addr_to_annotations: HashMap::new(),
             no_value_node: false,
+            chunk_nb_of_start_bytes_for_chunk_entropy: crate::params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
             heap_dump_data: None,
         }
     }
@@ -349,7 +363,8 @@ impl GraphData {
             nb_value_nodes: count_value_nodes,
             start_data_bytes_entropy: utils::compute_chunk_start_bytes_entropy(
                 &self.heap_dump_data.as_ref().unwrap().blocks, 
-                chunk_data_first_block_index
+                chunk_data_first_block_index,
+                self.chunk_nb_of_start_bytes_for_chunk_entropy,
             ),
             chunk_number_in_heap: chunk_number_in_heap,
         });
@@ -457,6 +472,99 @@ impl GraphData {
         }
     }
 
+    //////////////////////////////////////////////////////////////////////////////
+    // ------------------------- Graph repair -------------------------
+
+    /// Convert a PointerNode back to a ValueNode, and remove its spurious pointer edge.
+    /// This is used when we know that the bytes of the node are not a pointer
+    /// (for instance, key bytes that happened to fall in the heap range).
+    /// NOTE: if the graph has no value node, the weight of the edge between the CHNs is decremented instead.
+    /// 
+    /// :return: true if the node has been converted, false if the node is not a PointerNode.
+    pub fn convert_pointer_node_to_value_node(&mut self, addr: u64) -> bool {
+        let (points_to, chn_addr) = match self.addr_to_node.get(&addr) {
+            Some(Node::PointerNode(pointer_node)) => (pointer_node.points_to, pointer_node.chn_addr),
+            _ => return false,
+        };
+
+        // remove the spurious pointer edge
+        if self.no_value_node {
+            // the edge links the CHN of the pointer to the pointed CHN
+            let pointed_addr = self.addr_to_node.get(&points_to)
+                .map(|pointed_node| pointed_node.get_parent_chn_addr().unwrap_or(points_to));
+            if let Some(pointed_addr) = pointed_addr {
+                let remove_edge = match self.graph.edge_weight_mut(chn_addr, pointed_addr) {
+                    Some(edge) => {
+                        edge.weight -= 1;
+                        edge.weight == 0
+                    },
+                    None => false,
+                };
+                if remove_edge {
+                    self.graph.remove_edge(chn_addr, pointed_addr);
+                }
+            }
+        } else {
+            self.graph.remove_edge(addr, points_to);
+        }
+
+        // replace the node
+        self.addr_to_node.insert(addr, Node::ValueNode(ValueNode {
+            addr,
+            value: utils::convert_pointer_to_block(points_to),
+            chn_addr,
+        }));
+        self.pointer_node_addrs.retain(|pointer_addr| *pointer_addr != addr);
+        match self.value_node_addrs.binary_search(&addr) {
+            Ok(_) => {},
+            Err(index) => self.value_node_addrs.insert(index, addr),
+        }
+
+        // update the stats of the parent chunk
+        if let Some(Node::ChunkHeaderNode(chn)) = self.addr_to_node.get_mut(&chn_addr) {
+            chn.nb_pointer_nodes -= 1;
+            chn.nb_value_nodes += 1;
+        }
+
+        true
+    }
+
+    //////////////////////////////////////////////////////////////////////////////
+    // ------------------------- Chunk graph -------------------------
+
+    /// dense index of each chunk, in the order of the chunks in the heap
+    pub fn get_chn_addr_to_index(&self) -> HashMap<u64, usize> {
+        self.chn_addrs.iter()
+            .enumerate()
+            .map(|(index, addr)| (*addr, index))
+            .collect()
+    }
+
+    /// Pointer edges moved to the chunks of their nodes, and merged (their weights are summed),
+    /// by (source chunk index, target chunk index).
+    /// NOTE: The chunk edges are removed (they link a CHN to its own nodes), the pointers from a chunk to itself are kept.
+    pub fn get_chunk_pointer_edges(&self, chn_addr_to_index: &HashMap<u64, usize>) -> BTreeMap<(usize, usize), usize> {
+        let get_chunk_index = |addr: u64| -> usize {
+            let node = self.addr_to_node.get(&addr).unwrap();
+            let chn_addr = match node {
+                Node::ChunkHeaderNode(_) => addr,
+                _ => node.get_parent_chn_addr().expect("The chn addr should be set"),
+            };
+            *chn_addr_to_index.get(&chn_addr).unwrap_or_else(
+                || panic!("🚩 The CHN node [addr: {:#x}] doesn't exist", chn_addr)
+            )
+        };
+
+        let mut edge_to_weight: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for (from_addr, to_addr, edge) in self.graph.edge_references() {
+            if matches!(edge.edge_type, EdgeType::ChunkEdge) {
+                continue;
+            }
+            *edge_to_weight.entry((get_chunk_index(from_addr), get_chunk_index(to_addr))).or_insert(0) += edge.weight;
+        }
+        edge_to_weight
+    }
+
     //////////////////////////////////////////////////////////////////////////////
     /// CUSTOM FORMATTER FOR SPECIFIC GRAPH DISPLAYS /////////////////////////////
 
@@ -696,10 +804,13 @@ mod tests {
         crate::tests::setup();
         
         let graph_data = GraphData::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
             params::BLOCK_BYTE_SIZE,
             true,
-            false
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
         ).unwrap();
         check_heap_dump!(graph_data);
 
@@ -740,10 +851,13 @@ mod tests {
         crate::tests::setup();
         
         let graph_data = GraphData::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
             params::BLOCK_BYTE_SIZE,
             true,
-            false
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
         ).unwrap();
         let node = graph_data.create_node_from_bytes_wrapper_index(
             &*TEST_PTR_1_VALUE_BYTES, 
@@ -752,4 +866,46 @@ mod tests {
         );
         assert_eq!(node.get_address(), *TEST_PTR_1_ADDR);
     }
+    #[test]
+    fn test_convert_pointer_node_to_value_node() {
+        crate::tests::setup();
+        
+        let mut graph_data = GraphData::new(
+            crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone(), 
+            params::BLOCK_BYTE_SIZE,
+            true,
+            KeyAnnotationSource::JsonAddress,
+            None,
+            false,
+            params::DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        ).unwrap();
+        let chn_node = graph_data.addr_to_node.get(&*TEST_MALLOC_HEADER_1_ADDR).unwrap();
+        let (nb_pointer_nodes, nb_value_nodes) = match chn_node {
+            Node::ChunkHeaderNode(chn) => (chn.nb_pointer_nodes, chn.nb_value_nodes),
+            _ => panic!("Node is not a ChunkHeaderNode"),
+        };
+        assert!(graph_data.graph.contains_edge(*TEST_PTR_1_ADDR, *TEST_PTR_1_VALUE));
+
+        assert!(graph_data.convert_pointer_node_to_value_node(*TEST_PTR_1_ADDR));
+
+        let node = graph_data.addr_to_node.get(&*TEST_PTR_1_ADDR).unwrap();
+        assert!(node.is_value());
+        match node {
+            Node::ValueNode(value_node) => assert_eq!(value_node.value, *TEST_PTR_1_VALUE_BYTES),
+            _ => panic!("Node is not a ValueNode"),
+        }
+        assert!(!graph_data.graph.contains_edge(*TEST_PTR_1_ADDR, *TEST_PTR_1_VALUE));
+        assert!(!graph_data.pointer_node_addrs.contains(&*TEST_PTR_1_ADDR));
+        assert!(graph_data.value_node_addrs.binary_search(&*TEST_PTR_1_ADDR).is_ok());
+        match graph_data.addr_to_node.get(&*TEST_MALLOC_HEADER_1_ADDR).unwrap() {
+            Node::ChunkHeaderNode(chn) => {
+                assert_eq!(chn.nb_pointer_nodes, nb_pointer_nodes - 1);
+                assert_eq!(chn.nb_value_nodes, nb_value_nodes + 1);
+            },
+            _ => panic!("Node is not a ChunkHeaderNode"),
+        }
+
+        // already a ValueNode
+        assert!(!graph_data.convert_pointer_node_to_value_node(*TEST_PTR_1_ADDR));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use petgraph::graphmap::DiGraphMap;
+use petgraph::visit::IntoEdgeReferences;
+use serde_derive::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::graph_structs::{Node, Edge};
+use crate::graph_structs::annotations::AnnotationSet;
+use super::GraphData;
+use super::heap_dump_data::HeapDumpData;
+
+/// version of the saved graph format, increased at each breaking change
+pub const SAVED_GRAPH_FORMAT_VERSION: u32 = 1;
+
+/// extension of the saved graph files
+pub const SAVED_GRAPH_FILE_EXTENSION: &str = "graph.json";
+
+/// What is kept of the heap dump data in a saved graph (no blocks, no JSON data)
+#[derive(Serialize, Deserialize)]
+pub struct SavedHeapDumpData {
+    pub heap_dump_raw_file_path: PathBuf,
+    pub block_size: usize,
+    pub min_addr: u64,
+    pub max_addr: u64,
+}
+
+/// Serializable form of a GraphData.
+/// NOTE: The nodes and edges of the graph are kept in their insertion order,
+/// so that a reloaded graph is iterated (and exported) in the same order.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGraphData {
+    /// all the nodes, sorted by address
+    pub nodes: Vec<Node>,
+    /// addresses of the nodes of the graph, in insertion order
+    pub graph_node_addrs: Vec<u64>,
+    /// edges of the graph, in insertion order
+    pub edges: Vec<Edge>,
+    pub chn_addrs: Vec<u64>,
+    pub value_node_addrs: Vec<u64>,
+    pub pointer_node_addrs: Vec<u64>,
+    /// annotations, sorted by node address
+    pub node_addr_to_annotations: Vec<(u64, AnnotationSet)>,
+    pub no_value_node: bool,
+    pub chunk_nb_of_start_bytes_for_chunk_entropy: usize,
+    pub heap_dump_data: Option<SavedHeapDumpData>,
+}
+
+impl GraphData {
+    pub fn to_saved_graph_data(&self) -> SavedGraphData {
+        let mut nodes: Vec<Node> = self.addr_to_node.values().cloned().collect();
+        nodes.sort_by_key(|node| node.get_address());
+
+        let edges = self.graph.edge_references()
+            .map(|(_, _, edge)| edge.clone())
+            .collect();
+
+        let mut node_addr_to_annotations: Vec<(u64, AnnotationSet)> = self.node_addr_to_annotations.iter()
+            .map(|(addr, annotation_set)| (*addr, annotation_set.clone()))
+            .collect();
+        node_addr_to_annotations.sort_by_key(|(addr, _)| *addr);
+
+        SavedGraphData {
+            nodes,
+            graph_node_addrs: self.graph.nodes().collect(),
+            edges,
+            chn_addrs: self.chn_addrs.clone(),
+            value_node_addrs: self.value_node_addrs.clone(),
+            pointer_node_addrs: self.pointer_node_addrs.clone(),
+            node_addr_to_annotations,
+            no_value_node: self.no_value_node,
+            chunk_nb_of_start_bytes_for_chunk_entropy: self.chunk_nb_of_start_bytes_for_chunk_entropy,
+            heap_dump_data: self.heap_dump_data.as_ref().map(|heap_dump_data| SavedHeapDumpData {
+                heap_dump_raw_file_path: heap_dump_data.heap_dump_raw_file_path.clone(),
+                block_size: heap_dump_data.block_size,
+                min_addr: heap_dump_data.min_addr,
+                max_addr: heap_dump_data.max_addr,
+            }),
+        }
+    }
+
+    /// Rebuild the GraphData from its serializable form.
+    /// WARN: The heap dump data has no blocks nor JSON data (see HeapDumpData::new_without_blocks).
+    pub fn from_saved_graph_data(saved_graph_data: SavedGraphData) -> GraphData {
+        let mut graph = DiGraphMap::<u64, Edge>::new();
+        for addr in saved_graph_data.graph_node_addrs {
+            graph.add_node(addr);
+        }
+        for edge in saved_graph_data.edges {
+            graph.add_edge(edge.from, edge.to, edge);
+        }
+
+        GraphData {
+            graph,
+            addr_to_node: saved_graph_data.nodes.into_iter()
+                .map(|node| (node.get_address(), node))
+                .collect(),
+            chn_addrs: saved_graph_data.chn_addrs,
+            value_node_addrs: saved_graph_data.value_node_addrs,
+            pointer_node_addrs: saved_graph_data.pointer_node_addrs,
+            node_addr_to_annotations: saved_graph_data.node_addr_to_annotations.into_iter()
+                .collect::<HashMap<u64, AnnotationSet>>(),
+            no_value_node: saved_graph_data.no_value_node,
+            chunk_nb_of_start_bytes_for_chunk_entropy: saved_graph_data.chunk_nb_of_start_bytes_for_chunk_entropy,
+            heap_dump_data: saved_graph_data.heap_dump_data.map(|heap_dump_data| HeapDumpData::new_without_blocks(
+                heap_dump_data.heap_dump_raw_file_path,
+                heap_dump_data.block_size,
+                heap_dump_data.min_addr,
+                heap_dump_data.max_addr,
+            )),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::graph_annotate::GraphAnnotate;
+use crate::utils::{get_n_gram_columns, ErrorKind, MAX_N_GRAM};
+use crate::params::{
+    BLOCK_BYTE_SIZE,
+    DEFAULT_EMBEDDING_DEPTH,
+    DEFAULT_N_GRAM,
+    DEFAULT_CHUNK_BYTES_SIZE_TO_KEEP_FILTER,
+    DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+    DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING,
+    DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP,
+    DEFAULT_WL_NB_OF_HASH_BINS,
+    DEFAULT_BETWEENNESS_NB_OF_SOURCES,
+    DEFAULT_BYTE_BIGRAM_NB_OF_BUCKETS,
+    DEFAULT_VALUE_NODE_WINDOW_NB_OF_BLOCKS,
+    DEFAULT_CHUNK_LAYOUT_NB_OF_BLOCKS,
+};
+use crate::params::argv::{SelectAnnotationLocation, EntropyFilter, ChunkByteSizeFilter, KeyAnnotationSource, Pipeline, GraphFormat};
+
+use super::GraphEmbedding;
+use super::entropy_profile::EntropyProfileParams;
+use super::random_walk::RandomWalkParams;
+use super::embedding::chunk_byte_histogram_embedding::NB_OF_BYTE_BIGRAMS;
+
+/// Parameters of the construction of the graph of a heap dump
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphParams {
+    pub pointer_byte_size: usize,
+    pub without_value_node: bool,
+    /// number of bytes at the start of the user data of a chunk used to compute its entropy
+    pub chunk_nb_of_start_bytes_for_chunk_entropy: usize,
+}
+
+impl Default for GraphParams {
+    fn default() -> Self {
+        GraphParams {
+            pointer_byte_size: BLOCK_BYTE_SIZE,
+            without_value_node: false,
+            chunk_nb_of_start_bytes_for_chunk_entropy: DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        }
+    }
+}
+
+/// Parameters of the annotation of the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationParams {
+    pub annotation: SelectAnnotationLocation,
+    pub key_annotation_source: KeyAnnotationSource,
+    /// keys of the byte pattern search, instead of the keys of the JSON files (see load_key_patterns_file)
+    pub key_patterns_file: Option<PathBuf>,
+    pub repair_key_pointer_nodes: bool,
+}
+
+impl Default for AnnotationParams {
+    fn default() -> Self {
+        AnnotationParams {
+            annotation: SelectAnnotationLocation::ValueNode,
+            key_annotation_source: KeyAnnotationSource::JsonAddress,
+            key_patterns_file: None,
+            repair_key_pointer_nodes: false,
+        }
+    }
+}
+
+/// Parameters of the filters of the chunks of the embeddings
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterParams {
+    pub entropy_filter: EntropyFilter,
+    pub min_nb_of_chunks_to_keep: usize,
+    pub chunk_byte_size_filter: ChunkByteSizeFilter,
+    pub chunk_bytes_size_to_keep_filter: HashSet<usize>,
+}
+
+impl Default for FilterParams {
+    fn default() -> Self {
+        FilterParams {
+            entropy_filter: EntropyFilter::None,
+            min_nb_of_chunks_to_keep: DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP,
+            chunk_byte_size_filter: ChunkByteSizeFilter::None,
+            chunk_bytes_size_to_keep_filter: DEFAULT_CHUNK_BYTES_SIZE_TO_KEEP_FILTER.into_iter().collect(),
+        }
+    }
+}
+
+/// Parameters of the embeddings, each one only used by some of them (see the setters of GraphEmbeddingBuilder)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingParams {
+    pub depth: usize,
+    /// ascending order
+    pub n_gram: Vec<usize>,
+    pub chunk_nb_of_start_bytes_for_chunk_embedding: usize,
+    pub wl_nb_of_hash_bins: usize,
+    pub betweenness_nb_of_sources: usize,
+    pub byte_bigram_nb_of_buckets: usize,
+    pub value_node_window_nb_of_blocks: usize,
+    pub chunk_layout_nb_of_blocks: usize,
+    pub random_walk: RandomWalkParams,
+    pub entropy_profile: EntropyProfileParams,
+}
+
+impl Default for EmbeddingParams {
+    fn default() -> Self {
+        EmbeddingParams {
+            depth: DEFAULT_EMBEDDING_DEPTH,
+            n_gram: DEFAULT_N_GRAM.to_vec(),
+            chunk_nb_of_start_bytes_for_chunk_embedding: DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING,
+            wl_nb_of_hash_bins: DEFAULT_WL_NB_OF_HASH_BINS,
+            betweenness_nb_of_sources: DEFAULT_BETWEENNESS_NB_OF_SOURCES,
+            byte_bigram_nb_of_buckets: DEFAULT_BYTE_BIGRAM_NB_OF_BUCKETS,
+            value_node_window_nb_of_blocks: DEFAULT_VALUE_NODE_WINDOW_NB_OF_BLOCKS,
+            chunk_layout_nb_of_blocks: DEFAULT_CHUNK_LAYOUT_NB_OF_BLOCKS,
+            random_walk: RandomWalkParams::default(),
+            entropy_profile: EntropyProfileParams::default(),
+        }
+    }
+}
+
+/// Parameters of the graph files saved by the graph generation pipelines
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphOutputParams {
+    /// embedding used for the comments of the graph generation with embedding comments
+    pub graph_comment_embedding_type: Pipeline,
+    pub graph_format: GraphFormat,
+}
+
+impl Default for GraphOutputParams {
+    fn default() -> Self {
+        GraphOutputParams {
+            graph_comment_embedding_type: Pipeline::ChunkSemanticEmbedding,
+            graph_format: GraphFormat::Dot,
+        }
+    }
+}
+
+/// Builder of GraphEmbedding.
+/// Holds all the parameters of the graph construction, annotation and embedding, grouped by concern,
+/// so that the library doesn't depend on the CLI arguments nor on the environment variables.
+///
+/// NOTE: The same builder can be used to build the GraphEmbedding of several heap dump files.
+///
+/// ex:
+/// ```ignore
+/// let graph_embedding = GraphEmbeddingBuilder::new()
+///     .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+///     .entropy_filter(EntropyFilter::OnlyMaxEntropy)
+///     .build(heap_dump_raw_file_path)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GraphEmbeddingBuilder {
+    graph_params: GraphParams,
+    annotation_params: AnnotationParams,
+    filter_params: FilterParams,
+    embedding_params: EmbeddingParams,
+    graph_output_params: GraphOutputParams,
+}
+
+impl GraphEmbeddingBuilder {
+    /// Builder with the default parameters (the same as the CLI defaults)
+    pub fn new() -> GraphEmbeddingBuilder {
+        GraphEmbeddingBuilder::default()
+    }
+
+    pub fn pointer_byte_size(mut self, pointer_byte_size: usize) -> Self {
+        self.graph_params.pointer_byte_size = pointer_byte_size;
+        self
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.embedding_params.depth = depth;
+        self
+    }
+
+    pub fn entropy_filter(mut self, entropy_filter: EntropyFilter) -> Self {
+        self.filter_params.entropy_filter = entropy_filter;
+        self
+    }
+
+    /// NOTE: only used by EntropyFilter::MinOfChunkTresholdEntropy
+    pub fn min_nb_of_chunks_to_keep(mut self, min_nb_of_chunks_to_keep: usize) -> Self {
+        self.filter_params.min_nb_of_chunks_to_keep = min_nb_of_chunks_to_keep;
+        self
+    }
+
+    pub fn chunk_byte_size_filter(mut self, chunk_byte_size_filter: ChunkByteSizeFilter) -> Self {
+        self.filter_params.chunk_byte_size_filter = chunk_byte_size_filter;
+        self
+    }
+
+    /// NOTE: only used by ChunkByteSizeFilter::Activate
+    pub fn chunk_bytes_size_to_keep_filter(mut self, chunk_bytes_size_to_keep_filter: HashSet<usize>) -> Self {
+        self.filter_params.chunk_bytes_size_to_keep_filter = chunk_bytes_size_to_keep_filter;
+        self
+    }
+
+    pub fn annotation(mut self, annotation: SelectAnnotationLocation) -> Self {
+        self.annotation_params.annotation = annotation;
+        self
+    }
+
+    pub fn get_annotation(&self) -> SelectAnnotationLocation {
+        self.annotation_params.annotation
+    }
+
+    pub fn key_annotation_source(mut self, key_annotation_source: KeyAnnotationSource) -> Self {
+        self.annotation_params.key_annotation_source = key_annotation_source;
+        self
+    }
+
+    /// NOTE: only used by the byte pattern search, instead of the keys of the JSON files
+    /// (see load_key_patterns_file)
+    pub fn key_patterns_file(mut self, key_patterns_file: Option<PathBuf>) -> Self {
+        self.annotation_params.key_patterns_file = key_patterns_file;
+        self
+    }
+
+    /// NOTE: only used if the graph is annotated
+    pub fn repair_key_pointer_nodes(mut self, repair_key_pointer_nodes: bool) -> Self {
+        self.annotation_params.repair_key_pointer_nodes = repair_key_pointer_nodes;
+        self
+    }
+
+    pub fn without_value_node(mut self, without_value_node: bool) -> Self {
+        self.graph_params.without_value_node = without_value_node;
+        self
+    }
+
+    /// NOTE: the n-gram are sorted in ascending order, each between 1 and MAX_N_GRAM bits
+    pub fn n_gram(mut self, mut n_gram: Vec<usize>) -> Self {
+        assert!(
+            n_gram.iter().all(|n| *n > 0 && *n <= MAX_N_GRAM),
+            "🚩 The n-gram sizes must be between 1 and {} bits", MAX_N_GRAM
+        );
+        n_gram.sort();
+        self.embedding_params.n_gram = n_gram;
+        self
+    }
+
+    pub fn chunk_nb_of_start_bytes_for_chunk_entropy(mut self, nb_start_bytes: usize) -> Self {
+        self.graph_params.chunk_nb_of_start_bytes_for_chunk_entropy = nb_start_bytes;
+        self
+    }
+
+    pub fn chunk_nb_of_start_bytes_for_chunk_embedding(mut self, nb_start_bytes: usize) -> Self {
+        self.embedding_params.chunk_nb_of_start_bytes_for_chunk_embedding = nb_start_bytes;
+        self
+    }
+
+    /// NOTE: only used by the chunk WL subtree embedding
+    pub fn wl_nb_of_hash_bins(mut self, wl_nb_of_hash_bins: usize) -> Self {
+        assert!(wl_nb_of_hash_bins > 0, "🚩 The number of WL hash bins must be positive");
+        self.embedding_params.wl_nb_of_hash_bins = wl_nb_of_hash_bins;
+        self
+    }
+
+    /// NOTE: only used by the chunk centrality embedding
+    pub fn betweenness_nb_of_sources(mut self, betweenness_nb_of_sources: usize) -> Self {
+        self.embedding_params.betweenness_nb_of_sources = betweenness_nb_of_sources;
+        self
+    }
+
+    /// NOTE: only used by the chunk byte histogram embedding,
+    /// at most NB_OF_BYTE_BIGRAMS (a bucket per bigram, no hash)
+    pub fn byte_bigram_nb_of_buckets(mut self, byte_bigram_nb_of_buckets: usize) -> Self {
+        assert!(
+            byte_bigram_nb_of_buckets > 0 && byte_bigram_nb_of_buckets <= NB_OF_BYTE_BIGRAMS,
+            "🚩 The number of byte bigram buckets must be between 1 and {}", NB_OF_BYTE_BIGRAMS
+        );
+        self.embedding_params.byte_bigram_nb_of_buckets = byte_bigram_nb_of_buckets;
+        self
+    }
+
+    /// NOTE: only used by the value node window embedding
+    pub fn value_node_window_nb_of_blocks(mut self, value_node_window_nb_of_blocks: usize) -> Self {
+        self.embedding_params.value_node_window_nb_of_blocks = value_node_window_nb_of_blocks;
+        self
+    }
+
+    /// NOTE: only used by the chunk layout embedding
+    pub fn chunk_layout_nb_of_blocks(mut self, chunk_layout_nb_of_blocks: usize) -> Self {
+        self.embedding_params.chunk_layout_nb_of_blocks = chunk_layout_nb_of_blocks;
+        self
+    }
+
+    /// NOTE: only used by the random walk pipelines
+    pub fn random_walk_params(mut self, random_walk_params: RandomWalkParams) -> Self {
+        assert!(random_walk_params.p > 0.0 && random_walk_params.q > 0.0, "🚩 The random walk p and q must be positive");
+        self.embedding_params.random_walk = random_walk_params;
+        self
+    }
+
+    /// NOTE: only used by the chunk entropy profile embedding
+    pub fn entropy_profile_params(mut self, entropy_profile_params: EntropyProfileParams) -> Self {
+        assert!(
+            entropy_profile_params.window_nb_of_blocks > 0 && entropy_profile_params.profile_length > 0,
+            "🚩 The entropy profile window and length must be positive"
+        );
+        self.embedding_params.entropy_profile = entropy_profile_params;
+        self
+    }
+
+    /// NOTE: only used for the graph generation with embedding comments
+    pub fn graph_comment_embedding_type(mut self, graph_comment_embedding_type: Pipeline) -> Self {
+        self.graph_output_params.graph_comment_embedding_type = graph_comment_embedding_type;
+        self
+    }
+
+    /// NOTE: only used for the graph generation pipelines
+    pub fn graph_format(mut self, graph_format: GraphFormat) -> Self {
+        self.graph_output_params.graph_format = graph_format;
+        self
+    }
+
+    /// Build the GraphEmbedding of a given heap dump file:
+    /// construct the graph, annotate it and prepare the filters.
+    pub fn build(&self, heap_dump_raw_file_path: PathBuf) -> Result<GraphEmbedding, crate::utils::ErrorKind> {
+        let graph_annotate = GraphAnnotate::new(
+            heap_dump_raw_file_path,
+            self.graph_params.pointer_byte_size,
+            self.annotation_params.annotation,
+            self.annotation_params.key_annotation_source,
+            self.annotation_params.key_patterns_file.as_deref(),
+            self.graph_params.without_value_node,
+            self.graph_params.chunk_nb_of_start_bytes_for_chunk_entropy,
+        )?;
+        Ok(self.build_from_graph_annotate(graph_annotate))
+    }
+
+    /// true if the graphs built by the two builders only differ by their annotation location,
+    /// so that a graph built by one can be reused by the other (see build_from_graph_annotate)
+    pub fn has_same_graph_as(&self, other: &GraphEmbeddingBuilder) -> bool {
+        let (annotation_params, other_annotation_params) = (&self.annotation_params, &other.annotation_params);
+        let is_annotated = annotation_params.annotation != SelectAnnotationLocation::None;
+        let other_is_annotated = other_annotation_params.annotation != SelectAnnotationLocation::None;
+        self.graph_params == other.graph_params
+            && is_annotated == other_is_annotated
+            && (!is_annotated || (
+                annotation_params.key_annotation_source == other_annotation_params.key_annotation_source
+                && annotation_params.key_patterns_file == other_annotation_params.key_patterns_file
+                && annotation_params.repair_key_pointer_nodes == other_annotation_params.repair_key_pointer_nodes
+            ))
+    }
+
+    /// Build the GraphEmbedding from an already built graph:
+    /// annotate it again if needed and prepare the filters.
+    /// WARN: The graph must have been built by a builder with the same graph (see has_same_graph_as).
+    pub fn build_from_graph_annotate(&self, mut graph_annotate: GraphAnnotate) -> GraphEmbedding {
+        let annotation = self.annotation_params.annotation;
+        graph_annotate.reannotate(annotation);
+        if self.annotation_params.repair_key_pointer_nodes && annotation != SelectAnnotationLocation::None {
+            graph_annotate.repair_key_pointer_nodes();
+        }
+        self.embed_graph_annotate(graph_annotate)
+    }
+
+    /// Build the GraphEmbedding from a graph saved by GraphAnnotate::save, without the heap dump:
+    /// only prepare the filters.
+    /// NOTE: The graph parameters are the ones of the saved graph. The annotation location can't be changed.
+    pub fn build_from_saved_graph(&self, saved_graph_file_path: &Path) -> Result<GraphEmbedding, ErrorKind> {
+        let graph_annotate = GraphAnnotate::load(saved_graph_file_path)?;
+        if graph_annotate.annotation != self.annotation_params.annotation {
+            return Err(ErrorKind::InvalidSavedGraph(
+                saved_graph_file_path.to_path_buf(),
+                format!(
+                    "annotation {:?} instead of {:?} (a saved graph can't be annotated again)",
+                    graph_annotate.annotation, self.annotation_params.annotation
+                ),
+            ));
+        }
+        Ok(self.embed_graph_annotate(graph_annotate))
+    }
+
+    /// Build the GraphEmbedding from a dot file of the graph pipelines, without the heap dump:
+    /// only prepare the filters.
+    /// NOTE: The annotation location of the dot file can't be changed.
+    /// WARN: The dot files don't hold the bytes of the heap dump (see GraphData::from_dot_file),
+    /// the embeddings using the values of the blocks are meaningless.
+    pub fn build_from_dot_file(&self, dot_file_path: &Path) -> Result<GraphEmbedding, ErrorKind> {
+        let graph_annotate = GraphAnnotate::from_dot_file(dot_file_path)?;
+        if graph_annotate.annotation != self.annotation_params.annotation {
+            return Err(ErrorKind::InvalidDotFile(
+                dot_file_path.to_path_buf(),
+                format!(
+                    "annotation {:?} instead of {:?} (a dot file can't be annotated again)",
+                    graph_annotate.annotation, self.annotation_params.annotation
+                ),
+            ));
+        }
+        Ok(self.embed_graph_annotate(graph_annotate))
+    }
+
+    /// Prepare the filters of the embedding of an annotated graph
+    fn embed_graph_annotate(&self, graph_annotate: GraphAnnotate) -> GraphEmbedding {
+        let mut graph_embedding = GraphEmbedding {
+            graph_annotate,
+            entropy_treshold: None,
+            chunk_bytes_size_to_keep_filter: None,
+            n_gram_columns: get_n_gram_columns(&self.embedding_params.n_gram),
+            embedding_params: self.embedding_params.clone(),
+            graph_output_params: self.graph_output_params.clone(),
+        };
+
+        graph_embedding.chunk_bytes_size_to_keep_filter = match self.filter_params.chunk_byte_size_filter {
+            ChunkByteSizeFilter::Activate => Some(self.filter_params.chunk_bytes_size_to_keep_filter.clone()),
+            ChunkByteSizeFilter::None => None,
+        };
+        graph_embedding.entropy_treshold = graph_embedding.calculate_entropy_treshold(
+            self.filter_params.entropy_filter, self.filter_params.min_nb_of_chunks_to_keep
+        );
+
+        graph_embedding
+    }
+}
@@ -0,0 +1,283 @@
+use petgraph::graphmap::DiGraphMap;
+use std::collections::{HashMap, VecDeque};
+
+use crate::graph_data::GraphData;
+
+/// damping factor of the PageRank
+pub const PAGERANK_DAMPING: f64 = 0.85;
+/// maximum number of iterations of the PageRank and of the HITS
+pub const CENTRALITY_MAX_NB_OF_ITERATIONS: usize = 100;
+/// the PageRank and the HITS stop when the L1 change of their scores is below this tolerance
+pub const CENTRALITY_TOLERANCE: f64 = 1e-10;
+
+/// Graph of the chunks: the pointers of the nodes of a chunk are moved to the chunk,
+/// and the pointers between two chunks are merged (their weights are summed).
+/// It is the graph built with no_value_node, whatever the graph it comes from.
+/// NOTE: The chunks have dense indices, in the order of the chunks in the heap.
+/// NOTE: The chunk edges (inside a chunk) are removed, the pointers from a chunk to itself are kept.
+pub struct ChunkGraph {
+    pub chn_addrs: Vec<u64>,
+    pub chn_addr_to_index: HashMap<u64, usize>,
+    /// (target index, weight) of the outgoing edges of each chunk, sorted by target index
+    pub out_edges: Vec<Vec<(usize, f64)>>,
+    /// (source index, weight) of the incoming edges of each chunk, sorted by source index
+    pub in_edges: Vec<Vec<(usize, f64)>>,
+}
+
+impl ChunkGraph {
+    pub fn from_graph_data(graph_data: &GraphData) -> ChunkGraph {
+        let chn_addrs = graph_data.chn_addrs.clone();
+        let chn_addr_to_index = graph_data.get_chn_addr_to_index();
+        let edge_to_weight = graph_data.get_chunk_pointer_edges(&chn_addr_to_index);
+
+        let mut out_edges: Vec<Vec<(usize, f64)>> = vec![Vec::new(); chn_addrs.len()];
+        let mut in_edges: Vec<Vec<(usize, f64)>> = vec![Vec::new(); chn_addrs.len()];
+        for ((from_index, to_index), weight) in edge_to_weight.into_iter() {
+            out_edges[from_index].push((to_index, weight as f64));
+            in_edges[to_index].push((from_index, weight as f64));
+        }
+        in_edges.iter_mut().for_each(|edges| edges.sort_by_key(|(index, _)| *index));
+
+        ChunkGraph { chn_addrs, chn_addr_to_index, out_edges, in_edges }
+    }
+
+    pub fn nb_chunks(&self) -> usize {
+        self.chn_addrs.len()
+    }
+
+    /// sum of the weights of the incoming and outgoing edges of each chunk
+    pub fn weighted_degrees(&self) -> (Vec<f64>, Vec<f64>) {
+        let sum_weights = |edges: &Vec<(usize, f64)>| edges.iter().fold(0.0, |total, (_, weight)| total + weight);
+        (
+            self.in_edges.iter().map(sum_weights).collect(),
+            self.out_edges.iter().map(sum_weights).collect(),
+        )
+    }
+
+    /// weighted PageRank (power iteration).
+    /// NOTE: The score of the chunks without outgoing edge is spread over all the chunks.
+    pub fn pagerank(&self) -> Vec<f64> {
+        let nb_chunks = self.nb_chunks();
+        if nb_chunks == 0 {
+            return Vec::new();
+        }
+        let (_, out_degrees) = self.weighted_degrees();
+
+        let mut scores = vec![1.0 / nb_chunks as f64; nb_chunks];
+        for _ in 0..CENTRALITY_MAX_NB_OF_ITERATIONS {
+            let dangling_score: f64 = (0..nb_chunks)
+                .filter(|index| out_degrees[*index] == 0.0)
+                .map(|index| scores[index])
+                .sum();
+            let base_score = (1.0 - PAGERANK_DAMPING + PAGERANK_DAMPING * dangling_score) / nb_chunks as f64;
+            let new_scores: Vec<f64> = self.in_edges.iter()
+                .map(|edges| base_score + PAGERANK_DAMPING * edges.iter().fold(
+                    0.0, |total, (from_index, weight)| total + scores[*from_index] * weight / out_degrees[*from_index]
+                ))
+                .collect();
+
+            let change: f64 = new_scores.iter().zip(scores.iter()).map(|(new, old)| (new - old).abs()).sum();
+            scores = new_scores;
+            if change < CENTRALITY_TOLERANCE {
+                break;
+            }
+        }
+        scores
+    }
+
+    /// weighted HITS (power iteration), return the (hub, authority) scores, each summing to 1
+    /// NOTE: All the scores are 0 if there is no edge.
+    pub fn hits(&self) -> (Vec<f64>, Vec<f64>) {
+        let nb_chunks = self.nb_chunks();
+        let normalize = |scores: &mut Vec<f64>| {
+            let total: f64 = scores.iter().sum();
+            if total > 0.0 {
+                scores.iter_mut().for_each(|score| *score /= total);
+            }
+        };
+
+        let mut hubs = vec![1.0 / nb_chunks as f64; nb_chunks];
+        let mut authorities = vec![0.0; nb_chunks];
+        for _ in 0..CENTRALITY_MAX_NB_OF_ITERATIONS {
+            authorities = self.in_edges.iter()
+                .map(|edges| edges.iter().fold(0.0, |total, (from_index, weight)| total + hubs[*from_index] * weight))
+                .collect();
+            normalize(&mut authorities);
+            let mut new_hubs: Vec<f64> = self.out_edges.iter()
+                .map(|edges| edges.iter().fold(0.0, |total, (to_index, weight)| total + authorities[*to_index] * weight))
+                .collect();
+            normalize(&mut new_hubs);
+
+            let change: f64 = new_hubs.iter().zip(hubs.iter()).map(|(new, old)| (new - old).abs()).sum();
+            hubs = new_hubs;
+            if change < CENTRALITY_TOLERANCE {
+                break;
+            }
+        }
+        if hubs.iter().sum::<f64>() == 0.0 || authorities.iter().sum::<f64>() == 0.0 {
+            return (vec![0.0; nb_chunks], vec![0.0; nb_chunks]);
+        }
+        (hubs, authorities)
+    }
+
+    /// Betweenness of the chunks (Brandes, shortest paths in number of edges),
+    /// normalized by (n - 1)(n - 2), the number of pairs of other chunks.
+    /// NOTE: Approximated from nb_of_sources source chunks, evenly spaced in the heap,
+    /// and scaled to all the chunks (exact if nb_of_sources >= the number of chunks).
+    pub fn betweenness(&self, nb_of_sources: usize) -> Vec<f64> {
+        let nb_chunks = self.nb_chunks();
+        let mut betweenness = vec![0.0; nb_chunks];
+        if nb_chunks <= 2 || nb_of_sources == 0 {
+            return betweenness;
+        }
+        let nb_of_sources = nb_of_sources.min(nb_chunks);
+
+        let mut distances: Vec<Option<usize>> = vec![None; nb_chunks];
+        let mut nb_shortest_paths = vec![0.0; nb_chunks];
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); nb_chunks];
+        let mut dependencies = vec![0.0; nb_chunks];
+        for source_number in 0..nb_of_sources {
+            let source = source_number * nb_chunks / nb_of_sources;
+            distances.iter_mut().for_each(|distance| *distance = None);
+            nb_shortest_paths.iter_mut().for_each(|nb| *nb = 0.0);
+            predecessors.iter_mut().for_each(|chunk_predecessors| chunk_predecessors.clear());
+            dependencies.iter_mut().for_each(|dependency| *dependency = 0.0);
+
+            // BFS from the source, the chunks are visited by increasing distance
+            let mut visited: Vec<usize> = Vec::new();
+            let mut queue = VecDeque::from([source]);
+            distances[source] = Some(0);
+            nb_shortest_paths[source] = 1.0;
+            while let Some(chunk) = queue.pop_front() {
+                visited.push(chunk);
+                let distance = distances[chunk].unwrap();
+                for (next, _) in self.out_edges[chunk].iter() {
+                    if distances[*next].is_none() {
+                        distances[*next] = Some(distance + 1);
+                        queue.push_back(*next);
+                    }
+                    if distances[*next] == Some(distance + 1) {
+                        nb_shortest_paths[*next] += nb_shortest_paths[chunk];
+                        predecessors[*next].push(chunk);
+                    }
+                }
+            }
+
+            // accumulate the dependencies, from the farthest chunks
+            for chunk in visited.iter().rev() {
+                for predecessor in predecessors[*chunk].iter() {
+                    dependencies[*predecessor] += nb_shortest_paths[*predecessor] / nb_shortest_paths[*chunk]
+                        * (1.0 + dependencies[*chunk]);
+                }
+                if *chunk != source {
+                    betweenness[*chunk] += dependencies[*chunk];
+                }
+            }
+        }
+
+        let scale = nb_chunks as f64 / nb_of_sources as f64 / ((nb_chunks - 1) * (nb_chunks - 2)) as f64;
+        betweenness.iter_mut().for_each(|value| *value *= scale);
+        betweenness
+    }
+
+    /// Strongly connected component of each chunk, return the (SCC id, SCC size) of each chunk.
+    /// NOTE: The SCC are numbered in the order of their first chunk in the heap.
+    pub fn strongly_connected_components(&self) -> Vec<(usize, usize)> {
+        let mut graph = DiGraphMap::<usize, ()>::new();
+        for (from_index, edges) in self.out_edges.iter().enumerate() {
+            graph.add_node(from_index);
+            for (to_index, _) in edges.iter() {
+                graph.add_edge(from_index, *to_index, ());
+            }
+        }
+
+        let mut sccs = petgraph::algo::tarjan_scc(&graph);
+        sccs.iter_mut().for_each(|scc| scc.sort());
+        sccs.sort_by_key(|scc| scc[0]);
+
+        let mut scc_of_chunks = vec![(0, 0); self.nb_chunks()];
+        for (scc_id, scc) in sccs.iter().enumerate() {
+            for chunk in scc.iter() {
+                scc_of_chunks[*chunk] = (scc_id, scc.len());
+            }
+        }
+        scc_of_chunks
+    }
+
+    /// Number of edges of the shortest path from the nearest of the given chunks to each chunk,
+    /// following the pointers (None if not reachable).
+    pub fn distances_from(&self, source_chunks: &[usize]) -> Vec<Option<usize>> {
+        let mut distances: Vec<Option<usize>> = vec![None; self.nb_chunks()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for source in source_chunks.iter() {
+            if distances[*source].is_none() {
+                distances[*source] = Some(0);
+                queue.push_back(*source);
+            }
+        }
+        while let Some(chunk) = queue.pop_front() {
+            let distance = distances[chunk].unwrap();
+            for (next, _) in self.out_edges[chunk].iter() {
+                if distances[*next].is_none() {
+                    distances[*next] = Some(distance + 1);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// chunk graph of the given weighted edges, between nb_chunks chunks
+    fn new_chunk_graph(nb_chunks: usize, edges: &[(usize, usize, f64)]) -> ChunkGraph {
+        let chn_addrs: Vec<u64> = (0..nb_chunks as u64).map(|index| 0x1000 + 0x20 * index).collect();
+        let mut out_edges = vec![Vec::new(); nb_chunks];
+        let mut in_edges = vec![Vec::new(); nb_chunks];
+        for (from_index, to_index, weight) in edges.iter() {
+            out_edges[*from_index].push((*to_index, *weight));
+            in_edges[*to_index].push((*from_index, *weight));
+        }
+        ChunkGraph {
+            chn_addr_to_index: chn_addrs.iter().enumerate().map(|(index, addr)| (*addr, index)).collect(),
+            chn_addrs,
+            out_edges,
+            in_edges,
+        }
+    }
+
+    #[test]
+    fn test_chunk_graph_centralities() {
+        // 0 -> 1 -> 2 <-> 3, 4 alone
+        let chunk_graph = new_chunk_graph(5, &[(0, 1, 2.0), (1, 2, 1.0), (2, 3, 1.0), (3, 2, 3.0)]);
+
+        let (in_degrees, out_degrees) = chunk_graph.weighted_degrees();
+        assert_eq!(in_degrees, vec![0.0, 2.0, 4.0, 1.0, 0.0]);
+        assert_eq!(out_degrees, vec![2.0, 1.0, 1.0, 3.0, 0.0]);
+
+        let pageranks = chunk_graph.pagerank();
+        assert!((pageranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(pageranks[2] > pageranks[1] && pageranks[1] > pageranks[0]);
+        assert_eq!(pageranks[0], pageranks[4]);
+
+        let (hubs, authorities) = chunk_graph.hits();
+        assert!((hubs.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((authorities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(authorities[0], 0.0);
+        assert_eq!(hubs[4], 0.0);
+
+        // shortest paths through 1: 0->2, 0->3, through 2: 0->3, 1->3
+        let betweenness = chunk_graph.betweenness(5);
+        let normalization = (4 * 3) as f64;
+        assert_eq!(betweenness, vec![0.0, 2.0 / normalization, 2.0 / normalization, 0.0, 0.0]);
+        assert_eq!(chunk_graph.betweenness(0), vec![0.0; 5]);
+
+        assert_eq!(chunk_graph.strongly_connected_components(), vec![(0, 1), (1, 1), (2, 2), (2, 2), (3, 1)]);
+
+        assert_eq!(chunk_graph.distances_from(&[1]), vec![None, Some(0), Some(1), Some(2), None]);
+        assert_eq!(chunk_graph.distances_from(&[]), vec![None; 5]);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{get_node_label, extract_chunk_data_as_heap_bytes, chunk_basics_columns, get_chunk_basics_informations};
+
+/// number of possible byte bigrams, the maximum number of buckets of the bigram counts
+pub const NB_OF_BYTE_BIGRAMS: usize = 256 * 256;
+
+/// columns of the byte ratios (f64), in order
+const BYTE_RATIO_COLUMNS: [&str; 3] = ["printable_ratio", "zero_ratio", "high_bit_ratio"];
+
+/// generate the byte histogram embedding of all chunks
+/// in order :
+///    - chunk basics informations (see get_chunk_basics_informations)
+///    - count of each byte value in the user data of the chunk (256 columns)
+///    - count of the byte bigrams, hashed in byte_bigram_nb_of_buckets buckets
+///    - ratios (f64) of the printable ASCII bytes (space included), of the zero bytes
+///      and of the bytes with the high bit set
+///
+/// NOTE: Computed from the bytes of the blocks in the heap dump byte order (the bigrams are the ones in memory),
+/// with fewer columns than the bit n-grams of the statistic embedding.
+pub fn generate_chunk_byte_histogram_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_chunk_byte_histogram_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_byte_histogram_embedding, but the samples are generated while iterating
+pub fn iter_chunk_byte_histogram_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(|chn_addr| {
+            let sample = generate_chunk_byte_histogram_sample(graph_embedding, *chn_addr);
+            (sample, get_node_label(graph_embedding, *chn_addr))
+        });
+
+    SamplesIter::new(chunk_byte_histogram_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk byte histogram embedding (see generate_chunk_byte_histogram_sample)
+pub fn chunk_byte_histogram_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend((0..256).map(|byte| format!("byte_count_{}", byte)), ColumnType::Int);
+    columns.extend(
+        (0..graph_embedding.get_byte_bigram_nb_of_buckets()).map(|bucket| format!("bigram_bucket_{}", bucket)),
+        ColumnType::Int,
+    );
+    columns.extend(BYTE_RATIO_COLUMNS, ColumnType::Float);
+    columns
+}
+
+/// bucket of a byte bigram: the bigram itself if there is a bucket per bigram, else its hash
+fn get_bigram_bucket(first: u8, second: u8, nb_of_buckets: usize) -> usize {
+    let bigram = ((first as usize) << 8) | second as usize;
+    if nb_of_buckets >= NB_OF_BYTE_BIGRAMS {
+        return bigram;
+    }
+    // splitmix64 finalizer, stable between runs and platforms
+    let mut z = (bigram as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) % nb_of_buckets as u64) as usize
+}
+
+/// generate the byte histogram embedding of a chunk
+/// the integer features (chunk informations, byte and bigram counts), then the float ones (byte ratios)
+pub fn generate_chunk_byte_histogram_sample(graph_embedding : &GraphEmbedding, chn_addr: u64) -> Vec<SampleValue> {
+    let mut features = get_chunk_basics_informations(graph_embedding, chn_addr);
+    let bytes = extract_chunk_data_as_heap_bytes(graph_embedding, chn_addr);
+    let nb_of_buckets = graph_embedding.get_byte_bigram_nb_of_buckets();
+
+    let mut byte_counts = [0usize; 256];
+    for byte in bytes.iter() {
+        byte_counts[*byte as usize] += 1;
+    }
+    let mut bigram_counts = vec![0usize; nb_of_buckets];
+    for bigram in bytes.windows(2) {
+        bigram_counts[get_bigram_bucket(bigram[0], bigram[1], nb_of_buckets)] += 1;
+    }
+    features.extend(byte_counts.iter().map(|count| SampleValue::Int(*count)));
+    features.extend(bigram_counts.into_iter().map(SampleValue::Int));
+
+    // -------- f64
+    let nb_bytes = bytes.len().max(1) as f64;
+    let nb_printable: usize = byte_counts[0x20..0x7F].iter().sum();
+    let nb_high_bit: usize = byte_counts[0x80..].iter().sum();
+    features.extend([
+        SampleValue::Float(nb_printable as f64 / nb_bytes),
+        SampleValue::Float(byte_counts[0] as f64 / nb_bytes),
+        SampleValue::Float(nb_high_bit as f64 / nb_bytes),
+    ]);
+
+    features
+}
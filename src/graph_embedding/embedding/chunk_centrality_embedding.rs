@@ -0,0 +1,90 @@
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::centrality::ChunkGraph;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{chunk_basics_columns, get_chunk_basics_informations, get_node_label};
+
+/// generate the centrality embedding of all the chunks, on the graph of the chunks (see ChunkGraph)
+/// in order :
+///     - chunk basics informations (see get_chunk_basics_informations)
+///     - in and out degree, weighted by the number of pointers
+///     - PageRank, HITS hub and authority, betweenness (see betweenness_nb_of_sources)
+///     - id and size of the strongly connected component of the chunk
+///     - if the chunk is reachable from the annotated ssh struct following the pointers,
+///       and its distance from it (0 if not reachable)
+///     - label (if the chunk contains a key, or is the ssh or sessionState)
+///
+/// WARN: The ssh struct distance comes from the annotations, it is unknown for a heap dump without JSON file.
+pub fn generate_chunk_centrality_embedding(
+    graph_embedding : &GraphEmbedding
+) -> Samples {
+    iter_chunk_centrality_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_centrality_embedding, but the samples are generated while iterating
+/// NOTE: The centralities of all the chunks are computed before the first sample.
+pub fn iter_chunk_centrality_embedding(
+    graph_embedding : &GraphEmbedding
+) -> SamplesIter<'_> {
+    let graph_data = &graph_embedding.graph_annotate.graph_data;
+    let chunk_graph = ChunkGraph::from_graph_data(graph_data);
+
+    let (in_degrees, out_degrees) = chunk_graph.weighted_degrees();
+    let pageranks = chunk_graph.pagerank();
+    let (hubs, authorities) = chunk_graph.hits();
+    let betweenness = chunk_graph.betweenness(graph_embedding.get_betweenness_nb_of_sources());
+    let sccs = chunk_graph.strongly_connected_components();
+
+    // the annotated ssh struct, in its chunk
+    let ssh_struct_chunks: Vec<usize> = graph_data.node_addr_to_annotations.iter()
+        .filter(|(_, annotation_set)| annotation_set.is_ssh_struct_subclass())
+        .map(|(addr, _)| {
+            let node = graph_data.addr_to_node.get(addr).unwrap();
+            let chn_addr = node.get_parent_chn_addr().unwrap_or(*addr);
+            chunk_graph.chn_addr_to_index[&chn_addr]
+        })
+        .collect();
+    let ssh_struct_distances = chunk_graph.distances_from(&ssh_struct_chunks);
+
+    let samples = graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(move |chn_addr| {
+            let index = chunk_graph.chn_addr_to_index[chn_addr];
+            let mut sample = get_chunk_basics_informations(graph_embedding, *chn_addr);
+            sample.extend([
+                SampleValue::Float(in_degrees[index]),
+                SampleValue::Float(out_degrees[index]),
+                SampleValue::Float(pageranks[index]),
+                SampleValue::Float(hubs[index]),
+                SampleValue::Float(authorities[index]),
+                SampleValue::Float(betweenness[index]),
+                SampleValue::Int(sccs[index].0),
+                SampleValue::Int(sccs[index].1),
+                SampleValue::Int(ssh_struct_distances[index].is_some() as usize),
+                SampleValue::Int(ssh_struct_distances[index].unwrap_or(0)),
+            ]);
+            let label = get_node_label(graph_embedding, *chn_addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(chunk_centrality_columns(), samples)
+}
+
+/// columns of the chunk centrality embedding (see generate_chunk_centrality_embedding)
+pub fn chunk_centrality_columns() -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend([
+        "in_degree",
+        "out_degree",
+        "pagerank",
+        "hits_hub",
+        "hits_authority",
+        "betweenness",
+    ], ColumnType::Float);
+    columns.extend([
+        "scc_id",
+        "scc_size",
+        "is_reachable_from_ssh_struct",
+        "ssh_struct_distance",
+    ], ColumnType::Int);
+    columns
+}
@@ -0,0 +1,65 @@
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::entropy_profile::EntropyProfile;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{get_node_label, extract_chunk_data_as_bytes, chunk_basics_columns, get_chunk_basics_informations};
+use crate::params::BLOCK_BYTE_SIZE;
+
+/// generate the entropy profile embedding of all chunks (see EntropyProfile)
+/// in order :
+///    - chunk basics informations (see get_chunk_basics_informations)
+///    - entropy profile: entropy of the windows of blocks across the chunk, with a fixed length
+///    - number of windows
+///    - max window entropy, the block position of its window and its position relative to the chunk size
+///    - number of blocks of the longest run of high-entropy windows, and its block position
+///
+/// NOTE: A key is a high-entropy island in a bigger struct, the entropy of the whole chunk washes it out.
+pub fn generate_chunk_entropy_profile_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_chunk_entropy_profile_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_entropy_profile_embedding, but the samples are generated while iterating
+pub fn iter_chunk_entropy_profile_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(|chn_addr| {
+            let sample = generate_chunk_entropy_profile_sample(graph_embedding, *chn_addr);
+            (sample, get_node_label(graph_embedding, *chn_addr))
+        });
+
+    SamplesIter::new(chunk_entropy_profile_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk entropy profile embedding (see generate_chunk_entropy_profile_sample)
+pub fn chunk_entropy_profile_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend(
+        (0..graph_embedding.get_entropy_profile_params().profile_length).map(|index| format!("entropy_profile_{}", index)),
+        ColumnType::Float,
+    );
+    columns.push("nb_of_entropy_windows", ColumnType::Int);
+    columns.push("max_window_entropy", ColumnType::Float);
+    columns.push("max_window_entropy_position", ColumnType::Int);
+    columns.push("max_window_entropy_relative_position", ColumnType::Float);
+    columns.extend(["longest_high_entropy_run", "longest_high_entropy_run_position"], ColumnType::Int);
+    columns
+}
+
+/// generate the entropy profile embedding of a chunk
+pub fn generate_chunk_entropy_profile_sample(graph_embedding : &GraphEmbedding, chn_addr: u64) -> Vec<SampleValue> {
+    let mut features = get_chunk_basics_informations(graph_embedding, chn_addr);
+    let bytes = extract_chunk_data_as_bytes(graph_embedding, chn_addr, BLOCK_BYTE_SIZE);
+    let profile = EntropyProfile::from_bytes(&bytes, graph_embedding.get_entropy_profile_params());
+
+    let nb_of_blocks = bytes.len().div_ceil(BLOCK_BYTE_SIZE).max(1);
+    features.extend(profile.profile.iter().map(|entropy| SampleValue::Float(*entropy)));
+    features.extend([
+        SampleValue::Int(profile.window_entropies.len()),
+        SampleValue::Float(profile.max_entropy),
+        SampleValue::Int(profile.max_entropy_position),
+        SampleValue::Float(profile.max_entropy_position as f64 / nb_of_blocks as f64),
+        SampleValue::Int(profile.longest_high_entropy_run),
+        SampleValue::Int(profile.longest_high_entropy_run_position),
+    ]);
+
+    features
+}
@@ -1,31 +1,36 @@
-use std::collections::HashMap;
-
 use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::embedding::chunk_layout_embedding::get_chunk_layout;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
 use crate::graph_embedding::utils_embedding::{get_node_label, extract_chunk_data_as_bytes};
 use crate::params::BLOCK_BYTE_SIZE;
 use crate::utils::bytes_to_hex_string;
 
 
 
-/// Extract chunk data user as hexa string
+/// Extract chunk data user as hexa string, and the layout of the chunk (see get_chunk_layout)
 pub fn generate_chunk_extract(
     graph_embedding : &GraphEmbedding,
-) -> (Vec<HashMap<String, String>>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
-    for chn_addr in graph_embedding.graph_annotate.graph_data.chn_addrs.iter() {
-        if graph_embedding.is_filtered_addr(chn_addr) {
-            continue;
-        }
+) -> Samples {
+    iter_chunk_extract(graph_embedding).collect_samples()
+}
 
-        let bytes = extract_chunk_data_as_bytes(graph_embedding, *chn_addr, BLOCK_BYTE_SIZE);
-        let hexa_string = bytes_to_hex_string(&bytes);
-        let mut sample = HashMap::new();
-        sample.insert("hexa_representation".to_string(), hexa_string);
-        
+/// same as generate_chunk_extract, but the samples are generated while iterating
+pub fn iter_chunk_extract(
+    graph_embedding : &GraphEmbedding,
+) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(|chn_addr| {
+            let bytes = extract_chunk_data_as_bytes(graph_embedding, *chn_addr, BLOCK_BYTE_SIZE);
+            let hexa_string = bytes_to_hex_string(&bytes);
+            (
+                vec![
+                    SampleValue::Text(hexa_string),
+                    SampleValue::Text(get_chunk_layout(graph_embedding, *chn_addr)),
+                ],
+                get_node_label(graph_embedding, *chn_addr)
+            )
+        });
 
-        samples.push(sample);
-        labels.push(get_node_label(graph_embedding, *chn_addr));
-    }
-    (samples, labels)
+    SamplesIter::new(Columns::of_type(["hexa_representation", "layout"], ColumnType::Text), samples)
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use crate::graph_structs::Node;
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{get_node_label, chunk_basics_columns, get_chunk_basics_informations};
+use crate::params::BLOCK_BYTE_SIZE;
+
+/// letters of the blocks of a chunk layout, their code in the embedding is their index + 1 (0 is the padding)
+///     - P: pointer
+///     - V: value
+///     - Z: value of only zeros
+///     - F: footer
+const LAYOUT_BLOCK_LETTERS: [char; 4] = ['P', 'V', 'Z', 'F'];
+
+/// layout of the blocks of the user data of a chunk, one letter per block (see LAYOUT_BLOCK_LETTERS)
+/// ex: "PPVZZF"
+/// NOTE: Chunks with the same layout are likely instances of the same C struct.
+pub fn get_chunk_layout(graph_embedding : &GraphEmbedding, chn_addr: u64) -> String {
+    let addr_to_node = &graph_embedding.graph_annotate.graph_data.addr_to_node;
+    let chunk_byte_size = match addr_to_node.get(&chn_addr) {
+        Some(Node::ChunkHeaderNode(chunk_header_node)) => chunk_header_node.byte_size,
+        _ => panic!("🚩 Node at address {:#x} is not a chunk", chn_addr),
+    };
+
+    (1..chunk_byte_size / BLOCK_BYTE_SIZE)
+        .map(|block_index| {
+            let addr = chn_addr + (block_index * BLOCK_BYTE_SIZE) as u64;
+            match addr_to_node.get(&addr).unwrap() {
+                Node::PointerNode(_) => 'P',
+                Node::ValueNode(value_node) if value_node.value.iter().all(|byte| *byte == 0) => 'Z',
+                Node::ValueNode(_) => 'V',
+                Node::FooterNode(_) => 'F',
+                _ => panic!("🚩 Node at address {:#x} is not a pointer, value or footer node", addr),
+            }
+        })
+        .collect()
+}
+
+/// hash of a chunk layout (32-bit FNV-1a), stable between runs and platforms
+/// NOTE: 32 bits, so that it stays exact as a float (ex: numpy)
+pub fn hash_chunk_layout(layout: &str) -> usize {
+    let mut hash: u32 = 0x811C_9DC5;
+    for byte in layout.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash as usize
+}
+
+/// generate the layout embedding of all chunks
+/// in order :
+///    - chunk basics informations (see get_chunk_basics_informations)
+///    - hash of the layout of the chunk (see get_chunk_layout)
+///    - code of each of the chunk_layout_nb_of_blocks first blocks of the layout (see LAYOUT_BLOCK_LETTERS),
+///      padded with 0 after the end of the chunk
+pub fn generate_chunk_layout_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_chunk_layout_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_layout_embedding, but the samples are generated while iterating
+pub fn iter_chunk_layout_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(|chn_addr| {
+            let sample = generate_chunk_layout_sample(graph_embedding, *chn_addr);
+            (sample, get_node_label(graph_embedding, *chn_addr))
+        });
+
+    SamplesIter::new(chunk_layout_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk layout embedding (see generate_chunk_layout_sample)
+pub fn chunk_layout_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.push("layout_hash", ColumnType::Int);
+    columns.extend(
+        (0..graph_embedding.get_chunk_layout_nb_of_blocks()).map(|index| format!("layout_{}", index)),
+        ColumnType::Int,
+    );
+    columns
+}
+
+/// generate the layout embedding of a chunk
+pub fn generate_chunk_layout_sample(graph_embedding : &GraphEmbedding, chn_addr: u64) -> Vec<SampleValue> {
+    let mut features = get_chunk_basics_informations(graph_embedding, chn_addr);
+    let layout = get_chunk_layout(graph_embedding, chn_addr);
+
+    features.push(SampleValue::Int(hash_chunk_layout(&layout)));
+    features.extend(
+        layout.chars()
+            .map(|letter| LAYOUT_BLOCK_LETTERS.iter().position(|block_letter| *block_letter == letter).unwrap() + 1)
+            .chain(std::iter::repeat(0))
+            .take(graph_embedding.get_chunk_layout_nb_of_blocks())
+            .map(SampleValue::Int)
+    );
+
+    features
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::random_walk::RandomWalkCorpus;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{chunk_basics_columns, get_chunk_basics_informations, get_node_label};
+
+/// generate the random walk embedding of all the chunks
+/// in order :
+///     - chunk basics informations (see get_chunk_basics_informations)
+///     - vector of the chunk header node learned by the skip-gram on the random walks (see RandomWalkCorpus)
+///     - label (if the chunk contains a key, or is the ssh or sessionState)
+pub fn generate_chunk_random_walk_embedding(
+    graph_embedding : &GraphEmbedding
+) -> Samples {
+    iter_chunk_random_walk_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_random_walk_embedding, but the samples are generated while iterating
+/// NOTE: The walks are generated, and the skip-gram trained, before the first sample.
+pub fn iter_chunk_random_walk_embedding(
+    graph_embedding : &GraphEmbedding
+) -> SamplesIter<'_> {
+    let params = graph_embedding.get_random_walk_params();
+    let corpus = RandomWalkCorpus::generate(&graph_embedding.graph_annotate.graph_data, params);
+    let vectors = corpus.train_skip_gram(params);
+    let addr_to_index: HashMap<u64, usize> = corpus.node_addrs.iter()
+        .enumerate()
+        .map(|(index, addr)| (*addr, index))
+        .collect();
+    let dimension = params.dimension;
+
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(move |chn_addr| {
+            let mut sample = get_chunk_basics_informations(graph_embedding, *chn_addr);
+            let index = addr_to_index[chn_addr];
+            sample.extend(
+                vectors[index * dimension..(index + 1) * dimension].iter().map(|value| SampleValue::Float(*value))
+            );
+            let label = get_node_label(graph_embedding, *chn_addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(chunk_random_walk_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk random walk embedding (see generate_chunk_random_walk_embedding)
+pub fn chunk_random_walk_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend(
+        (0..graph_embedding.get_random_walk_params().dimension).map(|index| format!("rw_{}", index)),
+        ColumnType::Float,
+    );
+    columns
+}
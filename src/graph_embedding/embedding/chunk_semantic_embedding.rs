@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
-use crate::graph_embedding::{GraphEmbedding, utils_embedding::{get_chunk_basics_informations, get_node_label}, neighboring::generate_samples_for_neighbor_nodes_of_the_chunk};
+use crate::graph_embedding::{GraphEmbedding, utils_embedding::{chunk_basics_columns, get_chunk_basics_informations, get_node_label}, neighboring::{neighbors_columns, generate_samples_for_neighbor_nodes_of_the_chunk}};
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns};
 
 
 /// generate semantic embedding of all the chunks
@@ -8,35 +7,43 @@ use crate::graph_embedding::{GraphEmbedding, utils_embedding::{get_chunk_basics_
 ///     - chunk header addresse (not really usefull for learning, but can bu usefull to further analyse the data)
 ///     - chunk size
 ///     - nb pointer
-/// 
 ///     - ancestor (in order of depth, alternate CHN/PTR)
 ///     - children (same)
 ///     - label (if the chunk contains a key, or is the ssh or sessionState)
 pub fn generate_chunk_semantic_embedding(
     graph_embedding : &GraphEmbedding
-) -> (Vec<HashMap<String, usize>>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
+) -> Samples {
+    iter_chunk_semantic_embedding(graph_embedding).collect_samples()
+}
 
+/// same as generate_chunk_semantic_embedding, but the samples are generated while iterating
+pub fn iter_chunk_semantic_embedding(
+    graph_embedding : &GraphEmbedding
+) -> SamplesIter<'_> {
     // get chunk :
-    for chn_addr in graph_embedding.graph_annotate.graph_data.chn_addrs.iter() {
-        if graph_embedding.is_filtered_addr(chn_addr) {
-            continue;
-        }
-
-        let sample = generate_semantic_samples_of_a_chunk(graph_embedding, *chn_addr);
-        let label = get_node_label(graph_embedding, *chn_addr);
-
-        samples.push(sample);
-        labels.push(label);
-    }
-    (samples, labels)
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(|chn_addr| {
+            let sample = generate_semantic_samples_of_a_chunk(graph_embedding, *chn_addr);
+            let label = get_node_label(graph_embedding, *chn_addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(chunk_semantic_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk semantic embedding (see generate_semantic_samples_of_a_chunk)
+pub fn chunk_semantic_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.append(neighbors_columns(graph_embedding.embedding_params.depth, petgraph::Direction::Incoming));
+    columns.append(neighbors_columns(graph_embedding.embedding_params.depth, petgraph::Direction::Outgoing));
+    columns
 }
 
 pub fn generate_semantic_samples_of_a_chunk(
     graph_embedding : &GraphEmbedding, 
     chn_addr: u64
-) -> HashMap<String, usize> {
+) -> Vec<SampleValue> {
 
     let mut named_features = 
         get_chunk_basics_informations(graph_embedding, chn_addr);
@@ -1,8 +1,7 @@
-use std::collections::HashMap;
-
 use crate::graph_embedding::GraphEmbedding;
-use crate::graph_embedding::utils_embedding::{extract_chunk_data_as_bytes, get_node_label, get_chunk_basics_informations};
-use crate::params::{BLOCK_BYTE_SIZE, CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING};
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{extract_chunk_data_as_bytes, get_node_label, chunk_basics_columns, get_chunk_basics_informations};
+use crate::params::BLOCK_BYTE_SIZE;
 
 
 
@@ -10,36 +9,45 @@ use crate::params::{BLOCK_BYTE_SIZE, CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING
 
 
 /// generate an embedding of only the start bytes of the chunks (one value per byte)
-pub fn generate_chunk_start_bytes_embedding(graph_embedding : &GraphEmbedding) -> (Vec<HashMap<String, usize>>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
-
-    for addr in graph_embedding.graph_annotate.graph_data.chn_addrs.iter() {
-        if graph_embedding.is_filtered_addr(addr) {
-            continue;
-        }
-        
-        let sample = generate_chunk_start_bytes_sample(graph_embedding, *addr);
-        let label = get_node_label(graph_embedding, *addr);
-
-        samples.push(sample);
-        labels.push(label);
-    }
-    
-    (samples, labels)
+pub fn generate_chunk_start_bytes_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_chunk_start_bytes_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_start_bytes_embedding, but the samples are generated while iterating
+pub fn iter_chunk_start_bytes_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|addr| !graph_embedding.is_filtered_addr(addr))
+        .map(|addr| {
+            let sample = generate_chunk_start_bytes_sample(graph_embedding, *addr);
+            let label = get_node_label(graph_embedding, *addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(chunk_start_bytes_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk start bytes embedding (see generate_chunk_start_bytes_sample)
+pub fn chunk_start_bytes_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend(
+        (0..graph_embedding.get_chunk_nb_of_start_bytes_for_chunk_embedding()).map(|index| format!("byte_{}", index)),
+        ColumnType::Int,
+    );
+    columns
 }
 
-pub fn generate_chunk_start_bytes_sample(graph_embedding : &GraphEmbedding, addr: u64) -> HashMap<String, usize> {
+pub fn generate_chunk_start_bytes_sample(graph_embedding : &GraphEmbedding, addr: u64) -> Vec<SampleValue> {
     let mut sample = get_chunk_basics_informations(graph_embedding, addr);
 
     let bytes = extract_chunk_data_as_bytes(graph_embedding, addr, BLOCK_BYTE_SIZE);
+    let nb_start_bytes = graph_embedding.get_chunk_nb_of_start_bytes_for_chunk_embedding();
 
-    for (index, &byte) in bytes.iter().take(*CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING).enumerate() {
-        sample.insert(format!("byte_{}", index), byte as usize);
+    for &byte in bytes.iter().take(nb_start_bytes) {
+        sample.push(SampleValue::Int(byte as usize));
     }
     // insert missing 0
-    for index in bytes.len()..*CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING {
-        sample.insert(format!("byte_{}", index), 0);
+    for _ in bytes.len()..nb_start_bytes {
+        sample.push(SampleValue::Int(0));
     }
 
     sample
@@ -1,7 +1,11 @@
-use std::collections::HashMap;
 use crate::graph_embedding::GraphEmbedding;
-use crate::graph_embedding::utils_embedding::{get_node_label, extract_chunk_data_as_bytes, extract_chunk_data_as_bits, get_chunk_basics_informations};
-use crate::utils::{compute_statistics, shannon_entropy, get_bin_to_nb_starting};
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{get_node_label, extract_chunk_data_as_bytes, chunk_basics_columns, get_chunk_basics_informations};
+use crate::params::BLOCK_BYTE_SIZE;
+use crate::utils::{compute_statistics, count_bit_n_grams, shannon_entropy};
+
+/// columns of the common statistics (f64), in order
+const COMMON_STATISTIC_COLUMNS: [&str; 6] = ["mean", "mad", "std_dev", "skew", "kurt", "shannon_entropy"];
 
 /// generate statistic embedding of all chunks
 /// in order :
@@ -18,43 +22,57 @@ pub fn generate_chunk_statistic_embedding(
     graph_embedding : &GraphEmbedding, 
     n_gram : &Vec<usize>, 
     block_size : usize
-) -> (Vec<(HashMap<String, usize>, HashMap<String, f64>)>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
-    for chn_addr in graph_embedding.graph_annotate.graph_data.chn_addrs.iter() {
-        if graph_embedding.is_filtered_addr(chn_addr) {
-            continue;
-        }
-        let sample = generate_chunk_statistic_samples(graph_embedding, *chn_addr, n_gram, block_size);
-        samples.push(sample);
-        labels.push(get_node_label(graph_embedding, *chn_addr));
-    }
-    (samples, labels)
+) -> Samples {
+    iter_chunk_statistic_embedding(graph_embedding, n_gram, block_size).collect_samples()
+}
+
+/// same as generate_chunk_statistic_embedding, but the samples are generated while iterating
+pub fn iter_chunk_statistic_embedding<'a>(
+    graph_embedding : &'a GraphEmbedding, 
+    n_gram : &'a Vec<usize>, 
+    block_size : usize
+) -> SamplesIter<'a> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(move |chn_addr| {
+            let sample = generate_chunk_statistic_samples(graph_embedding, *chn_addr, n_gram, block_size);
+            (sample, get_node_label(graph_embedding, *chn_addr))
+        });
+
+    SamplesIter::new(chunk_statistic_columns(graph_embedding, n_gram), samples)
+}
+
+/// columns of the chunk statistic embedding (see generate_chunk_statistic_samples)
+pub fn chunk_statistic_columns(graph_embedding : &GraphEmbedding, n_gram : &[usize]) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend(graph_embedding.get_n_gram_columns(n_gram).iter().cloned(), ColumnType::Int);
+    columns.extend(COMMON_STATISTIC_COLUMNS, ColumnType::Float);
+    columns
 }
 
 /// generate statistic embedding of a chunk
+/// the integer features (chunk informations, n-gram), then the float ones (common statistics)
 pub fn generate_chunk_statistic_samples(graph_embedding : &GraphEmbedding, chn_addr: u64, n_gram : &Vec<usize>, block_size : usize) -> 
-    (HashMap<String, usize>, HashMap<String, f64>) {
-    let mut feature_usize = get_chunk_basics_informations(graph_embedding, chn_addr);
-    let mut feature_f64 = HashMap::new();
+    Vec<SampleValue> {
+    let mut features = get_chunk_basics_informations(graph_embedding, chn_addr);
     
     // -------- usize
 
     // add n-gram
     let n_gram_vec = generate_n_gram_for_chunk(graph_embedding, chn_addr, n_gram);
-    feature_usize.extend(n_gram_vec);
+    features.extend(n_gram_vec);
 
     // -------- f64
 
     let common_statistics = generate_common_statistic_for_chunk(graph_embedding, chn_addr, block_size);
-    feature_f64.extend(common_statistics);
+    features.extend(common_statistics);
     
 
-    (feature_usize, feature_f64)
+    features
 }
 
-/// generate common statistic
-fn generate_common_statistic_for_chunk(graph_embedding : &GraphEmbedding, addr: u64, block_size : usize) -> HashMap<String, f64> {
+/// generate common statistic, in the order of COMMON_STATISTIC_COLUMNS
+fn generate_common_statistic_for_chunk(graph_embedding : &GraphEmbedding, addr: u64, block_size : usize) -> Vec<SampleValue> {
 
 
     let bytes = extract_chunk_data_as_bytes(graph_embedding, addr, block_size);
@@ -64,40 +82,20 @@ fn generate_common_statistic_for_chunk(graph_embedding : &GraphEmbedding, addr:
 
     result.insert("shannon_entropy".to_string(), shannon_entropy(&bytes));
     
-    result
+    COMMON_STATISTIC_COLUMNS.iter()
+        .map(|column| SampleValue::Float(*result.get(*column).unwrap()))
+        .collect()
 }
 
-/// generate all the n-gram of the chunk
+/// generate all the n-gram of the chunk, in the order of their columns
 fn generate_n_gram_for_chunk(
     graph_embedding : &GraphEmbedding, 
     chn_addr: u64, 
-    n_grams : &Vec<usize>,
-) -> HashMap<String, usize> {
-    let mut n_gram_result = get_bin_to_nb_starting();
-
-    // get bits of the chunk
-    let chunk_bits = extract_chunk_data_as_bits(graph_embedding, chn_addr);
-
-    // for each bit
-    for char_i in 0..chunk_bits.len() {
-        let mut window = String::new();
-        // get the window
-        for window_size in n_grams {
-            // if the window is too big, we stop
-            if char_i + window_size > chunk_bits.len() {
-                break;
-            }
-            
-            // Extend the window to match the current window_size
-            while window.len() < *window_size {
-                window.push(chunk_bits[char_i + window.len()]);
-            }
-
-            // increment the counter of the window
-            let x = n_gram_result.get_mut(&window).unwrap();
-            *x += 1;
-        }
-    }
-
-    n_gram_result
+    n_grams : &[usize],
+) -> Vec<SampleValue> {
+    let chunk_bytes = extract_chunk_data_as_bytes(graph_embedding, chn_addr, BLOCK_BYTE_SIZE);
+
+    count_bit_n_grams(&chunk_bytes, n_grams).into_iter()
+        .map(SampleValue::Int)
+        .collect()
 }
@@ -1,11 +1,10 @@
-use std::collections::HashMap;
-
 use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::samples::{Samples, SamplesIter};
 use crate::graph_embedding::utils_embedding::get_node_label;
 use crate::graph_structs::Node;
 use crate::params::BLOCK_BYTE_SIZE;
 
-use super::value_node_semantic_embedding::generate_value_sample;
+use super::value_node_semantic_embedding::{generate_value_sample, value_node_semantic_columns};
 
 /// generate chunk top value node semantic embedding
 /// NOTE: A Value Node is a 8 byte block in the context of the memory graph.
@@ -23,36 +22,36 @@ use super::value_node_semantic_embedding::generate_value_sample;
 ///     3. If yes, generate the embedding
 pub fn generate_chunk_top_vn_semantic_embedding(
     graph_embedding : &GraphEmbedding
-) -> (Vec<HashMap<String, usize>>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
-
-    for chn_addr in graph_embedding.graph_annotate.graph_data.chn_addrs.iter() {
+) -> Samples {
+    iter_chunk_top_vn_semantic_embedding(graph_embedding).collect_samples()
+}
 
+/// same as generate_chunk_top_vn_semantic_embedding, but the samples are generated while iterating
+pub fn iter_chunk_top_vn_semantic_embedding(
+    graph_embedding : &GraphEmbedding
+) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
         // entropy filter
-        if graph_embedding.is_filtered_addr(chn_addr) {
-            continue;
-        }
-
-        // check if the first block of the user data section is a value node
-        let first_user_block_addr = chn_addr + BLOCK_BYTE_SIZE as u64;
-        let obtained_first_user_block = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&first_user_block_addr);
-        if obtained_first_user_block.is_none() {
-            panic!("The first user data block of the chunk is not in the graph, at address {:#x}", first_user_block_addr);
-        }
-        let first_user_block_node: &Node = obtained_first_user_block.unwrap();
-        match first_user_block_node {
-            Node::ValueNode(_) => {},
-            _ => continue, // skip this chunk as its first block is not a value node
-        }
-
-        // perform embedding on this value node
-        let sample = generate_value_sample(graph_embedding, first_user_block_addr);
-        let label = get_node_label(graph_embedding, first_user_block_addr);
-
-        samples.push(sample);
-        labels.push(label);
-    }
-    
-    (samples, labels)
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .filter_map(|chn_addr| {
+            // check if the first block of the user data section is a value node
+            let first_user_block_addr = chn_addr + BLOCK_BYTE_SIZE as u64;
+            let obtained_first_user_block = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&first_user_block_addr);
+            if obtained_first_user_block.is_none() {
+                panic!("The first user data block of the chunk is not in the graph, at address {:#x}", first_user_block_addr);
+            }
+            let first_user_block_node: &Node = obtained_first_user_block.unwrap();
+            match first_user_block_node {
+                Node::ValueNode(_) => {},
+                _ => return None, // skip this chunk as its first block is not a value node
+            }
+
+            // perform embedding on this value node
+            let sample = generate_value_sample(graph_embedding, first_user_block_addr);
+            let label = get_node_label(graph_embedding, first_user_block_addr);
+
+            Some((sample, label))
+        });
+
+    SamplesIter::new(value_node_semantic_columns(graph_embedding), samples)
 }
\ No newline at end of file
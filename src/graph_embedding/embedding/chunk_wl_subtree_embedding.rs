@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{chunk_basics_columns, get_chunk_basics_informations, get_node_label};
+use crate::graph_structs::{Node, EdgeType};
+
+/// generate the Weisfeiler-Lehman subtree embedding of all the chunks
+/// in order :
+///     - chunk basics informations (see get_chunk_basics_informations)
+///     - hashed histogram of the WL labels of the nodes of the chunk, for all the iterations
+///     - label (if the chunk contains a key, or is the ssh or sessionState)
+///
+/// NOTE: The number of WL iterations is the embedding depth,
+///     and the number of bins of the histogram is wl_nb_of_hash_bins.
+pub fn generate_chunk_wl_subtree_embedding(
+    graph_embedding : &GraphEmbedding
+) -> Samples {
+    iter_chunk_wl_subtree_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_chunk_wl_subtree_embedding, but the samples are generated while iterating
+/// NOTE: The WL labels of all the nodes are computed before the first sample.
+pub fn iter_chunk_wl_subtree_embedding(
+    graph_embedding : &GraphEmbedding
+) -> SamplesIter<'_> {
+    let wl_labels = get_wl_labels(graph_embedding);
+    let chn_addr_to_node_addrs = get_chn_addr_to_node_addrs(graph_embedding);
+
+    let samples = graph_embedding.graph_annotate.graph_data.chn_addrs.iter()
+        .filter(|chn_addr| !graph_embedding.is_filtered_addr(chn_addr))
+        .map(move |chn_addr| {
+            let mut sample = get_chunk_basics_informations(graph_embedding, *chn_addr);
+            sample.extend(generate_wl_histogram_of_a_chunk(
+                graph_embedding, &wl_labels, &chn_addr_to_node_addrs[chn_addr]
+            ));
+            let label = get_node_label(graph_embedding, *chn_addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(chunk_wl_subtree_columns(graph_embedding), samples)
+}
+
+/// columns of the chunk WL subtree embedding (see generate_chunk_wl_subtree_embedding)
+pub fn chunk_wl_subtree_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.extend(
+        (0..graph_embedding.get_wl_nb_of_hash_bins()).map(|bin| format!("wl_{}", bin)),
+        ColumnType::Int,
+    );
+    columns
+}
+
+/// mix the value into the seed (splitmix64 finalizer)
+/// NOTE: Stable between runs and platforms, unlike the hasher of the std HashMap.
+pub fn hash_combine(seed: u64, value: u64) -> u64 {
+    let mut z = (seed.rotate_left(32) ^ value).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// initial WL label of a node: its kind, the size bucket (log2) of its chunk, and if its chunk is free
+fn get_initial_label(graph_embedding : &GraphEmbedding, node: &Node) -> u64 {
+    let graph_data = &graph_embedding.graph_annotate.graph_data;
+    let kind = match node {
+        Node::ChunkHeaderNode(_) => 1,
+        Node::ValueNode(_) => 2,
+        Node::PointerNode(_) => 3,
+        Node::FooterNode(_) => 4,
+    };
+    let chn_addr = node.get_parent_chn_addr().unwrap_or(node.get_address());
+    let (size_bucket, is_free) = match graph_data.addr_to_node.get(&chn_addr) {
+        Some(Node::ChunkHeaderNode(chn)) => (
+            (usize::BITS - chn.byte_size.leading_zeros()) as u64,
+            chn.is_free as u64,
+        ),
+        _ => (0, 0),
+    };
+    hash_combine(hash_combine(kind, size_bucket), is_free)
+}
+
+/// WL labels of all the nodes of the graph, for each iteration (from 0 to the embedding depth).
+/// At each iteration, a node is relabelled with the hash of its label
+/// and of the sorted multiset of the labels of its neighbours,
+/// tagged by the direction and the type of their edge.
+fn get_wl_labels(graph_embedding : &GraphEmbedding) -> Vec<HashMap<u64, u64>> {
+    let graph_data = &graph_embedding.graph_annotate.graph_data;
+
+    let initial_labels: HashMap<u64, u64> = graph_data.graph.nodes()
+        .map(|addr| {
+            let node = graph_data.addr_to_node.get(&addr).unwrap();
+            (addr, get_initial_label(graph_embedding, node))
+        })
+        .collect();
+
+    let mut wl_labels = vec![initial_labels];
+    for _ in 0..graph_embedding.embedding_params.depth {
+        let labels = wl_labels.last().unwrap();
+        let new_labels: HashMap<u64, u64> = graph_data.graph.nodes()
+            .map(|addr| {
+                let mut neighbor_labels: Vec<u64> = Vec::new();
+                for (direction_tag, direction) in [
+                    (1, petgraph::Direction::Outgoing), (2, petgraph::Direction::Incoming)
+                ] {
+                    for neighbor_addr in graph_data.graph.neighbors_directed(addr, direction) {
+                        let edge = match direction {
+                            petgraph::Direction::Outgoing => graph_data.graph.edge_weight(addr, neighbor_addr),
+                            petgraph::Direction::Incoming => graph_data.graph.edge_weight(neighbor_addr, addr),
+                        }.unwrap();
+                        let edge_type_tag = match edge.edge_type {
+                            EdgeType::ChunkEdge => 1,
+                            EdgeType::PointerEdge => 2,
+                        };
+                        neighbor_labels.push(hash_combine(
+                            hash_combine(direction_tag, edge_type_tag), labels[&neighbor_addr]
+                        ));
+                    }
+                }
+                neighbor_labels.sort_unstable();
+
+                let new_label = neighbor_labels.into_iter().fold(labels[&addr], hash_combine);
+                (addr, new_label)
+            })
+            .collect();
+        wl_labels.push(new_labels);
+    }
+    wl_labels
+}
+
+/// addresses of the nodes of each chunk (the chunk header node and its children)
+fn get_chn_addr_to_node_addrs(graph_embedding : &GraphEmbedding) -> HashMap<u64, Vec<u64>> {
+    let graph_data = &graph_embedding.graph_annotate.graph_data;
+    let mut chn_addr_to_node_addrs: HashMap<u64, Vec<u64>> = graph_data.chn_addrs.iter()
+        .map(|chn_addr| (*chn_addr, vec![*chn_addr]))
+        .collect();
+    for addr in graph_data.graph.nodes() {
+        let node = graph_data.addr_to_node.get(&addr).unwrap();
+        if let Some(chn_addr) = node.get_parent_chn_addr() {
+            chn_addr_to_node_addrs.entry(chn_addr).or_default().push(addr);
+        }
+    }
+    chn_addr_to_node_addrs
+}
+
+/// histogram of the WL labels of the given nodes, for all the iterations, hashed in wl_nb_of_hash_bins bins
+fn generate_wl_histogram_of_a_chunk(
+    graph_embedding : &GraphEmbedding,
+    wl_labels: &[HashMap<u64, u64>],
+    node_addrs: &[u64],
+) -> Vec<SampleValue> {
+    let nb_of_hash_bins = graph_embedding.get_wl_nb_of_hash_bins();
+    let mut histogram = vec![0usize; nb_of_hash_bins];
+    for labels in wl_labels.iter() {
+        for addr in node_addrs.iter() {
+            histogram[(labels[addr] % nb_of_hash_bins as u64) as usize] += 1;
+        }
+    }
+    histogram.into_iter().map(SampleValue::Int).collect()
+}
@@ -1,5 +1,12 @@
 pub mod chunk_statistic_embedding;
 pub mod chunk_semantic_embedding;
+pub mod chunk_wl_subtree_embedding;
+pub mod chunk_random_walk_embedding;
+pub mod chunk_centrality_embedding;
+pub mod chunk_byte_histogram_embedding;
+pub mod value_node_window_embedding;
+pub mod chunk_entropy_profile_embedding;
+pub mod chunk_layout_embedding;
 pub mod value_node_semantic_embedding;
 pub mod chunk_top_vn_semantic_embedding;
 pub mod chunk_start_bytes_embedding;
@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
-use crate::{graph_structs::Node, graph_embedding::{GraphEmbedding, utils_embedding::{get_node_label, get_chunk_basics_informations}, neighboring::get_neighbors}};
+use crate::{graph_structs::Node, graph_embedding::{GraphEmbedding, utils_embedding::{get_node_label, chunk_basics_columns, get_chunk_basics_informations}, neighboring::{neighbors_columns, get_neighbors}}};
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns};
 
 
 /// generate semantic embedding of the nodes
@@ -8,33 +7,37 @@ use crate::{graph_structs::Node, graph_embedding::{GraphEmbedding, utils_embeddi
 ///     - position in the chunk
 ///     - nb pointer
 ///     - nb value
-/// 
 ///     - ancestor (in order of depth, alternate CHN/PTR)
 /// Labels [0.0, 1.0, ..., 0.0],
-pub fn generate_value_node_semantic_embedding(graph_embedding : &GraphEmbedding) -> (Vec<HashMap<String, usize>>, Vec<usize>) {
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
+pub fn generate_value_node_semantic_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_value_node_semantic_embedding(graph_embedding).collect_samples()
+}
 
-    for addr in graph_embedding.graph_annotate.graph_data.value_node_addrs.iter() {
-        if graph_embedding.is_filtered_addr(addr) {
-            continue;
-        }
+/// same as generate_value_node_semantic_embedding, but the samples are generated while iterating
+pub fn iter_value_node_semantic_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.value_node_addrs.iter()
+        .filter(|addr| !graph_embedding.is_filtered_addr(addr))
+        .map(|addr| {
+            let sample = generate_value_sample(graph_embedding, *addr);
+            let label = get_node_label(graph_embedding, *addr);
+            (sample, label)
+        });
 
-        let sample = generate_value_sample(graph_embedding, *addr);
-        let label = get_node_label(graph_embedding, *addr);
+    SamplesIter::new(value_node_semantic_columns(graph_embedding), samples)
+}
 
-        samples.push(sample);
-        labels.push(label);
-    }
-    
-    (samples, labels)
+/// columns of the value node semantic embedding (see generate_value_sample)
+pub fn value_node_semantic_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = chunk_basics_columns();
+    columns.append(neighbors_columns(graph_embedding.embedding_params.depth, petgraph::Direction::Incoming));
+    columns
 }
 
 /// get the semantics data from the parent chunk of a node
 fn add_features_from_parent_chunk(
     graph_embedding : &GraphEmbedding, 
     addr: u64,
-) -> HashMap<String, usize> {
+) -> Vec<SampleValue> {
     let node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&addr).unwrap();
     let parent_chn_node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(
         &node.get_parent_chn_addr().unwrap_or_else(
@@ -57,12 +60,12 @@ fn add_features_from_parent_chunk(
 pub fn generate_value_sample(
     graph_embedding : &GraphEmbedding, 
     addr: u64
-) -> HashMap<String, usize> {
+) -> Vec<SampleValue> {
     let mut named_features_from_parent_chunk = 
         add_features_from_parent_chunk(graph_embedding, addr);
     let named_features_from_ancestors = get_neighbors(graph_embedding, vec![addr].into_iter().collect(), petgraph::Direction::Incoming);
     
-    // combine the two features hashmaps
+    // combine the two features
     named_features_from_parent_chunk.extend(
         named_features_from_ancestors
     );
@@ -0,0 +1,100 @@
+use crate::graph_structs::Node;
+use crate::graph_embedding::GraphEmbedding;
+use crate::graph_embedding::embedding::value_node_semantic_embedding::{generate_value_sample, value_node_semantic_columns};
+use crate::graph_embedding::samples::{Samples, SamplesIter, SampleValue, Columns, ColumnType};
+use crate::graph_embedding::utils_embedding::{get_block_bytes, get_node_label};
+use crate::params::BLOCK_BYTE_SIZE;
+use crate::utils::shannon_entropy;
+
+/// generate the window embedding of the value nodes
+///     - value node semantic embedding (see generate_value_sample)
+///     - bytes of the block, and their entropy (f64)
+///     - for each of the value_node_window_nb_of_blocks blocks before and after the block, in address order:
+///       if the block is in the chunk, then its bytes
+/// Labels [0.0, 1.0, ..., 0.0],
+/// NOTE: The blocks out of the chunk (header, footer, other chunks) are padded with zeros.
+pub fn generate_value_node_window_embedding(graph_embedding : &GraphEmbedding) -> Samples {
+    iter_value_node_window_embedding(graph_embedding).collect_samples()
+}
+
+/// same as generate_value_node_window_embedding, but the samples are generated while iterating
+pub fn iter_value_node_window_embedding(graph_embedding : &GraphEmbedding) -> SamplesIter<'_> {
+    let samples = graph_embedding.graph_annotate.graph_data.value_node_addrs.iter()
+        .filter(|addr| !graph_embedding.is_filtered_addr(addr))
+        .map(|addr| {
+            let sample = generate_value_window_sample(graph_embedding, *addr);
+            let label = get_node_label(graph_embedding, *addr);
+            (sample, label)
+        });
+
+    SamplesIter::new(value_node_window_columns(graph_embedding), samples)
+}
+
+/// offsets (in blocks) of the window around a block, in address order, without the block itself
+fn get_window_offsets(graph_embedding : &GraphEmbedding) -> impl Iterator<Item = i64> {
+    let nb_of_blocks = graph_embedding.get_value_node_window_nb_of_blocks() as i64;
+    (-nb_of_blocks..=nb_of_blocks).filter(|offset| *offset != 0)
+}
+
+/// name of a block of the window, ex: "before_2", "after_1"
+fn window_block_name(offset: i64) -> String {
+    if offset < 0 {
+        format!("before_{}", -offset)
+    } else {
+        format!("after_{}", offset)
+    }
+}
+
+/// columns of the value node window embedding (see generate_value_window_sample)
+pub fn value_node_window_columns(graph_embedding : &GraphEmbedding) -> Columns {
+    let mut columns = value_node_semantic_columns(graph_embedding);
+    columns.extend((0..BLOCK_BYTE_SIZE).map(|index| format!("block_byte_{}", index)), ColumnType::Int);
+    columns.push("block_entropy", ColumnType::Float);
+    for offset in get_window_offsets(graph_embedding) {
+        let name = window_block_name(offset);
+        columns.push(format!("{}_is_in_chunk", name), ColumnType::Int);
+        columns.extend((0..BLOCK_BYTE_SIZE).map(|index| format!("{}_byte_{}", name, index)), ColumnType::Int);
+    }
+    columns
+}
+
+/// bytes of the block at the given address, if it is a data block of the given chunk
+fn get_window_block_bytes(graph_embedding : &GraphEmbedding, chn_addr: u64, addr: u64) -> Option<[u8; BLOCK_BYTE_SIZE]> {
+    let chunk_byte_size = match graph_embedding.graph_annotate.graph_data.addr_to_node.get(&chn_addr) {
+        Some(Node::ChunkHeaderNode(chunk_header_node)) => chunk_header_node.byte_size as u64,
+        _ => panic!("🚩 Node at address {:#x} is not a chunk", chn_addr),
+    };
+    if addr <= chn_addr || addr >= chn_addr + chunk_byte_size {
+        return None;
+    }
+    get_block_bytes(graph_embedding, addr)
+}
+
+/// generate the window embedding of a value node
+pub fn generate_value_window_sample(
+    graph_embedding : &GraphEmbedding,
+    addr: u64
+) -> Vec<SampleValue> {
+    let mut features = generate_value_sample(graph_embedding, addr);
+
+    let block_bytes = get_block_bytes(graph_embedding, addr).unwrap();
+    features.extend(block_bytes.iter().map(|byte| SampleValue::Int(*byte as usize)));
+    features.push(SampleValue::Float(shannon_entropy(&block_bytes.to_vec())));
+
+    let chn_addr = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&addr).unwrap()
+        .get_parent_chn_addr().unwrap();
+    for offset in get_window_offsets(graph_embedding) {
+        let window_addr = addr as i64 + offset * BLOCK_BYTE_SIZE as i64;
+        let window_bytes = if window_addr < 0 {
+            None
+        } else {
+            get_window_block_bytes(graph_embedding, chn_addr, window_addr as u64)
+        };
+        features.push(SampleValue::Int(window_bytes.is_some() as usize));
+        features.extend(
+            window_bytes.unwrap_or([0; BLOCK_BYTE_SIZE]).iter().map(|byte| SampleValue::Int(*byte as usize))
+        );
+    }
+
+    features
+}
@@ -0,0 +1,193 @@
+use serde_derive::{Serialize, Deserialize};
+
+use crate::params::{
+    BLOCK_BYTE_SIZE,
+    DEFAULT_ENTROPY_PROFILE_WINDOW_NB_OF_BLOCKS,
+    DEFAULT_ENTROPY_PROFILE_LENGTH,
+    DEFAULT_ENTROPY_PROFILE_HIGH_ENTROPY_THRESHOLD,
+};
+
+/// Parameters of the entropy profile of the chunks.
+/// NOTE: In the config file, it is the [entropy_profile] table, every field is optional.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EntropyProfileParams {
+    /// number of blocks of a window, the windows slide block by block
+    pub window_nb_of_blocks: usize,
+    /// number of values of the profile: the window entropies are averaged in this number of bins,
+    /// or padded with zeros if there are fewer windows
+    pub profile_length: usize,
+    /// minimum entropy (in bits) of a high-entropy window
+    pub high_entropy_threshold: f64,
+}
+
+impl Default for EntropyProfileParams {
+    fn default() -> Self {
+        EntropyProfileParams {
+            window_nb_of_blocks: DEFAULT_ENTROPY_PROFILE_WINDOW_NB_OF_BLOCKS,
+            profile_length: DEFAULT_ENTROPY_PROFILE_LENGTH,
+            high_entropy_threshold: DEFAULT_ENTROPY_PROFILE_HIGH_ENTROPY_THRESHOLD,
+        }
+    }
+}
+
+/// Entropy of the windows of blocks across the data of a chunk.
+/// NOTE: The positions are in blocks, from the start of the data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntropyProfile {
+    /// shannon entropy (in bits) of each window, in order
+    pub window_entropies: Vec<f64>,
+    /// window entropies, with a fixed length (see EntropyProfileParams::profile_length)
+    pub profile: Vec<f64>,
+    pub max_entropy: f64,
+    /// start of the first window with the max entropy
+    pub max_entropy_position: usize,
+    /// number of blocks covered by the longest run of consecutive high-entropy windows (0 if none)
+    pub longest_high_entropy_run: usize,
+    pub longest_high_entropy_run_position: usize,
+}
+
+/// shannon entropy (in bits) of the bytes counted in byte_counts
+fn entropy_of_byte_counts(byte_counts: &[usize; 256], nb_bytes: usize) -> f64 {
+    // fold from 0.0, an empty sum of f64 is -0.0
+    byte_counts.iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let probability = *count as f64 / nb_bytes as f64;
+            -probability * probability.log2()
+        })
+        .fold(0.0, |entropy, term| entropy + term)
+}
+
+impl EntropyProfile {
+    /// compute the entropy profile of the data of a chunk
+    /// NOTE: If the data is shorter than a window, there is a single window over all of it.
+    pub fn from_bytes(bytes: &[u8], params: &EntropyProfileParams) -> EntropyProfile {
+        let nb_of_blocks = bytes.len().div_ceil(BLOCK_BYTE_SIZE);
+        let window_nb_of_blocks = params.window_nb_of_blocks.min(nb_of_blocks);
+        let nb_of_windows = if nb_of_blocks == 0 { 0 } else { nb_of_blocks - window_nb_of_blocks + 1 };
+
+        // the byte counts slide with the window
+        let mut byte_counts = [0usize; 256];
+        let window_end = (window_nb_of_blocks * BLOCK_BYTE_SIZE).min(bytes.len());
+        for byte in &bytes[..window_end] {
+            byte_counts[*byte as usize] += 1;
+        }
+        let mut window_entropies = Vec::with_capacity(nb_of_windows);
+        for window_index in 0..nb_of_windows {
+            if window_index > 0 {
+                let removed_start = (window_index - 1) * BLOCK_BYTE_SIZE;
+                for byte in &bytes[removed_start..removed_start + BLOCK_BYTE_SIZE] {
+                    byte_counts[*byte as usize] -= 1;
+                }
+                let added_start = (window_index + window_nb_of_blocks - 1) * BLOCK_BYTE_SIZE;
+                for byte in &bytes[added_start..(added_start + BLOCK_BYTE_SIZE).min(bytes.len())] {
+                    byte_counts[*byte as usize] += 1;
+                }
+            }
+            let window_start = window_index * BLOCK_BYTE_SIZE;
+            let window_byte_size = ((window_index + window_nb_of_blocks) * BLOCK_BYTE_SIZE).min(bytes.len()) - window_start;
+            window_entropies.push(entropy_of_byte_counts(&byte_counts, window_byte_size));
+        }
+
+        // average in bins, or pad with zeros
+        let profile_length = params.profile_length;
+        let profile: Vec<f64> = if nb_of_windows >= profile_length {
+            (0..profile_length)
+                .map(|bin| {
+                    let bin_windows = &window_entropies[bin * nb_of_windows / profile_length..(bin + 1) * nb_of_windows / profile_length];
+                    bin_windows.iter().sum::<f64>() / bin_windows.len() as f64
+                })
+                .collect()
+        } else {
+            window_entropies.iter().cloned()
+                .chain(std::iter::repeat(0.0))
+                .take(profile_length)
+                .collect()
+        };
+
+        let (mut max_entropy, mut max_entropy_position) = (0.0, 0);
+        for (window_index, entropy) in window_entropies.iter().enumerate() {
+            if *entropy > max_entropy {
+                (max_entropy, max_entropy_position) = (*entropy, window_index);
+            }
+        }
+
+        // runs of high-entropy windows, a run of n windows covers n + window_nb_of_blocks - 1 blocks
+        let (mut longest_run, mut longest_run_position) = (0, 0);
+        let mut run_start = None;
+        let are_high_entropy = window_entropies.iter()
+            .map(|entropy| *entropy >= params.high_entropy_threshold)
+            .chain(std::iter::once(false)); // end the last run
+        for (window_index, is_high_entropy) in are_high_entropy.enumerate() {
+            match (is_high_entropy, run_start) {
+                (true, None) => run_start = Some(window_index),
+                (false, Some(start)) => {
+                    let run = window_index - start + window_nb_of_blocks - 1;
+                    if run > longest_run {
+                        (longest_run, longest_run_position) = (run, start);
+                    }
+                    run_start = None;
+                },
+                _ => {},
+            }
+        }
+
+        EntropyProfile {
+            window_entropies,
+            profile,
+            max_entropy,
+            max_entropy_position,
+            longest_high_entropy_run: longest_run,
+            longest_high_entropy_run_position: longest_run_position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entropy_profile() {
+        let params = EntropyProfileParams {
+            window_nb_of_blocks: 4,
+            profile_length: 4,
+            high_entropy_threshold: 4.0,
+        };
+
+        // 8 blocks of zeros, 4 blocks of distinct bytes, 8 blocks of zeros
+        let mut bytes = vec![0u8; 8 * BLOCK_BYTE_SIZE];
+        bytes.extend(1..=(4 * BLOCK_BYTE_SIZE) as u8);
+        bytes.extend(vec![0u8; 8 * BLOCK_BYTE_SIZE]);
+
+        let profile = EntropyProfile::from_bytes(&bytes, &params);
+        assert_eq!(profile.window_entropies.len(), 20 - 4 + 1);
+        assert_eq!(profile.window_entropies[0], 0.0);
+        assert_eq!(profile.max_entropy, 5.0);
+        assert_eq!(profile.max_entropy_position, 8);
+        // windows 7 (3 distinct blocks) to 9 are above the threshold, covering blocks 7 to 12
+        assert!(profile.window_entropies[6] < 4.0 && profile.window_entropies[7] > 4.0);
+        assert_eq!(profile.longest_high_entropy_run, 6);
+        assert_eq!(profile.longest_high_entropy_run_position, 7);
+
+        // 17 windows averaged in 4 bins of 4, 4, 4 and 5 windows
+        assert_eq!(profile.profile.len(), 4);
+        assert_eq!(profile.profile[0], 0.0);
+        let last_bin: f64 = profile.window_entropies[12..].iter().sum::<f64>() / 5.0;
+        assert_eq!(profile.profile[3], last_bin);
+        assert!(profile.profile[2] > profile.profile[1]);
+
+        // shorter than a window: a single window, padded profile
+        let profile = EntropyProfile::from_bytes(&bytes[8 * BLOCK_BYTE_SIZE..10 * BLOCK_BYTE_SIZE], &params);
+        assert_eq!(profile.window_entropies, vec![4.0]);
+        assert_eq!(profile.profile, vec![4.0, 0.0, 0.0, 0.0]);
+        assert_eq!(profile.longest_high_entropy_run, 2);
+
+        // no data
+        let profile = EntropyProfile::from_bytes(&[], &params);
+        assert!(profile.window_entropies.is_empty());
+        assert_eq!(profile.profile, vec![0.0; 4]);
+        assert_eq!((profile.max_entropy, profile.longest_high_entropy_run), (0.0, 0));
+    }
+}
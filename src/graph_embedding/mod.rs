@@ -1,74 +1,131 @@
 pub mod embedding;
+pub mod builder;
+pub mod samples;
+pub mod random_walk;
+pub mod entropy_profile;
+pub mod centrality;
 
 mod utils_embedding;
 mod neighboring;
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 #[cfg(test)]
 use crate::exe_pipeline::save_embedding;
 use crate::graph_annotate::GraphAnnotate;
 use crate::graph_structs::Node;
-use crate::params::{MIN_NB_OF_CHUNKS_TO_KEEP, CHUNK_BYTES_SIZE_TO_KEEP_FILTER};
-use crate::params::argv::{SelectAnnotationLocation, EntropyFilter, ChunkByteSizeFilter};
+use crate::utils::get_n_gram_columns;
+use crate::params::argv::{EntropyFilter, Pipeline, GraphFormat};
 
+#[cfg(test)]
 use std::path::PathBuf;
 
-use self::embedding::chunk_extract::generate_chunk_extract;
-use self::embedding::chunk_semantic_embedding::generate_chunk_semantic_embedding;
-use self::embedding::chunk_start_bytes_embedding::generate_chunk_start_bytes_embedding;
-use self::embedding::chunk_statistic_embedding::generate_chunk_statistic_embedding;
-use self::embedding::chunk_top_vn_semantic_embedding::generate_chunk_top_vn_semantic_embedding;
-use self::embedding::value_node_semantic_embedding::generate_value_node_semantic_embedding;
-
-type SamplesAndLabels = (Vec<HashMap<String, usize>>, Vec<usize>);
-
+use self::embedding::chunk_extract::{generate_chunk_extract, iter_chunk_extract};
+use self::embedding::chunk_semantic_embedding::{generate_chunk_semantic_embedding, iter_chunk_semantic_embedding};
+use self::embedding::chunk_start_bytes_embedding::{generate_chunk_start_bytes_embedding, iter_chunk_start_bytes_embedding};
+use self::embedding::chunk_byte_histogram_embedding::{generate_chunk_byte_histogram_embedding, iter_chunk_byte_histogram_embedding};
+use self::embedding::chunk_centrality_embedding::{generate_chunk_centrality_embedding, iter_chunk_centrality_embedding};
+use self::embedding::chunk_random_walk_embedding::{generate_chunk_random_walk_embedding, iter_chunk_random_walk_embedding};
+use self::embedding::chunk_wl_subtree_embedding::{generate_chunk_wl_subtree_embedding, iter_chunk_wl_subtree_embedding};
+use self::embedding::chunk_statistic_embedding::{generate_chunk_statistic_embedding, iter_chunk_statistic_embedding};
+use self::embedding::chunk_top_vn_semantic_embedding::{generate_chunk_top_vn_semantic_embedding, iter_chunk_top_vn_semantic_embedding};
+use self::embedding::value_node_window_embedding::{generate_value_node_window_embedding, iter_value_node_window_embedding};
+use self::embedding::value_node_semantic_embedding::{generate_value_node_semantic_embedding, iter_value_node_semantic_embedding};
+use self::embedding::chunk_layout_embedding::{generate_chunk_layout_embedding, iter_chunk_layout_embedding};
+use self::embedding::chunk_entropy_profile_embedding::{generate_chunk_entropy_profile_embedding, iter_chunk_entropy_profile_embedding};
+use self::builder::{EmbeddingParams, GraphOutputParams};
+use self::entropy_profile::EntropyProfileParams;
+use self::random_walk::{RandomWalkParams, RandomWalkCorpus};
+use self::samples::{Samples, SamplesIter};
+
+/// NOTE: Use the GraphEmbeddingBuilder to create a GraphEmbedding
 pub struct GraphEmbedding {
     pub graph_annotate: GraphAnnotate,
-    depth: usize,
 
     entropy_treshold: Option<f64>,
     chunk_bytes_size_to_keep_filter : Option<HashSet<usize>>,
+
+    /// names of the n-gram columns, in order (see utils::get_n_gram_columns)
+    n_gram_columns: Vec<String>,
+    embedding_params: EmbeddingParams,
+    graph_output_params: GraphOutputParams,
 }
 
 impl GraphEmbedding {
-    pub fn new(
-        heap_dump_raw_file_path: PathBuf, 
-        pointer_byte_size: usize,
-        depth: usize,
-        entropy_filter : EntropyFilter,
-        chunk_byte_size_filter : ChunkByteSizeFilter,
-        annotation : SelectAnnotationLocation,
-        without_value_node : bool,
-    ) -> Result<GraphEmbedding, crate::utils::ErrorKind> {
-        let graph_annotate = GraphAnnotate::new(heap_dump_raw_file_path, pointer_byte_size, annotation, without_value_node)?;
-        let mut graph_embedding = GraphEmbedding {
-            graph_annotate,
-            depth,
-            entropy_treshold: None,
-            chunk_bytes_size_to_keep_filter : None,
-        };
+    /// give back the annotated graph, so that it can be reused by another GraphEmbedding
+    /// (see GraphEmbeddingBuilder::build_from_graph_annotate)
+    pub fn into_graph_annotate(self) -> GraphAnnotate {
+        self.graph_annotate
+    }
 
+    /// n-gram used by the chunk statistic embedding (ascending order)
+    pub fn get_n_gram(&self) -> &Vec<usize> {
+        &self.embedding_params.n_gram
+    }
 
-        graph_embedding.chunk_bytes_size_to_keep_filter = Self::get_chunk_byte_size_filter(chunk_byte_size_filter);
-        graph_embedding.entropy_treshold = graph_embedding.calculate_entropy_treshold(entropy_filter);
+    /// names of the columns of the given n-gram, in order
+    pub fn get_n_gram_columns(&self, n_gram: &[usize]) -> Cow<'_, [String]> {
+        if n_gram == self.embedding_params.n_gram.as_slice() {
+            Cow::Borrowed(&self.n_gram_columns)
+        } else {
+            Cow::Owned(get_n_gram_columns(n_gram))
+        }
+    }
 
-        Ok(graph_embedding)
+    /// number of bytes at the start of the user data of a chunk used by the chunk start bytes embedding
+    pub fn get_chunk_nb_of_start_bytes_for_chunk_embedding(&self) -> usize {
+        self.embedding_params.chunk_nb_of_start_bytes_for_chunk_embedding
     }
 
-    /// manage the chunk byte size filter
-    fn get_chunk_byte_size_filter(chunk_byte_size_filter : ChunkByteSizeFilter) -> Option<HashSet<usize>> {
-        match chunk_byte_size_filter {
-            ChunkByteSizeFilter::Activate => {
-                Some((*CHUNK_BYTES_SIZE_TO_KEEP_FILTER).clone())
-            },
-            ChunkByteSizeFilter::None => None,
-        }
+    /// number of bins of the histogram of the chunk WL subtree embedding
+    pub fn get_wl_nb_of_hash_bins(&self) -> usize {
+        self.embedding_params.wl_nb_of_hash_bins
+    }
+
+    /// number of source chunks of the approximate betweenness of the chunk centrality embedding
+    pub fn get_betweenness_nb_of_sources(&self) -> usize {
+        self.embedding_params.betweenness_nb_of_sources
+    }
+
+    /// number of buckets of the byte bigram counts of the chunk byte histogram embedding
+    pub fn get_byte_bigram_nb_of_buckets(&self) -> usize {
+        self.embedding_params.byte_bigram_nb_of_buckets
+    }
+
+    /// number of blocks before and after each value node in the value node window embedding
+    pub fn get_value_node_window_nb_of_blocks(&self) -> usize {
+        self.embedding_params.value_node_window_nb_of_blocks
+    }
+
+    /// number of blocks of the layout of each chunk in the chunk layout embedding
+    pub fn get_chunk_layout_nb_of_blocks(&self) -> usize {
+        self.embedding_params.chunk_layout_nb_of_blocks
+    }
+
+    /// parameters of the random walks and of the skip-gram
+    pub fn get_random_walk_params(&self) -> &RandomWalkParams {
+        &self.embedding_params.random_walk
+    }
+
+    /// parameters of the entropy profile of the chunks
+    pub fn get_entropy_profile_params(&self) -> &EntropyProfileParams {
+        &self.embedding_params.entropy_profile
+    }
+
+    /// embedding used for the comments of the graph generation with embedding comments
+    pub fn get_graph_comment_embedding_type(&self) -> Pipeline {
+        self.graph_output_params.graph_comment_embedding_type
+    }
+
+    /// format of the graph file saved by the graph generation pipelines
+    pub fn get_graph_format(&self) -> GraphFormat {
+        self.graph_output_params.graph_format
     }
 
     /// calculate the minimum entropy for a chunk node or a parent chunk node of data node to be kept
-    fn calculate_entropy_treshold(&self, entropy_filter : EntropyFilter) -> Option<f64>{
+    fn calculate_entropy_treshold(&self, entropy_filter : EntropyFilter, min_nb_of_chunks_to_keep : usize) -> Option<f64>{
         match entropy_filter {
             EntropyFilter::None => None,
             EntropyFilter::OnlyMaxEntropy => {
@@ -113,7 +170,7 @@ impl GraphEmbedding {
 
                 // get the entropy treshold with the min of chunk
 
-                let nb_chunks = *MIN_NB_OF_CHUNKS_TO_KEEP;
+                let nb_chunks = min_nb_of_chunks_to_keep;
 
                 let chn_addr = 
                     if nb_chunks >= entropy_ordonned_chn_addr.len() {
@@ -204,70 +261,613 @@ impl GraphEmbedding {
         self.entropy_treshold.is_some() || self.chunk_bytes_size_to_keep_filter.is_some()
     }
 
+    /// label of the node (bit flags of its annotations, 0 if not annotated)
+    pub fn get_node_label(&self, addr : u64) -> usize {
+        utils_embedding::get_node_label(self, addr)
+    }
+
     // ----------------------------------------- test   -----------------------------------------------//
 
     #[cfg(test)]
     fn save_samples_and_labels_to_csv(&self, csv_path: PathBuf) {
-        let (samples, labels) = self.generate_value_node_semantic_embedding();
-        save_embedding(samples, labels, csv_path);
+        let samples = self.generate_value_node_semantic_embedding();
+        save_embedding(&samples, csv_path);
     }
 
+    // NOTE: The iter_* methods generate the samples while iterating, without keeping them in memory.
+
     // ----------------------------- statistic chunk embedding -----------------------------//
-    pub fn generate_chunk_statistic_embedding(&self, n_gram : &Vec<usize>, block_size : usize) -> (Vec<(HashMap<String, usize>, HashMap<String, f64>)>, Vec<usize>) {
+    pub fn generate_chunk_statistic_embedding(&self, n_gram : &Vec<usize>, block_size : usize) -> Samples {
         generate_chunk_statistic_embedding(&self, n_gram, block_size)
     }
 
+    pub fn iter_chunk_statistic_embedding<'a>(&'a self, n_gram : &'a Vec<usize>, block_size : usize) -> SamplesIter<'a> {
+        iter_chunk_statistic_embedding(self, n_gram, block_size)
+    }
+
     // ----------------------------- semantic chunk embedding -----------------------------//
-    pub fn generate_chunk_semantic_embedding(&self) -> SamplesAndLabels {
+    pub fn generate_chunk_semantic_embedding(&self) -> Samples {
         generate_chunk_semantic_embedding(&self)
     }
 
+    pub fn iter_chunk_semantic_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_semantic_embedding(self)
+    }
+
+    // ----------------------------- WL subtree chunk embedding -----------------------------//
+    pub fn generate_chunk_wl_subtree_embedding(&self) -> Samples {
+        generate_chunk_wl_subtree_embedding(self)
+    }
+
+    pub fn iter_chunk_wl_subtree_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_wl_subtree_embedding(self)
+    }
+
+    // ----------------------------- centrality chunk embedding -----------------------------//
+    pub fn generate_chunk_centrality_embedding(&self) -> Samples {
+        generate_chunk_centrality_embedding(self)
+    }
+
+    pub fn iter_chunk_centrality_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_centrality_embedding(self)
+    }
+
+    // ----------------------------- random walk chunk embedding -----------------------------//
+    pub fn generate_random_walk_corpus(&self) -> RandomWalkCorpus {
+        RandomWalkCorpus::generate(&self.graph_annotate.graph_data, &self.embedding_params.random_walk)
+    }
+
+    pub fn generate_chunk_random_walk_embedding(&self) -> Samples {
+        generate_chunk_random_walk_embedding(self)
+    }
+
+    pub fn iter_chunk_random_walk_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_random_walk_embedding(self)
+    }
+
     // ----------------------------- value embedding -----------------------------//
-    pub fn generate_value_node_semantic_embedding(&self) -> SamplesAndLabels {
+    pub fn generate_value_node_semantic_embedding(&self) -> Samples {
         generate_value_node_semantic_embedding(&self)
     }
 
+    pub fn iter_value_node_semantic_embedding(&self) -> SamplesIter<'_> {
+        iter_value_node_semantic_embedding(self)
+    }
+
+    // ----------------------------- value window embedding -----------------------------//
+    pub fn generate_value_node_window_embedding(&self) -> Samples {
+        generate_value_node_window_embedding(self)
+    }
+
+    pub fn iter_value_node_window_embedding(&self) -> SamplesIter<'_> {
+        iter_value_node_window_embedding(self)
+    }
+
     // ----------------------------- chunk top value node embedding -----------------------------//
-    pub fn generate_chunk_top_vn_semantic_embedding(&self) -> SamplesAndLabels {
+    pub fn generate_chunk_top_vn_semantic_embedding(&self) -> Samples {
         generate_chunk_top_vn_semantic_embedding(&self)
     }
 
+    pub fn iter_chunk_top_vn_semantic_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_top_vn_semantic_embedding(self)
+    }
+
+    // ----------------------------- chunk byte histogram embedding -----------------------------//
+    pub fn generate_chunk_byte_histogram_embedding(&self) -> Samples {
+        generate_chunk_byte_histogram_embedding(self)
+    }
+
+    pub fn iter_chunk_byte_histogram_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_byte_histogram_embedding(self)
+    }
+
+    // ----------------------------- chunk entropy profile embedding -----------------------------//
+    pub fn generate_chunk_entropy_profile_embedding(&self) -> Samples {
+        generate_chunk_entropy_profile_embedding(self)
+    }
+
+    pub fn iter_chunk_entropy_profile_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_entropy_profile_embedding(self)
+    }
+
+    // ----------------------------- chunk layout embedding -----------------------------//
+    pub fn generate_chunk_layout_embedding(&self) -> Samples {
+        generate_chunk_layout_embedding(self)
+    }
+
+    pub fn iter_chunk_layout_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_layout_embedding(self)
+    }
+
     // ----------------------------- chunk start bytes embedding -----------------------------//
-    pub fn generate_chunk_start_bytes_embedding(&self) -> SamplesAndLabels {
+    pub fn generate_chunk_start_bytes_embedding(&self) -> Samples {
         generate_chunk_start_bytes_embedding(&self)
     }
 
+    pub fn iter_chunk_start_bytes_embedding(&self) -> SamplesIter<'_> {
+        iter_chunk_start_bytes_embedding(self)
+    }
+
 
     // ----------------------------------------------------------------------------------------//
     // ------------------------------------ chunk extraction --------------------------------------------//
 
-    pub fn generate_chunk_extract(&self) -> (Vec<HashMap<String, String>>, Vec<usize>) {
+    pub fn generate_chunk_extract(&self) -> Samples {
         generate_chunk_extract(&self)
     }
 
+    pub fn iter_chunk_extract(&self) -> SamplesIter<'_> {
+        iter_chunk_extract(self)
+    }
+
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::params::{self};
+    use super::builder::GraphEmbeddingBuilder;
+    use crate::params::argv::{SelectAnnotationLocation, ChunkByteSizeFilter, KeyAnnotationSource};
+    use crate::graph_embedding::embedding::chunk_byte_histogram_embedding::NB_OF_BYTE_BIGRAMS;
+    use crate::graph_embedding::embedding::chunk_wl_subtree_embedding::hash_combine;
+    use crate::graph_annotate::report::AnnotationReport;
+    use crate::graph_data::GraphData;
+    use crate::graph_structs::{ChunkHeaderNode, PointerNode, ValueNode, FooterNode, HeaderFlags, Edge, EdgeType, DEFAULT_CHUNK_EDGE_WEIGHT};
+    use crate::graph_structs::annotations::{AnnotationSet, NodeAnnotation};
 
     #[test]
     fn test_label_to_csv() {
         crate::tests::setup();
 
-        let graph_embedding = GraphEmbedding::new(
-            params::TEST_HEAP_DUMP_FILE_PATH.clone(), 
-            crate::params::BLOCK_BYTE_SIZE,
-            5,
-            EntropyFilter::None,
-            ChunkByteSizeFilter::None,
-            SelectAnnotationLocation::ValueNode,
-            false,
-        ).unwrap();
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .depth(5)
+            .annotation(SelectAnnotationLocation::ValueNode)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
 
         graph_embedding.save_samples_and_labels_to_csv(
-            params::TEST_CSV_EMBEDDING_FILE_PATH.clone()
+            crate::tests::TEST_CSV_EMBEDDING_FILE_PATH.clone()
+        );
+    }
+
+    #[test]
+    fn test_builder_chunk_byte_size_filter() {
+        crate::tests::setup();
+
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .chunk_byte_size_filter(ChunkByteSizeFilter::Activate)
+            .chunk_bytes_size_to_keep_filter(vec![32].into_iter().collect())
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+        assert!(graph_embedding.is_filtering_active());
+
+        // only the chunks of 32 bytes (or annotated) are kept
+        let graph_data = &graph_embedding.graph_annotate.graph_data;
+        for chn_addr in graph_data.chn_addrs.iter() {
+            if graph_embedding.is_filtered_addr(chn_addr) {
+                match graph_data.addr_to_node.get(chn_addr).unwrap() {
+                    Node::ChunkHeaderNode(chn) => assert_ne!(chn.byte_size, 32),
+                    _ => panic!("Node is not a ChunkHeaderNode"),
+                }
+            }
+        }
+
+        // the default builder doesn't filter
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+        assert!(!graph_embedding.is_filtering_active());
+    }
+
+    #[test]
+    fn test_builder_build_from_graph_annotate() {
+        crate::tests::setup();
+
+        let value_node_builder = GraphEmbeddingBuilder::new();
+        let chunk_builder = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .entropy_filter(EntropyFilter::OnlyMaxEntropy);
+        assert!(value_node_builder.has_same_graph_as(&chunk_builder));
+        assert!(!chunk_builder.has_same_graph_as(&chunk_builder.clone().without_value_node(true)));
+        assert!(!chunk_builder.has_same_graph_as(
+            &chunk_builder.clone().annotation(SelectAnnotationLocation::None)
+        ));
+
+        // reuse the graph of the value node embedding for the chunk embedding
+        let graph_annotate = value_node_builder
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap()
+            .into_graph_annotate();
+        let reused_graph_embedding = chunk_builder.build_from_graph_annotate(graph_annotate);
+        let graph_embedding = chunk_builder.build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone()).unwrap();
+
+        let reused_samples = reused_graph_embedding.generate_chunk_semantic_embedding();
+        let samples = graph_embedding.generate_chunk_semantic_embedding();
+        assert_eq!(reused_samples.len(), samples.len());
+        assert_eq!(reused_samples.labels(), samples.labels());
+    }
+
+    #[test]
+    fn test_samples_columns_order() {
+        crate::tests::setup();
+
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .chunk_nb_of_start_bytes_for_chunk_embedding(12)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+
+        // the columns are in a logical order, not in lexicographic order
+        let samples = graph_embedding.generate_chunk_start_bytes_embedding();
+        let columns = samples.columns();
+        assert_eq!(columns[0], "chn_addr");
+        let byte_2 = columns.iter().position(|column| column == "byte_2").unwrap();
+        let byte_10 = columns.iter().position(|column| column == "byte_10").unwrap();
+        assert!(byte_2 < byte_10);
+        assert_eq!(columns.last().unwrap(), "byte_11");
+
+        // each sample has a value per column
+        assert!(!samples.is_empty());
+        for (values, _) in samples.iter() {
+            assert_eq!(values.len(), columns.len());
+        }
+    }
+
+    #[test]
+    fn test_iter_embedding() {
+        crate::tests::setup();
+
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+
+        // the samples generated while iterating are the same as the collected ones
+        let samples_iter = graph_embedding.iter_chunk_semantic_embedding();
+        assert_eq!(samples_iter.columns(), graph_embedding.generate_chunk_semantic_embedding().columns());
+        assert_eq!(samples_iter.collect_samples(), graph_embedding.generate_chunk_semantic_embedding());
+
+        let samples = graph_embedding.generate_chunk_top_vn_semantic_embedding();
+        let nb_samples = graph_embedding.iter_chunk_top_vn_semantic_embedding().count();
+        assert_eq!(nb_samples, samples.len());
+    }
+
+    /// Small hand-built graph of 3 chunks, A -> B -> C following the pointers:
+    ///     A (0x1000, 32 bytes): P(-> value of B) V("abcdefgh") F
+    ///     B (0x1020, 32 bytes): V([0xff; 8]) P(-> header of C) F, annotated as the ssh struct
+    ///     C (0x1040, 24 bytes): Z F
+    fn new_test_graph_embedding(builder: GraphEmbeddingBuilder, annotation: SelectAnnotationLocation) -> GraphEmbedding {
+        let flags = HeaderFlags { p: true, m: false, a: false };
+        let chunk_header_node = |addr: u64, byte_size: usize, nb_pointer_nodes: usize, nb_value_nodes: usize, chunk_number_in_heap: usize| {
+            Node::ChunkHeaderNode(ChunkHeaderNode {
+                addr, byte_size, flags: flags.clone(), is_free: false, nb_pointer_nodes, nb_value_nodes,
+                start_data_bytes_entropy: 0.0, chunk_number_in_heap,
+            })
+        };
+        let footer_node = |addr: u64, byte_size: usize, chn_addr: u64| {
+            Node::FooterNode(FooterNode { addr, byte_size, flags: flags.clone(), chn_addr })
+        };
+        let nodes = vec![
+            chunk_header_node(0x1000, 32, 1, 1, 0),
+            Node::PointerNode(PointerNode { addr: 0x1008, points_to: 0x1028, chn_addr: 0x1000 }),
+            Node::ValueNode(ValueNode { addr: 0x1010, value: *b"abcdefgh", chn_addr: 0x1000 }),
+            footer_node(0x1018, 32, 0x1000),
+            chunk_header_node(0x1020, 32, 1, 1, 1),
+            Node::ValueNode(ValueNode { addr: 0x1028, value: [0xff; 8], chn_addr: 0x1020 }),
+            Node::PointerNode(PointerNode { addr: 0x1030, points_to: 0x1040, chn_addr: 0x1020 }),
+            footer_node(0x1038, 32, 0x1020),
+            chunk_header_node(0x1040, 24, 0, 1, 2),
+            Node::ValueNode(ValueNode { addr: 0x1048, value: [0; 8], chn_addr: 0x1040 }),
+            footer_node(0x1050, 24, 0x1040),
+        ];
+
+        let mut graph_data = GraphData::new_empty();
+        for node in nodes {
+            let addr = node.get_address();
+            match node {
+                Node::ChunkHeaderNode(_) => graph_data.chn_addrs.push(addr),
+                Node::ValueNode(_) => graph_data.value_node_addrs.push(addr),
+                Node::PointerNode(_) => graph_data.pointer_node_addrs.push(addr),
+                Node::FooterNode(_) => {},
+            }
+            graph_data.graph.add_node(addr);
+            if let Some(chn_addr) = node.get_parent_chn_addr() {
+                graph_data.graph.add_edge(chn_addr, addr, Edge {
+                    from: chn_addr, to: addr, weight: DEFAULT_CHUNK_EDGE_WEIGHT, edge_type: EdgeType::ChunkEdge,
+                });
+            }
+            graph_data.addr_to_node.insert(addr, node);
+        }
+        for (from, to) in [(0x1008, 0x1028), (0x1030, 0x1040)] {
+            graph_data.graph.add_edge(from, to, Edge { from, to, weight: 1, edge_type: EdgeType::PointerEdge });
+        }
+
+        let ssh_struct_addr = match annotation {
+            SelectAnnotationLocation::ChunkHeaderNode => 0x1020,
+            _ => 0x1028,
+        };
+        graph_data.node_addr_to_annotations.insert(
+            ssh_struct_addr, AnnotationSet::new(NodeAnnotation::SshStructNodeAnnotation(ssh_struct_addr))
         );
+
+        let graph_annotate = GraphAnnotate {
+            graph_data,
+            annotation,
+            key_annotation_source: KeyAnnotationSource::JsonAddress,
+            annotation_report: AnnotationReport::new(PathBuf::from("test_graph-heap.raw")),
+            key_pointer_node_addrs: Vec::new(),
+        };
+        builder.annotation(annotation).build_from_graph_annotate(graph_annotate)
+    }
+
+    /// values of the given column, for all the samples
+    fn column_values(samples: &samples::Samples, column: &str) -> Vec<samples::SampleValue> {
+        (0..samples.len()).map(|index| samples.get(index, column).unwrap()).collect()
+    }
+
+    fn assert_approx_eq(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn test_chunk_wl_subtree_embedding() {
+        let builder = GraphEmbeddingBuilder::new().wl_nb_of_hash_bins(64);
+        let graph_embedding = new_test_graph_embedding(builder.clone().depth(1), SelectAnnotationLocation::ChunkHeaderNode);
+
+        let samples = graph_embedding.generate_chunk_wl_subtree_embedding();
+        assert_eq!(samples.columns().last().unwrap(), "wl_63");
+        assert_eq!(samples.labels(), &[0, 2, 0]);
+        let histogram = |index: usize| samples.sample(index)[samples.columns().len() - 64..].to_vec();
+
+        // C (24 bytes, size bucket 5): its header is pointed to by the pointer of B (32 bytes, size bucket 6)
+        let initial_label = |kind: u64, size_bucket: u64| hash_combine(hash_combine(kind, size_bucket), 0);
+        let tagged_label = |direction: u64, edge_type: u64, label: u64| hash_combine(hash_combine(direction, edge_type), label);
+        let (chn, value, footer, pointer_of_b) = (initial_label(1, 5), initial_label(2, 5), initial_label(4, 5), initial_label(3, 6));
+        let mut chn_neighbor_labels = vec![tagged_label(1, 1, value), tagged_label(1, 1, footer), tagged_label(2, 2, pointer_of_b)];
+        chn_neighbor_labels.sort_unstable();
+        let labels = [
+            chn, value, footer,
+            chn_neighbor_labels.into_iter().fold(chn, hash_combine),
+            hash_combine(value, tagged_label(2, 1, chn)),
+            hash_combine(footer, tagged_label(2, 1, chn)),
+        ];
+        let mut expected_histogram = vec![samples::SampleValue::Int(0); 64];
+        for label in labels {
+            if let samples::SampleValue::Int(count) = &mut expected_histogram[(label % 64) as usize] {
+                *count += 1;
+            }
+        }
+        assert_eq!(histogram(2), expected_histogram);
+
+        // A and B have the same nodes, only their neighbors differ
+        assert_ne!(histogram(0), histogram(1));
+        let samples = new_test_graph_embedding(builder.depth(0), SelectAnnotationLocation::ChunkHeaderNode)
+            .generate_chunk_wl_subtree_embedding();
+        assert_eq!(samples.sample(0)[6..], samples.sample(1)[6..]);
+    }
+
+    #[test]
+    fn test_chunk_random_walk_embedding() {
+        let params = RandomWalkParams {
+            walk_length: 4,
+            nb_walks_per_node: 2,
+            dimension: 4,
+            ..RandomWalkParams::default()
+        };
+        let builder = GraphEmbeddingBuilder::new().random_walk_params(params.clone());
+        let graph_embedding = new_test_graph_embedding(builder, SelectAnnotationLocation::ChunkHeaderNode);
+
+        // 2 walks of 4 nodes from each of the 11 nodes, in the order of the nodes
+        let corpus = graph_embedding.generate_random_walk_corpus();
+        assert_eq!(corpus.node_addrs, vec![0x1000, 0x1008, 0x1010, 0x1018, 0x1020, 0x1028, 0x1030, 0x1038, 0x1040, 0x1048, 0x1050]);
+        assert_eq!(corpus.walks.len(), 22);
+        for (walk_index, walk) in corpus.walks.iter().enumerate() {
+            assert_eq!(walk.len(), 4);
+            assert_eq!(walk[0], walk_index % 11);
+        }
+        // the footers and the values of A and C only have their chunk header as neighbor
+        for (start, chn) in [(2, 0), (3, 0), (7, 4), (9, 8), (10, 8)] {
+            assert_eq!(corpus.walks[start][1], chn);
+            assert_eq!(corpus.walks[start + 11][1], chn);
+        }
+
+        // the samples are the vectors of the chunk headers
+        let vectors = corpus.train_skip_gram(&params);
+        let samples = graph_embedding.generate_chunk_random_walk_embedding();
+        assert_eq!(samples.columns()[6..], ["rw_0", "rw_1", "rw_2", "rw_3"]);
+        for (sample_index, node_index) in [(0, 0), (1, 4), (2, 8)] {
+            let expected_vector: Vec<samples::SampleValue> = vectors[node_index * 4..(node_index + 1) * 4].iter()
+                .map(|value| samples::SampleValue::Float(*value))
+                .collect();
+            assert_eq!(samples.sample(sample_index)[6..], expected_vector[..]);
+        }
+    }
+
+    #[test]
+    fn test_chunk_centrality_embedding() {
+        let graph_embedding = new_test_graph_embedding(GraphEmbeddingBuilder::new(), SelectAnnotationLocation::ChunkHeaderNode);
+
+        let samples = graph_embedding.generate_chunk_centrality_embedding();
+        let floats = |column: &str| column_values(&samples, column).iter().map(|value| value.as_f64().unwrap()).collect::<Vec<f64>>();
+        let ints = |column: &str| column_values(&samples, column).iter().map(|value| value.as_f64().unwrap() as usize).collect::<Vec<usize>>();
+
+        // A -> B -> C, C has no outgoing edge
+        assert_eq!(floats("in_degree"), vec![0.0, 1.0, 1.0]);
+        assert_eq!(floats("out_degree"), vec![1.0, 1.0, 0.0]);
+        let base_score = 1.0 / (3.0 + 2.0 * centrality::PAGERANK_DAMPING + centrality::PAGERANK_DAMPING.powi(2));
+        for (pagerank, expected) in floats("pagerank").into_iter().zip([
+            base_score,
+            base_score * (1.0 + centrality::PAGERANK_DAMPING),
+            base_score * (1.0 + centrality::PAGERANK_DAMPING + centrality::PAGERANK_DAMPING.powi(2)),
+        ]) {
+            assert_approx_eq(pagerank, expected);
+        }
+        assert_eq!(floats("hits_hub"), vec![0.5, 0.5, 0.0]);
+        assert_eq!(floats("hits_authority"), vec![0.0, 0.5, 0.5]);
+        // the only path through a chunk is A -> B -> C, out of 2 pairs of other chunks
+        assert_eq!(floats("betweenness"), vec![0.0, 0.5, 0.0]);
+        assert_eq!(ints("scc_id"), vec![0, 1, 2]);
+        assert_eq!(ints("scc_size"), vec![1, 1, 1]);
+        assert_eq!(ints("is_reachable_from_ssh_struct"), vec![0, 1, 1]);
+        assert_eq!(ints("ssh_struct_distance"), vec![0, 0, 1]);
+
+        // the same graph of the chunks, with the ssh struct annotated on its value node
+        let value_node_graph_embedding = new_test_graph_embedding(GraphEmbeddingBuilder::new(), SelectAnnotationLocation::ValueNode);
+        let value_node_samples = value_node_graph_embedding.generate_chunk_centrality_embedding();
+        for index in 0..3 {
+            assert_eq!(value_node_samples.sample(index), samples.sample(index));
+        }
+    }
+
+    #[test]
+    fn test_chunk_byte_histogram_embedding() {
+        let builder = GraphEmbeddingBuilder::new().byte_bigram_nb_of_buckets(NB_OF_BYTE_BIGRAMS);
+        let graph_embedding = new_test_graph_embedding(builder.clone(), SelectAnnotationLocation::ChunkHeaderNode);
+
+        let samples = graph_embedding.generate_chunk_byte_histogram_embedding();
+        let nb_basics_columns = 6;
+        let first_bigram_index = nb_basics_columns + 256;
+        assert_eq!(samples.columns()[first_bigram_index], "bigram_bucket_0");
+        assert_eq!(samples.columns().len(), first_bigram_index + NB_OF_BYTE_BIGRAMS + 3);
+
+        let counts = |nb_counts: usize, counted: &[(usize, usize)]| {
+            let mut counts = vec![samples::SampleValue::Int(0); nb_counts];
+            for (index, count) in counted {
+                counts[*index] = samples::SampleValue::Int(*count);
+            }
+            counts
+        };
+        // user data of A: the pointer 0x1028 (little-endian, as in the heap dump) then "abcdefgh"
+        let mut bigrams_of_a = vec![(0x2810, 1), (0x1000, 1), (0x0000, 5), (0x0061, 1)];
+        bigrams_of_a.extend((b'a'..b'h').map(|byte| ((byte as usize) << 8 | (byte as usize + 1), 1)));
+        let mut bytes_of_a = vec![(0x00, 6), (0x10, 1), (0x28, 1)];
+        bytes_of_a.extend((b'a'..=b'h').map(|byte| (byte as usize, 1)));
+        let expected = [
+            (bytes_of_a, bigrams_of_a, [9.0 / 16.0, 6.0 / 16.0, 0.0]),
+            // user data of B: [0xff; 8] then the pointer 0x1040
+            (
+                vec![(0x00, 6), (0x10, 1), (0x40, 1), (0xff, 8)],
+                vec![(0xffff, 7), (0xff40, 1), (0x4010, 1), (0x1000, 1), (0x0000, 5)],
+                [1.0 / 16.0, 6.0 / 16.0, 8.0 / 16.0],
+            ),
+            // user data of C: 8 zeros
+            (vec![(0x00, 8)], vec![(0x0000, 7)], [0.0, 1.0, 0.0]),
+        ];
+        for (index, (byte_counts, bigram_counts, ratios)) in expected.iter().enumerate() {
+            let values = samples.sample(index);
+            assert_eq!(values[nb_basics_columns..first_bigram_index], counts(256, byte_counts)[..]);
+            assert_eq!(values[first_bigram_index..first_bigram_index + NB_OF_BYTE_BIGRAMS], counts(NB_OF_BYTE_BIGRAMS, bigram_counts)[..]);
+            let ratios: Vec<samples::SampleValue> = ratios.iter().map(|ratio| samples::SampleValue::Float(*ratio)).collect();
+            assert_eq!(values[first_bigram_index + NB_OF_BYTE_BIGRAMS..], ratios[..]);
+        }
+
+        // hashed in fewer buckets, the same bigrams are counted
+        let hashed_samples = new_test_graph_embedding(builder.byte_bigram_nb_of_buckets(16), SelectAnnotationLocation::ChunkHeaderNode)
+            .generate_chunk_byte_histogram_embedding();
+        for (index, nb_bigrams) in [(0, 15.0), (1, 15.0), (2, 7.0)] {
+            let values = hashed_samples.sample(index);
+            let hashed_nb_bigrams: f64 = values[first_bigram_index..first_bigram_index + 16].iter().map(|value| value.as_f64().unwrap()).sum();
+            assert_eq!(hashed_nb_bigrams, nb_bigrams);
+            assert_eq!(values[..first_bigram_index], samples.sample(index)[..first_bigram_index]);
+        }
+    }
+
+    #[test]
+    fn test_chunk_entropy_profile_embedding() {
+        let builder = GraphEmbeddingBuilder::new()
+            .entropy_profile_params(EntropyProfileParams {
+                window_nb_of_blocks: 1,
+                profile_length: 3,
+                high_entropy_threshold: 2.5,
+            });
+        let graph_embedding = new_test_graph_embedding(builder, SelectAnnotationLocation::ChunkHeaderNode);
+
+        let samples = graph_embedding.generate_chunk_entropy_profile_embedding();
+        assert_eq!(samples.columns()[6..9], ["entropy_profile_0", "entropy_profile_1", "entropy_profile_2"]);
+
+        // the pointer blocks: 6 zeros and 2 distinct bytes, "abcdefgh": 8 distinct bytes, the other blocks: a single byte
+        let pointer_entropy = 2.25 - 0.75 * 3f64.log2();
+        let expected = [
+            (vec![pointer_entropy, 3.0, 0.0], 2, 3.0, 1, 0.5, 1, 1),
+            (vec![0.0, pointer_entropy, 0.0], 2, pointer_entropy, 1, 0.5, 0, 0),
+            (vec![0.0, 0.0, 0.0], 1, 0.0, 0, 0.0, 0, 0),
+        ];
+        for (index, (profile, nb_windows, max_entropy, max_position, max_relative_position, run, run_position)) in expected.into_iter().enumerate() {
+            let values = samples.sample(index);
+            for (value, expected_entropy) in values[6..9].iter().zip(profile) {
+                assert_approx_eq(value.as_f64().unwrap(), expected_entropy);
+            }
+            assert_eq!(values[9], samples::SampleValue::Int(nb_windows));
+            assert_approx_eq(values[10].as_f64().unwrap(), max_entropy);
+            assert_eq!(
+                values[11..],
+                [
+                    samples::SampleValue::Int(max_position),
+                    samples::SampleValue::Float(max_relative_position),
+                    samples::SampleValue::Int(run),
+                    samples::SampleValue::Int(run_position),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunk_layout_embedding() {
+        let builder = GraphEmbeddingBuilder::new().chunk_layout_nb_of_blocks(4);
+        let graph_embedding = new_test_graph_embedding(builder, SelectAnnotationLocation::ChunkHeaderNode);
+
+        let samples = graph_embedding.generate_chunk_layout_embedding();
+        assert_eq!(samples.columns()[6..], ["layout_hash", "layout_0", "layout_1", "layout_2", "layout_3"]);
+        let extract_samples = graph_embedding.generate_chunk_extract();
+        assert_eq!(extract_samples.columns().names(), ["hexa_representation", "layout"]);
+
+        // 32-bit FNV-1a of the layouts, then the codes of the blocks (P: 1, V: 2, Z: 3, F: 4, padded with 0)
+        let expected = [
+            ("PVF", 0xbe27_23e7, [1, 2, 4, 0]),
+            ("VPF", 0x232b_6747, [2, 1, 4, 0]),
+            ("ZF", 0x2b18_0451, [3, 4, 0, 0]),
+        ];
+        for (index, (layout, hash, codes)) in expected.into_iter().enumerate() {
+            assert_eq!(extract_samples.get(index, "layout"), Some(samples::SampleValue::Text(layout.to_string())));
+            let expected_values: Vec<samples::SampleValue> = std::iter::once(hash).chain(codes)
+                .map(samples::SampleValue::Int)
+                .collect();
+            assert_eq!(samples.sample(index)[6..], expected_values[..]);
+        }
+    }
+
+    #[test]
+    fn test_value_node_window_embedding() {
+        let builder = GraphEmbeddingBuilder::new().value_node_window_nb_of_blocks(1);
+        let graph_embedding = new_test_graph_embedding(builder, SelectAnnotationLocation::ValueNode);
+
+        let samples = graph_embedding.generate_value_node_window_embedding();
+        let nb_of_semantic_columns = graph_embedding.generate_value_node_semantic_embedding().columns().len();
+        assert_eq!(samples.columns().len(), nb_of_semantic_columns + 8 + 1 + 2 * 9);
+        assert_eq!(samples.columns()[nb_of_semantic_columns + 9], "before_1_is_in_chunk");
+        assert_eq!(samples.columns()[nb_of_semantic_columns + 18], "after_1_is_in_chunk");
+        assert_eq!(samples.labels(), &[0, 2, 0]);
+
+        // the bytes of the block, its entropy, then the blocks before and after it in its chunk
+        // (the header, the footer and the blocks of the other chunks are padded with zeros),
+        // the pointers are in the heap dump byte order (little-endian)
+        let window = |block: [u8; 8], entropy: f64, before: Option<[u8; 8]>, after: Option<[u8; 8]>| {
+            let block_bytes = |bytes: [u8; 8]| bytes.into_iter().map(|byte| samples::SampleValue::Int(byte as usize)).collect::<Vec<_>>();
+            let mut values = block_bytes(block);
+            values.push(samples::SampleValue::Float(entropy));
+            for window_block in [before, after] {
+                values.push(samples::SampleValue::Int(window_block.is_some() as usize));
+                values.extend(block_bytes(window_block.unwrap_or([0; 8])));
+            }
+            values
+        };
+        let expected = [
+            window(*b"abcdefgh", 3.0, Some([0x28, 0x10, 0, 0, 0, 0, 0, 0]), None),
+            window([0xff; 8], 0.0, None, Some([0x40, 0x10, 0, 0, 0, 0, 0, 0])),
+            window([0; 8], 0.0, None, None),
+        ];
+        for (index, expected_values) in expected.iter().enumerate() {
+            assert_eq!(samples.sample(index)[nb_of_semantic_columns..], expected_values[..]);
+        }
     }
-}
\ No newline at end of file
+}
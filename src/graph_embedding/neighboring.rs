@@ -1,8 +1,26 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::HashSet;
 
 use crate::graph_structs::Node;
 
 use super::GraphEmbedding;
+use super::samples::{SampleValue, Columns, ColumnType};
+
+/// name of the direction in the columns of the neighbors
+fn direction_name(direction : petgraph::Direction) -> &'static str {
+    match direction {
+        petgraph::Direction::Incoming => "ancestor",
+        petgraph::Direction::Outgoing => "children",
+    }
+}
+
+/// columns of the children/ancestor (given direction), up to the given depth (see get_neighbors)
+pub fn neighbors_columns(depth : usize, direction : petgraph::Direction) -> Columns {
+    let feature_direction_name = direction_name(direction);
+    Columns::of_type((1..=depth).flat_map(|i| [
+        format!("chns_{}_{}", feature_direction_name, i),
+        format!("ptrs_{}_{}", feature_direction_name, i),
+    ]), ColumnType::Int)
+}
 
 /// get the children/ancestor (given direction) of a node
 /// in order : chn_depth_1, ptr_depth_1, chn_depth_2, ptr_depth_2, ... , chn_depth_n, ptr_depth_n
@@ -10,13 +28,13 @@ pub fn get_neighbors(
     graph_embedding : &GraphEmbedding, 
     addrs: HashSet<u64>, 
     direction : petgraph::Direction,
-) -> HashMap<String, usize> {
-    let mut result = HashMap::new();
+) -> Vec<SampleValue> {
+    let mut result = Vec::new();
     // vectorize ancestors
     let mut current_node_addrs: HashSet<u64>;
     let mut ancestor_addrs: HashSet<u64> = addrs;
 
-    for i in 0..graph_embedding.depth {
+    for i in 0..graph_embedding.embedding_params.depth {
         // swap current and next ancestors
         current_node_addrs = ancestor_addrs;
         ancestor_addrs = HashSet::new();
@@ -60,23 +78,10 @@ pub fn get_neighbors(
                 }
             }
         }
-        
-        let feature_direction_name = match direction {
-            petgraph::Direction::Incoming => "ancestor",
-            petgraph::Direction::Outgoing => "children",
-        };
-
-        // add number of chns for this depth
-        let feature_name_chn = format!(
-            "chns_{}_{}", feature_direction_name, i + 1
-        );
-        result.insert(feature_name_chn, nb_chn);
 
-        // add number of ptrs for this depth
-        let feature_name_ptr = format!(
-            "ptrs_{}_{}", feature_direction_name, i + 1
-        );
-        result.insert(feature_name_ptr, nb_ptr);
+        // add number of chns, then of ptrs, for this depth
+        result.push(SampleValue::Int(nb_chn));
+        result.push(SampleValue::Int(nb_ptr));
     }
 
     result
@@ -94,7 +99,7 @@ pub fn generate_samples_for_neighbor_nodes_of_the_chunk(
     graph_embedding : &GraphEmbedding, 
     chn_addr : u64, 
     direction : petgraph::Direction
-) -> HashMap<String, usize> {
+) -> Vec<SampleValue> {
     // get the children for starting the algorithm
     let mut ancestor_addrs: HashSet<u64> = HashSet::new();
 
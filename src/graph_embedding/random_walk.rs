@@ -0,0 +1,324 @@
+use serde_derive::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::graph_data::GraphData;
+use crate::params::{
+    DEFAULT_RANDOM_WALK_P,
+    DEFAULT_RANDOM_WALK_Q,
+    DEFAULT_RANDOM_WALK_LENGTH,
+    DEFAULT_RANDOM_WALK_NB_WALKS_PER_NODE,
+    DEFAULT_SKIP_GRAM_WINDOW_SIZE,
+    DEFAULT_SKIP_GRAM_DIMENSION,
+    DEFAULT_SKIP_GRAM_NB_NEGATIVE_SAMPLES,
+    DEFAULT_SKIP_GRAM_NB_EPOCHS,
+    DEFAULT_SKIP_GRAM_LEARNING_RATE,
+    DEFAULT_RANDOM_WALK_SEED,
+};
+
+/// Parameters of the random walks (node2vec) and of the skip-gram trained on them.
+/// NOTE: In the config file, it is the [random_walk] table, every field is optional.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RandomWalkParams {
+    /// return parameter: the higher, the less a walk goes back to the node it comes from
+    pub p: f64,
+    /// in-out parameter: the higher, the more a walk stays around the node it comes from (BFS-like),
+    /// the lower, the more it goes away (DFS-like)
+    pub q: f64,
+    /// maximum number of nodes of a walk (shorter if it reaches a node without neighbor)
+    pub walk_length: usize,
+    pub nb_walks_per_node: usize,
+    /// maximum distance, in a walk, between a node and its context nodes
+    pub window_size: usize,
+    /// size of the learned vectors
+    pub dimension: usize,
+    pub nb_negative_samples: usize,
+    pub nb_epochs: usize,
+    /// initial learning rate, linearly decreased to 0 during the training
+    pub learning_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for RandomWalkParams {
+    fn default() -> Self {
+        RandomWalkParams {
+            p: DEFAULT_RANDOM_WALK_P,
+            q: DEFAULT_RANDOM_WALK_Q,
+            walk_length: DEFAULT_RANDOM_WALK_LENGTH,
+            nb_walks_per_node: DEFAULT_RANDOM_WALK_NB_WALKS_PER_NODE,
+            window_size: DEFAULT_SKIP_GRAM_WINDOW_SIZE,
+            dimension: DEFAULT_SKIP_GRAM_DIMENSION,
+            nb_negative_samples: DEFAULT_SKIP_GRAM_NB_NEGATIVE_SAMPLES,
+            nb_epochs: DEFAULT_SKIP_GRAM_NB_EPOCHS,
+            learning_rate: DEFAULT_SKIP_GRAM_LEARNING_RATE,
+            seed: DEFAULT_RANDOM_WALK_SEED,
+        }
+    }
+}
+
+/// Pseudo random number generator (splitmix64), so that the walks and the training are reproducible
+struct RandomWalkRng {
+    state: u64,
+}
+
+impl RandomWalkRng {
+    fn new(seed: u64) -> RandomWalkRng {
+        RandomWalkRng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// value in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// index drawn with a probability proportional to its cumulative weight
+    /// NOTE: The cumulative weights are in ascending order, the last one is the total.
+    fn next_weighted_index(&mut self, cumulative_weights: &[f64]) -> usize {
+        let value = self.next_f64() * cumulative_weights.last().unwrap();
+        cumulative_weights.partition_point(|cumulative_weight| *cumulative_weight <= value)
+            .min(cumulative_weights.len() - 1)
+    }
+}
+
+/// Corpus of the random walks over the memory graph.
+/// The nodes have dense indices, in the order of the nodes of the graph.
+pub struct RandomWalkCorpus {
+    /// address of each node
+    pub node_addrs: Vec<u64>,
+    /// walks, as node indices
+    pub walks: Vec<Vec<usize>>,
+}
+
+impl RandomWalkCorpus {
+    /// Generate the truncated random walks over the graph (node2vec, DeepWalk when p = q = 1).
+    /// nb_walks_per_node walks start from each node, the next node being drawn
+    /// among the neighbors with a probability proportional to the edge weight, biased by p and q.
+    /// NOTE: The graph is walked as an undirected graph, since the value nodes have no outgoing edge.
+    pub fn generate(graph_data: &GraphData, params: &RandomWalkParams) -> RandomWalkCorpus {
+        let node_addrs: Vec<u64> = graph_data.graph.nodes().collect();
+        let addr_to_index: HashMap<u64, usize> = node_addrs.iter()
+            .enumerate()
+            .map(|(index, addr)| (*addr, index))
+            .collect();
+
+        // neighbors of each node (both directions), sorted by index, with the edge weight
+        let mut neighbors: Vec<Vec<(usize, f64)>> = vec![Vec::new(); node_addrs.len()];
+        for (from, to, edge) in graph_data.graph.all_edges() {
+            let (from, to) = (addr_to_index[&from], addr_to_index[&to]);
+            neighbors[from].push((to, edge.weight as f64));
+            if from != to {
+                neighbors[to].push((from, edge.weight as f64));
+            }
+        }
+        for node_neighbors in neighbors.iter_mut() {
+            node_neighbors.sort_by_key(|(index, _)| *index);
+        }
+
+        let mut rng = RandomWalkRng::new(params.seed);
+        let mut cumulative_weights: Vec<f64> = Vec::new();
+        let mut walks: Vec<Vec<usize>> = Vec::with_capacity(node_addrs.len() * params.nb_walks_per_node);
+        for _ in 0..params.nb_walks_per_node {
+            for start in 0..node_addrs.len() {
+                let mut walk = vec![start];
+                while walk.len() < params.walk_length {
+                    let current = *walk.last().unwrap();
+                    let previous = walk.len().checked_sub(2).map(|index| walk[index]);
+                    if neighbors[current].is_empty() {
+                        break;
+                    }
+
+                    cumulative_weights.clear();
+                    let mut total_weight = 0.0;
+                    for (neighbor, weight) in neighbors[current].iter() {
+                        let bias = match previous {
+                            None => 1.0,
+                            Some(previous) if *neighbor == previous => 1.0 / params.p,
+                            Some(previous) => {
+                                let is_neighbor_of_previous = neighbors[previous]
+                                    .binary_search_by_key(neighbor, |(index, _)| *index)
+                                    .is_ok();
+                                if is_neighbor_of_previous { 1.0 } else { 1.0 / params.q }
+                            },
+                        };
+                        total_weight += weight * bias;
+                        cumulative_weights.push(total_weight);
+                    }
+                    let next = neighbors[current][rng.next_weighted_index(&cumulative_weights)].0;
+                    walk.push(next);
+                }
+                walks.push(walk);
+            }
+        }
+
+        RandomWalkCorpus { node_addrs, walks }
+    }
+
+    /// Train a skip-gram with negative sampling (word2vec) on the walks, by SGD.
+    /// Return the learned vector of each node (dimension values per node, in the order of the nodes).
+    /// NOTE: The negative nodes are drawn with a probability proportional to
+    /// their number of occurrences in the walks to the power 0.75.
+    pub fn train_skip_gram(&self, params: &RandomWalkParams) -> Vec<f64> {
+        let nb_nodes = self.node_addrs.len();
+        let dimension = params.dimension;
+        let mut rng = RandomWalkRng::new(params.seed.wrapping_add(1));
+
+        let mut input_vectors: Vec<f64> = (0..nb_nodes * dimension)
+            .map(|_| (rng.next_f64() - 0.5) / dimension as f64)
+            .collect();
+        let mut output_vectors: Vec<f64> = vec![0.0; nb_nodes * dimension];
+
+        // distribution of the negative nodes
+        let mut nb_occurrences = vec![0usize; nb_nodes];
+        for walk in self.walks.iter() {
+            for node in walk.iter() {
+                nb_occurrences[*node] += 1;
+            }
+        }
+        let mut negative_cumulative_weights: Vec<f64> = Vec::with_capacity(nb_nodes);
+        let mut total_weight = 0.0;
+        for nb in nb_occurrences.iter() {
+            total_weight += (*nb as f64).powf(0.75);
+            negative_cumulative_weights.push(total_weight);
+        }
+        if total_weight == 0.0 {
+            return input_vectors;
+        }
+
+        let nb_steps = params.nb_epochs * self.walks.iter().map(|walk| walk.len()).sum::<usize>();
+        let mut step = 0;
+        let mut gradient = vec![0.0; dimension];
+        for _ in 0..params.nb_epochs {
+            for walk in self.walks.iter() {
+                for (position, center) in walk.iter().enumerate() {
+                    let learning_rate = params.learning_rate * (1.0 - step as f64 / nb_steps as f64).max(1e-4);
+                    step += 1;
+
+                    let first = position.saturating_sub(params.window_size);
+                    let last = (position + params.window_size).min(walk.len() - 1);
+                    for (context_position, context) in walk.iter().enumerate().take(last + 1).skip(first) {
+                        if context_position == position {
+                            continue;
+                        }
+                        let context = *context;
+                        let center_range = center * dimension..(center + 1) * dimension;
+
+                        // the context node is the positive target, the others are drawn
+                        gradient.iter_mut().for_each(|value| *value = 0.0);
+                        for k in 0..=params.nb_negative_samples {
+                            let (target, target_label) = match k {
+                                0 => (context, 1.0),
+                                _ => {
+                                    let negative = rng.next_weighted_index(&negative_cumulative_weights);
+                                    if negative == context {
+                                        continue;
+                                    }
+                                    (negative, 0.0)
+                                },
+                            };
+                            let target_range = target * dimension..(target + 1) * dimension;
+
+                            let dot: f64 = input_vectors[center_range.clone()].iter()
+                                .zip(output_vectors[target_range.clone()].iter())
+                                .map(|(a, b)| a * b)
+                                .sum();
+                            let g = (target_label - 1.0 / (1.0 + (-dot).exp())) * learning_rate;
+                            for ((gradient_value, output_value), input_value) in gradient.iter_mut()
+                                .zip(output_vectors[target_range].iter_mut())
+                                .zip(input_vectors[center_range.clone()].iter())
+                            {
+                                *gradient_value += g * *output_value;
+                                *output_value += g * input_value;
+                            }
+                        }
+                        for (input_value, gradient_value) in input_vectors[center_range].iter_mut().zip(gradient.iter()) {
+                            *input_value += gradient_value;
+                        }
+                    }
+                }
+            }
+        }
+        input_vectors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+    use crate::params::argv::SelectAnnotationLocation;
+
+    #[test]
+    fn test_random_walks() {
+        crate::tests::setup();
+
+        let params = RandomWalkParams {
+            walk_length: 6,
+            nb_walks_per_node: 2,
+            q: 0.5,
+            ..RandomWalkParams::default()
+        };
+        let graph_embedding = GraphEmbeddingBuilder::new()
+            .annotation(SelectAnnotationLocation::ChunkHeaderNode)
+            .random_walk_params(params)
+            .build(crate::tests::TEST_HEAP_DUMP_FILE_PATH.clone())
+            .unwrap();
+        let graph = &graph_embedding.graph_annotate.graph_data.graph;
+
+        let corpus = graph_embedding.generate_random_walk_corpus();
+        assert_eq!(corpus.node_addrs.len(), graph.node_count());
+        assert_eq!(corpus.walks.len(), 2 * graph.node_count());
+        for (i, walk) in corpus.walks.iter().enumerate() {
+            assert_eq!(walk[0], i % corpus.node_addrs.len());
+            assert!(!walk.is_empty() && walk.len() <= 6);
+            // each step follows an edge, in any direction
+            for step in walk.windows(2) {
+                let (from, to) = (corpus.node_addrs[step[0]], corpus.node_addrs[step[1]]);
+                assert!(graph.contains_edge(from, to) || graph.contains_edge(to, from));
+            }
+        }
+
+        // the walks are reproducible
+        assert_eq!(corpus.walks, graph_embedding.generate_random_walk_corpus().walks);
+    }
+
+    #[test]
+    fn test_train_skip_gram() {
+        // two communities that never appear in the same walk
+        let corpus = RandomWalkCorpus {
+            node_addrs: (0..6).collect(),
+            walks: (0..200).map(|i| {
+                let community = (i % 2) * 3;
+                (0..10).map(|step| community + (step * 7 + i) % 3).collect()
+            }).collect(),
+        };
+        let params = RandomWalkParams {
+            dimension: 8,
+            window_size: 2,
+            nb_negative_samples: 2,
+            nb_epochs: 5,
+            ..RandomWalkParams::default()
+        };
+        let vectors = corpus.train_skip_gram(&params);
+        assert_eq!(vectors.len(), 6 * 8);
+
+        let cosine = |a: usize, b: usize| {
+            let (a, b) = (&vectors[a * 8..(a + 1) * 8], &vectors[b * 8..(b + 1) * 8]);
+            let dot: f64 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+            let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+            dot / (norm(a) * norm(b))
+        };
+        assert!(cosine(0, 1) > cosine(0, 3));
+        assert!(cosine(4, 5) > cosine(4, 2));
+
+        // the training is reproducible
+        assert_eq!(vectors, corpus.train_skip_gram(&params));
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+use std::ops::Deref;
+
+/// Value of a column of a sample
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleValue {
+    Int(usize),
+    Float(f64),
+    /// NOTE: Only for the CSV output (ex: hexa representation of a chunk)
+    Text(String),
+}
+
+impl SampleValue {
+    /// the value as a float (None for a text)
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            SampleValue::Int(value) => Some(*value as f64),
+            SampleValue::Float(value) => Some(*value),
+            SampleValue::Text(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for SampleValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SampleValue::Int(value) => write!(f, "{}", value),
+            SampleValue::Float(value) => write!(f, "{}", value),
+            SampleValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<usize> for SampleValue {
+    fn from(value: usize) -> SampleValue {
+        SampleValue::Int(value)
+    }
+}
+
+impl From<f64> for SampleValue {
+    fn from(value: f64) -> SampleValue {
+        SampleValue::Float(value)
+    }
+}
+
+impl From<String> for SampleValue {
+    fn from(value: String) -> SampleValue {
+        SampleValue::Text(value)
+    }
+}
+
+/// Type of the values of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    /// NOTE: Only for the CSV output (ex: hexa representation of a chunk)
+    Text,
+}
+
+impl ColumnType {
+    /// type of the given value
+    pub fn of(value: &SampleValue) -> ColumnType {
+        match value {
+            SampleValue::Int(_) => ColumnType::Int,
+            SampleValue::Float(_) => ColumnType::Float,
+            SampleValue::Text(_) => ColumnType::Text,
+        }
+    }
+}
+
+/// Names and types of the columns of an embedding, in order.
+/// NOTE: Derefs to the names of the columns.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Columns {
+    names: Vec<String>,
+    types: Vec<ColumnType>,
+}
+
+impl Columns {
+    pub fn new() -> Columns {
+        Columns::default()
+    }
+
+    /// columns of the given names, all of the same type
+    pub fn of_type<S: Into<String>>(names: impl IntoIterator<Item = S>, column_type: ColumnType) -> Columns {
+        let mut columns = Columns::new();
+        columns.extend(names, column_type);
+        columns
+    }
+
+    pub fn push(&mut self, name: impl Into<String>, column_type: ColumnType) {
+        self.names.push(name.into());
+        self.types.push(column_type);
+    }
+
+    /// add the columns of the given names, all of the same type
+    pub fn extend<S: Into<String>>(&mut self, names: impl IntoIterator<Item = S>, column_type: ColumnType) {
+        for name in names {
+            self.push(name, column_type);
+        }
+    }
+
+    /// add the given columns after these ones
+    pub fn append(&mut self, mut columns: Columns) {
+        self.names.append(&mut columns.names);
+        self.types.append(&mut columns.types);
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn types(&self) -> &[ColumnType] {
+        &self.types
+    }
+}
+
+impl Deref for Columns {
+    type Target = [String];
+
+    fn deref(&self) -> &[String] {
+        &self.names
+    }
+}
+
+/// Samples of an embedding, generated one by one while iterating, as (values, label).
+/// NOTE: Used to write the samples of a large heap without keeping them all in memory.
+/// Use collect_samples to get all of them (see Samples).
+pub struct SamplesIter<'a> {
+    columns: Columns,
+    samples: Box<dyn Iterator<Item = (Vec<SampleValue>, usize)> + 'a>,
+}
+
+impl<'a> SamplesIter<'a> {
+    /// the values of each sample must be in the order of the columns
+    pub fn new(
+        columns: Columns,
+        samples: impl Iterator<Item = (Vec<SampleValue>, usize)> + 'a,
+    ) -> SamplesIter<'a> {
+        SamplesIter {
+            columns,
+            samples: Box::new(samples),
+        }
+    }
+
+    pub fn columns(&self) -> &Columns {
+        &self.columns
+    }
+
+    /// generate all the remaining samples, and keep them in memory
+    pub fn collect_samples(self) -> Samples {
+        let mut samples = Samples::new(self.columns);
+        for (values, label) in self.samples {
+            samples.push(values, label);
+        }
+        samples
+    }
+}
+
+impl<'a> Iterator for SamplesIter<'a> {
+    type Item = (Vec<SampleValue>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.samples.next()
+    }
+}
+
+/// Samples of an embedding, and their labels.
+/// The columns are declared once per embedding, in a logical order (ex: byte_2 before byte_10).
+/// The values are stored by type, row by row: the integers and the floats in flat Vecs,
+/// and the texts in a side Vec (only used by the text embeddings).
+/// NOTE: The values of a sample are rebuilt in the order of the columns when read (see Samples::sample).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Samples {
+    columns: Columns,
+    /// index of each column among the columns of its type
+    column_type_indexes: Vec<usize>,
+    nb_int_columns: usize,
+    nb_float_columns: usize,
+    nb_text_columns: usize,
+    int_values: Vec<usize>,
+    float_values: Vec<f64>,
+    text_values: Vec<String>,
+    labels: Vec<usize>,
+}
+
+impl Samples {
+    pub fn new(columns: Columns) -> Samples {
+        let (mut nb_int_columns, mut nb_float_columns, mut nb_text_columns) = (0, 0, 0);
+        let column_type_indexes = columns.types().iter()
+            .map(|column_type| {
+                let nb_columns = match column_type {
+                    ColumnType::Int => &mut nb_int_columns,
+                    ColumnType::Float => &mut nb_float_columns,
+                    ColumnType::Text => &mut nb_text_columns,
+                };
+                *nb_columns += 1;
+                *nb_columns - 1
+            })
+            .collect();
+        Samples {
+            columns,
+            column_type_indexes,
+            nb_int_columns,
+            nb_float_columns,
+            nb_text_columns,
+            int_values: Vec::new(),
+            float_values: Vec::new(),
+            text_values: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// add a sample, with its values in the order and of the type of the columns
+    pub fn push(&mut self, values: Vec<SampleValue>, label: usize) {
+        assert_eq!(values.len(), self.columns.len(), "Headers mismatch between samples!");
+        assert!(
+            values.iter().zip(self.columns.types().iter()).all(|(value, column_type)| ColumnType::of(value) == *column_type),
+            "Column types mismatch between samples!"
+        );
+        for value in values {
+            match value {
+                SampleValue::Int(value) => self.int_values.push(value),
+                SampleValue::Float(value) => self.float_values.push(value),
+                SampleValue::Text(value) => self.text_values.push(value),
+            }
+        }
+        self.labels.push(label);
+    }
+
+    pub fn columns(&self) -> &Columns {
+        &self.columns
+    }
+
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    /// number of samples
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// value of the column at the given index, for the sample at the given index
+    fn value(&self, index: usize, column_index: usize) -> SampleValue {
+        let column_type_index = self.column_type_indexes[column_index];
+        match self.columns.types()[column_index] {
+            ColumnType::Int => SampleValue::Int(self.int_values[index * self.nb_int_columns + column_type_index]),
+            ColumnType::Float => SampleValue::Float(self.float_values[index * self.nb_float_columns + column_type_index]),
+            ColumnType::Text => SampleValue::Text(self.text_values[index * self.nb_text_columns + column_type_index].clone()),
+        }
+    }
+
+    /// values of the sample at the given index, in the order of the columns
+    pub fn sample(&self, index: usize) -> Vec<SampleValue> {
+        (0..self.columns.len()).map(|column_index| self.value(index, column_index)).collect()
+    }
+
+    /// value of the given column, for the sample at the given index
+    pub fn get(&self, index: usize, column: &str) -> Option<SampleValue> {
+        let column_index = self.columns.iter().position(|name| name == column)?;
+        Some(self.value(index, column_index))
+    }
+
+    /// (values, label) of each sample
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<SampleValue>, usize)> + '_ {
+        (0..self.len()).map(|index| (self.sample(index), self.labels[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples() {
+        let mut columns = Columns::new();
+        columns.push("byte_2", ColumnType::Int);
+        columns.push("mean", ColumnType::Float);
+        columns.push("byte_10", ColumnType::Int);
+        columns.push("layout", ColumnType::Text);
+        let mut samples = Samples::new(columns);
+        assert!(samples.is_empty());
+        samples.push(vec![SampleValue::Int(1), SampleValue::Float(0.5), SampleValue::Int(2), SampleValue::Text("PV".to_string())], 0);
+        samples.push(vec![SampleValue::Int(3), SampleValue::Float(1.5), SampleValue::Int(4), SampleValue::Text("Z".to_string())], 1);
+
+        // the declared order is kept, the values are stored by type
+        assert_eq!(samples.columns().names(), &["byte_2", "mean", "byte_10", "layout"]);
+        assert_eq!(samples.columns().types(), &[ColumnType::Int, ColumnType::Float, ColumnType::Int, ColumnType::Text]);
+        assert_eq!((samples.int_values.as_slice(), samples.float_values.as_slice()), (&[1, 2, 3, 4][..], &[0.5, 1.5][..]));
+        assert_eq!(samples.text_values, vec!["PV", "Z"]);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(
+            samples.sample(1), 
+            vec![SampleValue::Int(3), SampleValue::Float(1.5), SampleValue::Int(4), SampleValue::Text("Z".to_string())]
+        );
+        assert_eq!(samples.get(0, "byte_10"), Some(SampleValue::Int(2)));
+        assert_eq!(samples.get(0, "layout"), Some(SampleValue::Text("PV".to_string())));
+        assert_eq!(samples.get(0, "other"), None);
+        assert_eq!(samples.iter().map(|(_, label)| label).collect::<Vec<usize>>(), vec![0, 1]);
+        assert_eq!(samples.get(1, "mean").unwrap().as_f64(), Some(1.5));
+    }
+
+    #[test]
+    fn test_samples_iter() {
+        let mut columns = Columns::new();
+        columns.push("a", ColumnType::Int);
+        columns.push("b", ColumnType::Float);
+        let samples_iter = SamplesIter::new(
+            columns.clone(),
+            (0..3).map(|i| (vec![SampleValue::Int(i), SampleValue::Float(i as f64 / 2.0)], i % 2)),
+        );
+        assert_eq!(samples_iter.columns(), &columns);
+
+        let samples = samples_iter.collect_samples();
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples.labels(), &[0, 1, 0]);
+        assert_eq!(samples.get(2, "b"), Some(SampleValue::Float(1.0)));
+
+        // the samples are only generated while iterating
+        let mut nb_generated = 0;
+        let mut samples_iter = SamplesIter::new(
+            columns,
+            (0..3).map(|i| { nb_generated += 1; (vec![SampleValue::Int(i), SampleValue::Float(i as f64)], 0) }),
+        );
+        assert!(samples_iter.next().is_some());
+        drop(samples_iter);
+        assert_eq!(nb_generated, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Headers mismatch between samples!")]
+    fn test_samples_push_wrong_length() {
+        let mut samples = Samples::new(Columns::of_type(["a"], ColumnType::Int));
+        samples.push(vec![SampleValue::Int(1), SampleValue::Int(2)], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Column types mismatch between samples!")]
+    fn test_samples_push_wrong_type() {
+        let mut samples = Samples::new(Columns::of_type(["a"], ColumnType::Int));
+        samples.push(vec![SampleValue::Float(0.5)], 0);
+    }
+}
@@ -1,8 +1,7 @@
-use std::collections::HashMap;
-
-use crate::{graph_structs::Node, params::BLOCK_BYTE_SIZE, utils::{to_n_bits_binary, u64_to_bytes}};
+use crate::{graph_structs::Node, params::BLOCK_BYTE_SIZE, utils::{u64_to_bytes, convert_pointer_to_block}};
 
 use super::GraphEmbedding;
+use super::samples::{SampleValue, Columns, ColumnType};
 
  /// extract the data of the chunk :
 /// get all the bit of the chunk as u8
@@ -41,47 +40,29 @@ pub fn extract_chunk_data_as_bytes(graph_embedding : &GraphEmbedding, addr: u64,
 }
 
 
-/// extract the data of the chunk :
-/// get all the bit of the chunk as char ('1' or 'O')
-pub fn extract_chunk_data_as_bits(graph_embedding : &GraphEmbedding, addr: u64) -> Vec<char> {
-    let mut data = Vec::new();
-    let node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&addr).unwrap();
-
+/// get the bytes of a data block: the value of a value node, or the address of a pointer node
+/// in the heap dump byte order (see convert_pointer_to_block)
+/// None for the other nodes (chunk header, footer)
+pub fn get_block_bytes(graph_embedding : &GraphEmbedding, addr: u64) -> Option<[u8; BLOCK_BYTE_SIZE]> {
+    let node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&addr)?;
     match node {
-        Node::ChunkHeaderNode(chunk_header_node) => {
-            let mut current_addr = chunk_header_node.addr + BLOCK_BYTE_SIZE as u64;
-            let block_size_bit = (BLOCK_BYTE_SIZE * 8) as usize;
-
-            // get the data of the chunk
-            for _ in 1..(chunk_header_node.byte_size/8) {
-                // get the node at the current address
-                let node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&current_addr).unwrap();
+        Node::PointerNode(_) => Some(convert_pointer_to_block(node.points_to().unwrap())),
+        Node::ValueNode(_) => node.get_value(),
+        _ => None,
+    }
+}
 
-                match node {
-                    Node::PointerNode(_) => {
-                        let bits: Vec<char> = to_n_bits_binary(node.points_to().unwrap(), block_size_bit).chars().collect();
-                        data.extend_from_slice(&bits);
-                    },
-                    Node::ValueNode(_) => {
-                        let current_block = node.get_value().unwrap();
-                        
-                        // convert the block to binary
-                        for i in 0..BLOCK_BYTE_SIZE {
-                            // each value of the array are bytes, so 8 bit long
-                            let bits: Vec<char> = to_n_bits_binary(current_block[i] as u64, 8).chars().collect();
-                            data.extend_from_slice(&bits);
-                        }
-                    },
-                    Node::FooterNode(_) => {},
-                    _ => panic!("Node is not a pointer nor a value node"),
-                }
-                
-                current_addr += BLOCK_BYTE_SIZE as u64;
-            }
-        },
+/// extract the user data of the chunk in the heap dump byte order (see get_block_bytes),
+/// unlike extract_chunk_data_as_bytes which writes the pointers in big endian
+pub fn extract_chunk_data_as_heap_bytes(graph_embedding : &GraphEmbedding, chn_addr: u64) -> Vec<u8> {
+    let chunk_byte_size = match graph_embedding.graph_annotate.graph_data.addr_to_node.get(&chn_addr) {
+        Some(Node::ChunkHeaderNode(chunk_header_node)) => chunk_header_node.byte_size,
         _ => panic!("Node is not a chunk"),
-    }
-    data
+    };
+    (1..chunk_byte_size / BLOCK_BYTE_SIZE)
+        .filter_map(|block_index| get_block_bytes(graph_embedding, chn_addr + (block_index * BLOCK_BYTE_SIZE) as u64))
+        .flatten()
+        .collect()
 }
 
 /// get the label of a node
@@ -95,56 +76,44 @@ pub fn get_node_label(graph_embedding : &GraphEmbedding, addr : u64) -> usize {
     }
 }
 
+/// columns of the basics information of the chunk (see get_chunk_basics_informations)
+pub fn chunk_basics_columns() -> Columns {
+    Columns::of_type([
+        "chn_addr",
+        "block_position_in_chunk",
+        "chunk_byte_size",
+        "chunk_ptrs",
+        "chunk_vns",
+        "chunk_number_in_heap",
+    ], ColumnType::Int)
+}
+
 /// extract the basics information of the chunk
-/// Return [
-///     ("chn_addr", addr), 
-///     ("chunk_byte_size", byte_size),
-///     ("chunk_ptrs", nb_pointer_nodes),
-/// ]
+/// Return, in order : 
+///     chn_addr, block_position_in_chunk, chunk_byte_size, 
+///     chunk_ptrs (nb pointer nodes), chunk_vns (nb value nodes), chunk_number_in_heap
 pub fn get_chunk_basics_informations(
     graph_embedding : &GraphEmbedding, 
     addr: u64,
-) -> HashMap<String, usize> {
-    let mut named_features = HashMap::new();
+) -> Vec<SampleValue> {
     let node: &Node = graph_embedding.graph_annotate.graph_data.addr_to_node.get(&addr).unwrap();
 
     // add features from parent chn node
     match node {
         Node::ChunkHeaderNode(chunk_header_node) => {
-            named_features.insert(
-                "chn_addr".to_string(),
-                chunk_header_node.addr as usize
-            );
-
             let block_position_in_chunk = (
                 (node.get_address() - chunk_header_node.addr) / crate::params::BLOCK_BYTE_SIZE as u64
             ) as usize;
-            named_features.insert(
-                "block_position_in_chunk".to_string(),
-                block_position_in_chunk
-            );
-
-            named_features.insert(
-                "chunk_byte_size".to_string(),
-                chunk_header_node.byte_size as usize
-            );
-
-            named_features.insert(
-                "chunk_ptrs".to_string(),
-                chunk_header_node.nb_pointer_nodes as usize
-            );
-
-            named_features.insert(
-                "chunk_vns".to_string(),
-                chunk_header_node.nb_value_nodes as usize
-            );
 
-            named_features.insert(
-                "chunk_number_in_heap".to_string(),
-                chunk_header_node.chunk_number_in_heap as usize
-            );
+            vec![
+                SampleValue::Int(chunk_header_node.addr as usize),
+                SampleValue::Int(block_position_in_chunk),
+                SampleValue::Int(chunk_header_node.byte_size),
+                SampleValue::Int(chunk_header_node.nb_pointer_nodes),
+                SampleValue::Int(chunk_header_node.nb_value_nodes),
+                SampleValue::Int(chunk_header_node.chunk_number_in_heap),
+            ]
         },
         _ => panic!("Node is not a chunk"),
     }
-    named_features
 }
\ No newline at end of file
@@ -108,6 +108,25 @@ impl AnnotationSet {
         embedding_class
     }
 
+    /// bit of each subclass in the embedding value (label) of an annotation set
+    /// NOTE: 0 means no annotation
+    pub fn label_encoding() -> Vec<(&'static str, u8)> {
+        vec![
+            ("key", AnnotationSubclass::Key as u8),
+            ("ssh_struct", AnnotationSubclass::SshStruct as u8),
+            ("session_state", AnnotationSubclass::SessionState as u8),
+        ]
+    }
+
+    /// names of the subclasses of the annotation set, in the order of label_encoding
+    pub fn get_subclass_names(&self) -> Vec<&'static str> {
+        let class = self.annotation_set_embedding();
+        AnnotationSet::label_encoding().into_iter()
+            .filter(|(_, bit)| class & bit != 0)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
     pub fn new(annotation: NodeAnnotation) -> AnnotationSet {
         let mut set = HashSet::new();
         set.insert(annotation);
@@ -274,6 +293,14 @@ pub struct KeyDataJSON {
     pub real_len: usize,
 }
 
+/// Key bytes from JSON file, without any address.
+/// Used to search the key in the whole heap dump.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyPatternJSON {
+    pub name: String,
+    pub key: Vec<u8>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct KeyAnnotation {
     pub addr: u64, // address of annotated node
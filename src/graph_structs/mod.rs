@@ -259,6 +259,7 @@ pub struct PointerNode {
 
 pub const DEFAULT_CHUNK_EDGE_WEIGHT: usize = 1;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Edge {
     pub from: u64,
     pub to: u64,
@@ -266,6 +267,7 @@ pub struct Edge {
     pub weight: usize, // Number of edge pointers between the two nodes, default is 1 for a DataStructure edge.
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub enum EdgeType {
     ChunkEdge,
     PointerEdge,
@@ -0,0 +1,26 @@
+//! Convert heap dumps into memory graphs, annotate them and generate their embeddings.
+//!
+//! The parameters are given explicitly (see GraphEmbeddingBuilder),
+//! so that the library doesn't depend on the CLI arguments nor on the environment variables.
+//! NOTE: The CLI (main.rs) is a thin wrapper over this library.
+
+// link modules
+pub mod params;
+pub mod graph_data;
+pub mod graph_structs;
+pub mod utils;
+pub mod graph_annotate;
+pub mod graph_embedding;
+pub mod exe_pipeline;
+
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(test)]
+mod tests;
+
+pub use graph_data::heap_dump_data::HeapDumpData;
+pub use graph_data::GraphData;
+pub use graph_annotate::GraphAnnotate;
+pub use graph_embedding::GraphEmbedding;
+pub use graph_embedding::builder::GraphEmbeddingBuilder;
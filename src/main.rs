@@ -1,51 +1,39 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use exe_pipeline::chunk_extract::gen_and_save_chunk_extract;
-use exe_pipeline::chunk_semantic_embedding::gen_and_save_chunk_semantic_embedding;
-use exe_pipeline::chunk_start_bytes_embedding::gen_and_save_chunk_start_bytes_embedding;
-use exe_pipeline::chunk_statistic_embedding::gen_and_save_chunk_statistic_embedding;
-use exe_pipeline::chunk_top_vn_semantic_embedding::gen_and_save_chunk_top_vn_semantic_embedding;
-use exe_pipeline::graph_generation::gen_and_save_memory_graph;
-use exe_pipeline::graph_generation_with_embedding::gen_and_save_memory_graph_with_embedding_comments;
-use exe_pipeline::pipeline::{embedding_pipeline, embedding_pipeline_to_csv};
-use exe_pipeline::value_embedding::gen_and_save_value_node_embedding;
-use params::argv::Pipeline;
-
-// link modules
-mod params;
-mod tests;
-mod graph_data;
-mod graph_structs;
-mod utils;
-mod graph_annotate;
-mod graph_embedding;
-mod exe_pipeline;
+use mem_to_graph::exe_pipeline::manifest::RUN_MANIFEST_FILE_NAME;
+use mem_to_graph::exe_pipeline::pipeline::{embedding_pipeline, get_gen_and_write_samples};
+use mem_to_graph::graph_data::heap_dump_data::load_key_patterns_file;
+use mem_to_graph::params::{self, argv::{Pipeline, OutputFormat, GraphFormat, KeyAnnotationSource, SelectAnnotationLocation}, config::RunConfig};
 
 fn main() {
-    crate::params::init();
+    init();
+    let run_configs = params::config::get_run_configs();
+    // NOTE: the inputs and output are the same for all the variants
+    let run_config = &run_configs[0];
 
     // call pipeline
     let mut input_path: Vec<std::path::PathBuf> = Vec::new();
-    if params::ARGV.files.is_some() {
-        let files = params::ARGV.files.as_ref().unwrap();
+    if run_config.files.is_some() {
+        let files = run_config.files.as_ref().unwrap();
         for (_, file) in files.iter().enumerate() {
             input_path.push(PathBuf::from(file));
         }
-    } else if params::ARGV.directories.is_some() {
-        for path in params::ARGV.directories.as_ref().unwrap() {
+    } else if run_config.directories.is_some() {
+        for path in run_config.directories.as_ref().unwrap() {
             input_path.push(PathBuf::from(path));
         }
     } else {
         // default
-        input_path.push(params::DEFAULT_DATA_DIR_PATH.clone());
+        input_path.push(get_path_from_env("PROJECT_BASE_DIR").join(get_path_from_env("DEFAULT_DATA_DIR_PATH")));
     }
 
     // output folder
     let output_folder;
-    if params::ARGV.output.is_some() {
-        output_folder = PathBuf::from(params::ARGV.output.as_ref().unwrap());
+    if run_config.output.is_some() {
+        output_folder = PathBuf::from(run_config.output.as_ref().unwrap());
     } else {
-        output_folder = params::DEFAULT_SAVE_SAMPLES_AND_LABELS_DIR_PATH.clone();
+        output_folder = get_path_from_env("DEFAULT_SAVE_SAMPLES_AND_LABELS_DIR_PATH");
     }
 
     // test all provided paths
@@ -54,120 +42,130 @@ fn main() {
             panic!("🚩 The path doesn't exist: {}", path.to_str().unwrap());
         }
     }
-    // annotation of the graph
-    let annotation = params::ARGV.annotation;
-    let no_value_node = params::ARGV.no_value_node;
-    let entropy_filter = params::ARGV.entropy_filter;
-    let chunk_byte_size_filter = params::ARGV.chunk_byte_size_filter;
 
-    // launch computations
-    for path in input_path {
-        match params::ARGV.pipeline {
-            params::argv::Pipeline::ValueNodeEmbedding => {
-                no_arg_no_value_and_pointer_node(params::ARGV.pipeline);
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    false,
-                    gen_and_save_value_node_embedding
-                )
-            },
-            params::argv::Pipeline::Graph => {
-                embedding_pipeline(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    no_value_node,
-                    gen_and_save_memory_graph,
-                    "dot.gv",
-                )
-            },
-            Pipeline::GraphWithEmbeddingComments => {
-                embedding_pipeline(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    no_value_node,
-                    gen_and_save_memory_graph_with_embedding_comments,
-                    "dot.gv",
-                )
-            },
-            params::argv::Pipeline::ChunkSemanticEmbedding => {
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter, 
-                    chunk_byte_size_filter,
-                    no_value_node,
-                    gen_and_save_chunk_semantic_embedding
-                )
-            },
-            params::argv::Pipeline::ChunkStatisticEmbedding => {
-                no_arg_no_value_and_pointer_node(params::ARGV.pipeline);
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter, 
-                    chunk_byte_size_filter,
-                    false,
-                    gen_and_save_chunk_statistic_embedding,
-                )
-            },
-            params::argv::Pipeline::ChunkTopVnSemanticEmbedding => {
-                no_arg_no_value_and_pointer_node(params::ARGV.pipeline);
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    false,
-                    gen_and_save_chunk_top_vn_semantic_embedding
-                )
-            },
-            params::argv::Pipeline::ChunkStartBytesEmbedding => {
-                no_arg_no_value_and_pointer_node(params::ARGV.pipeline);
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    false,
-                    gen_and_save_chunk_start_bytes_embedding
-                )
-            },
-            Pipeline::ChunkExtraction => {
-                no_arg_no_value_and_pointer_node(params::ARGV.pipeline);
-                embedding_pipeline_to_csv(
-                    path, 
-                    output_folder.clone(), 
-                    annotation, 
-                    entropy_filter,
-                    chunk_byte_size_filter,
-                    false,
-                    gen_and_save_chunk_extract
-                )
-            },
+    // check the variants
+    for run_config in run_configs.iter() {
+        match run_config.pipeline {
+            Pipeline::Graph | Pipeline::GraphWithEmbeddingComments | Pipeline::GraphTensors
+                | Pipeline::ChunkSemanticEmbedding | Pipeline::ChunkWlSubtreeEmbedding | Pipeline::ChunkCentralityEmbedding
+                | Pipeline::RandomWalkCorpus | Pipeline::ChunkRandomWalkEmbedding => {},
+            _ => no_arg_no_value_and_pointer_node(run_config),
+        }
+        if run_config.aggregate && get_gen_and_write_samples(run_config.pipeline).is_none() {
+            panic!("🚩 The pipeline {:?} doesn't produce samples, it can't be aggregated", run_config.pipeline);
+        }
+        if run_config.pipeline == Pipeline::GraphTensors && run_config.annotation != SelectAnnotationLocation::ChunkHeaderNode {
+            panic!("🚩 The pipeline {:?} needs the {:?} annotation (-a chunk-header-node)", run_config.pipeline, SelectAnnotationLocation::ChunkHeaderNode);
+        }
+        if run_config.graph_format != GraphFormat::Dot && !matches!(run_config.pipeline, Pipeline::Graph | Pipeline::GraphWithEmbeddingComments) {
+            panic!("🚩 The {:?} graph format is only for the graph and graph-with-embedding-comments pipelines", run_config.graph_format);
+        }
+        if run_config.output_format != OutputFormat::Csv {
+            if run_config.aggregate || get_gen_and_write_samples(run_config.pipeline).is_none() {
+                panic!("🚩 The {:?} output format is only for the samples of each file (not graphs, not aggregated)", run_config.output_format);
+            }
+            if run_config.pipeline == Pipeline::ChunkExtraction {
+                panic!("🚩 The pipeline {:?} produces text samples, they can only be saved as CSV", run_config.pipeline);
+            }
+        }
+        if let Some(key_patterns_file) = &run_config.key_patterns_file {
+            if run_config.key_annotation_source != KeyAnnotationSource::BytePatternSearch {
+                panic!("🚩 The key patterns file is only used by the {:?} key annotation source", KeyAnnotationSource::BytePatternSearch);
+            }
+            // check the key patterns before the computations
+            load_key_patterns_file(key_patterns_file).unwrap_or_else(|err| panic!("🚩 {}", err));
         }
     }
+
+    // an output folder holding a run manifest is resumed, unless forced
+    if !run_config.force {
+        for run_config in run_configs.iter() {
+            let run_manifest_path = run_config.variant_output_folder(&output_folder).join(RUN_MANIFEST_FILE_NAME);
+            if run_manifest_path.exists() {
+                log::info!(
+                    " 🔵 Resuming the run manifest: {} (use --force to redo all the outputs)", 
+                    run_manifest_path.to_str().unwrap()
+                );
+            }
+        }
+    }
+
+    // launch computations
+    embedding_pipeline(
+        input_path, 
+        output_folder, 
+        &run_configs,
+        run_config.force,
+    )
 }
 
 /// This function is call if the flag '-v' is used on a pipeline that doesn't use it
 /// 
 /// NOTE: The flag '-v' is used to remove the value node and the pointer node
 /// This function ensure that this is not done on a pipeline that use value nodes.
-fn no_arg_no_value_and_pointer_node(pipeline : Pipeline) {
-    if params::ARGV.no_value_node {
-        panic!("🚩 The flag '-v' is used with the wrong pipeline : {:?}", pipeline);
+fn no_arg_no_value_and_pointer_node(run_config : &RunConfig) {
+    if run_config.no_value_node {
+        panic!("🚩 The flag '-v' is used with the wrong pipeline : {:?}", run_config.pipeline);
+    }
+}
+
+/// Load the optional .env file and initialize the logger.
+/// WARN: Must be called before any logging is done.
+fn init() {
+    // NOTE: the .env file is optional, the parameters can be given with a config file
+    print!("Loading .env file... ");
+    if dotenv::dotenv().is_err() {
+        print!("(no .env file) ");
     }
-}
\ No newline at end of file
+
+    print!("Initializing logger... ");
+    init_logger();
+}
+
+/// Initialize the logger, with the level of the LOGGER_MODE environment variable (info by default).
+fn init_logger() {
+    let log_directory = "./log";
+    std::fs::create_dir_all(log_directory).expect("Failed to create log directory");
+
+    let file_out = fern::log_file(format!("{}/output.log", log_directory)).expect("Failed to open log file");
+
+    // Parse the log level from LOGGER_MODE
+    let log_level = match std::env::var("LOGGER_MODE") {
+        Ok(logger_mode) => log::LevelFilter::from_str(logger_mode.as_str()).unwrap_or_else(|_| {
+            println!("Invalid LOGGER_MODE value. Defaulting to 'info'.");
+            log::LevelFilter::Info
+        }),
+        Err(_) => log::LevelFilter::Info,
+    };
+
+    let logger_config = fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {}][{} {}] {}",
+                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
+                chrono::offset::Utc::now().format("%Z"),
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .level(log_level)
+        .chain(file_out)
+        .chain(fern::Output::call(|record| {
+            println!("{}", record.args());
+        }))
+        .apply();
+
+    if let Err(e) = logger_config {
+        panic!("Failed to initialize logger: {}", e);
+    }
+
+    log::info!(" 🚀 starting mem to graph converter");
+}
+
+/// path of an environment variable (see .env), for the default input and output
+fn get_path_from_env(var_name: &str) -> PathBuf {
+    PathBuf::from(std::env::var(var_name).unwrap_or_else(
+        |_| panic!("🚩 No input or output given, and the {} environment variable is not set", var_name)
+    ))
+}
@@ -1,5 +1,6 @@
 use clap::{Parser, ValueEnum};
-use serde_derive::Deserialize;
+use std::path::PathBuf;
+use serde_derive::{Serialize, Deserialize};
 
 // NOTE: 'group = "file_input_group"' means that only one of the options in the group can be used
 // the result is stored always in 'files_input', and on the option used (the other is None)
@@ -23,15 +24,24 @@ pub struct Argv {
     pub files_input: Option<Vec<String>>, 
 
     /// The pipeline to run
-    #[arg(value_enum, short, long, default_value = "value-node-embedding")]
-    pub pipeline: Pipeline,
-
-    /// A flag that's only relevant when pipeline is set to 'graph-with-embedding'
-    /// Specifies the type of embedding to use for the graph node comments
+    /// NOTE : Several pipelines can be run in a single pass, by repeating the flag
+    ///     or separating them with commas (ex: -p chunk-semantic-embedding,chunk-statistic-embedding).
+    ///     Each pipeline is then saved in its own output subfolder (see the config file variants).
+    #[arg(value_enum, short, long, default_value = "value-node-embedding", value_delimiter = ',')]
+    pub pipeline: Vec<Pipeline>,
+
+    /// A flag that's only relevant when pipeline is set to 'graph-with-embedding' or 'graph-tensors'
+    /// Specifies the type of embedding to use for the graph node comments (or the node features of the graph tensors)
     /// Supported values: 'chunk-semantic-embedding', 'chunk-statistic-embedding', 'chunk-start-bytes-embedding'
     #[arg(short = 'c', long, required = false, requires = "pipeline", default_value = "chunk-semantic-embedding", hide_possible_values = true)]
     pub graph_comment_embedding_type: Pipeline,
 
+    /// format of the graph file of each heap dump, for the graph pipelines
+    /// 
+    /// NOTE : GraphML and GEXF save the node features and the embedding fields as typed attributes
+    #[arg(long, default_value = "dot")]
+    pub graph_format: GraphFormat,
+
     /// The directory to output the results
     #[arg(short, long, required = false)]
     pub output: Option<String>,
@@ -41,6 +51,16 @@ pub struct Argv {
     #[arg(short = 'a', long, default_value = "value-node")]
     pub annotation: SelectAnnotationLocation,
 
+    /// How the keys are found in the heap dump for the annotation
+    /// NOTE : By default, the keys are located using the KEY_X_ADDR of the json file
+    #[arg(short = 'k', long, default_value = "json-address")]
+    pub key_annotation_source: KeyAnnotationSource,
+
+    /// Text file of the keys to search with the byte pattern search, one hex key per line
+    /// NOTE : By default, the keys are the KEY_X values of the json file of each heap dump
+    #[arg(long, required = false)]
+    pub key_patterns_file: Option<PathBuf>,
+
     /// If the embedding is filtered with the entropy of the firsts blocks of each chunk
     /// NOTE : only used in the embedding pipeline
     #[arg(short = 'e', long, default_value = "none")]
@@ -59,10 +79,51 @@ pub struct Argv {
     ///     'ChunkSemanticEmbedding' or 'ChunkExtraction' or 'graph'
     #[arg(short = 'v', long, action)]
     pub no_value_node: bool,
+
+    /// if the PointerNodes holding key bytes are converted back to ValueNodes
+    /// 
+    /// NOTE : Random key bytes can look like a pointer to the heap, 
+    ///     which adds spurious edges to the graph
+    /// NOTE : This flag is only used if the graph is annotated
+    #[arg(short = 'r', long, action)]
+    pub repair_key_pointer_nodes: bool,
+
+    /// Run configuration file (TOML)
+    /// 
+    /// NOTE : The precedence is CLI > config file > environment variables > defaults
+    /// NOTE : The resolved configuration is saved next to every output
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// save the samples of all the files in a single dataset, split in CSV shards,
+    /// with provenance columns (source file, directory category, SSH version, heap start)
+    /// 
+    /// NOTE : Only for the pipelines producing samples (not the graph pipelines)
+    #[arg(long, action)]
+    pub aggregate: bool,
+
+    /// format of the samples and labels file of each heap dump
+    /// 
+    /// NOTE : The NPY formats are only for the pipelines producing numeric samples
+    ///     (not the graph pipelines nor 'chunk-extraction'), and not aggregated
+    #[arg(long, default_value = "csv")]
+    pub output_format: OutputFormat,
+
+    /// resume a run in an output folder holding a run manifest (default)
+    /// 
+    /// NOTE : The outputs recorded as done in the run manifest, from the same input files 
+    ///     and with the same parameters, are skipped. The other ones are redone.
+    #[arg(long, action, conflicts_with = "force")]
+    pub resume: bool,
+
+    /// redo all the outputs of an output folder holding a run manifest
+    #[arg(long, action)]
+    pub force: bool,
 }
 
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 /// control the annotation of the graph
 /// This specifies where we want the annotation to be
 /// By default the annotation is on the value node
@@ -72,15 +133,29 @@ pub enum SelectAnnotationLocation {
     ValueNode,
     /// annotate the chunk header node
     ChunkHeaderNode,
+    /// annotate every value node or pointer node covering the bytes of a key
+    /// NOTE : the other annotations (ssh struct, session state) are annotated as for the value node
+    KeySpan,
     /// don't annotate the graph
     None,
 }
 
+/// control how the keys are located in the heap dump during the annotation
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAnnotationSource {
+    /// use the KEY_X_ADDR of the json file, and check the bytes found at this address
+    JsonAddress,
+    /// search the bytes of every KEY_X of the json file in the whole heap dump (no address needed)
+    /// NOTE : every occurrence of a key is annotated, including copies
+    BytePatternSearch,
+}
 
 /// Filter the embedding with the entropy of the firsts blocks of each chunk
 /// NOTE : the entropy is computed on the firsts blocks of each chunk
 /// NOTE : the annotated blocks are not filtered
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum EntropyFilter {
     /// don't filter the embedding
     None,
@@ -93,7 +168,8 @@ pub enum EntropyFilter {
 
 /// Filter the embedding with the size of the user data of each chunk
 /// NOTE : the annotated blocks are not filtered
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ChunkByteSizeFilter {
     /// don't filter the embedding
     None,
@@ -101,20 +177,39 @@ pub enum ChunkByteSizeFilter {
     Activate,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Pipeline {
     /// make the value embedding
     ValueNodeEmbedding,
+    /// make the value embedding, with the bytes of the value node and of the blocks around it
+    ValueNodeWindowEmbedding,
     /// make chunk top value node semantic embedding
     ChunkTopVnSemanticEmbedding,
     /// make the graph and save it
     Graph,
     /// make the graph and save it with the embedding on nodes as comment
     GraphWithEmbeddingComments,
+    /// make the tensors of the chunk graph (NPZ), for the GNN libraries
+    GraphTensors,
     /// make a semantic embedding of the chunk
     ChunkSemanticEmbedding,
+    /// make a Weisfeiler-Lehman subtree embedding of the chunk (hashed histogram of the WL labels)
+    ChunkWlSubtreeEmbedding,
+    /// make an embedding of the position of the chunk in the graph of the chunks (degrees, PageRank, HITS, betweenness, SCC)
+    ChunkCentralityEmbedding,
+    /// make the corpus of the random walks over the graph (one walk of node addresses per line), for word2vec
+    RandomWalkCorpus,
+    /// make an embedding of the chunk learned by a skip-gram on the random walks over the graph (node2vec)
+    ChunkRandomWalkEmbedding,
     /// make a statistic embedding of the chunk
     ChunkStatisticEmbedding,
+    /// make an embedding of the bytes of the chunk (byte histogram, hashed byte bigrams, byte ratios)
+    ChunkByteHistogramEmbedding,
+    /// make an embedding of the entropy of the windows of blocks across each chunk
+    ChunkEntropyProfileEmbedding,
+    /// make an embedding of the layout of each chunk (pointer, value, zero and footer blocks)
+    ChunkLayoutEmbedding,
     /// make an embeding with the beginning of each chunk (the number of bytes is controlled by CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY)
     ChunkStartBytesEmbedding,
 
@@ -122,6 +217,30 @@ pub enum Pipeline {
     ChunkExtraction,
 }
 
+/// format of the samples and labels file of each heap dump
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// CSV file, with a last "label" column
+    Csv,
+    /// NPY array, with a last "label" column, and a JSON sidecar of the columns
+    Npy,
+    /// NPZ archive of the "samples" and "labels" arrays, and a JSON sidecar of the columns
+    Npz,
+}
+
+/// format of the graph file of each heap dump
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphFormat {
+    /// Graphviz dot file, with the embedding fields in the comments of the nodes
+    Dot,
+    /// GraphML file, with typed attributes (NetworkX, igraph, Gephi)
+    Graphml,
+    /// GEXF file, with typed attributes (Gephi, NetworkX)
+    Gexf,
+}
+
 pub fn get_program_args() -> Argv {
     return Argv::parse();
 }
\ No newline at end of file
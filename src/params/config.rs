@@ -0,0 +1,626 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, ValueEnum};
+use clap::parser::ValueSource;
+use serde_derive::{Serialize, Deserialize};
+
+use crate::graph_embedding::builder::GraphEmbeddingBuilder;
+use crate::graph_embedding::entropy_profile::EntropyProfileParams;
+use crate::graph_embedding::random_walk::RandomWalkParams;
+use super::{DEFAULT_EMBEDDING_DEPTH, DEFAULT_N_GRAM, DEFAULT_CHUNK_BYTES_SIZE_TO_KEEP_FILTER, DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP, DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY, DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING};
+use super::{DEFAULT_AGGREGATE_SHARD_NB_SAMPLES, DEFAULT_WL_NB_OF_HASH_BINS, DEFAULT_BETWEENNESS_NB_OF_SOURCES, DEFAULT_BYTE_BIGRAM_NB_OF_BUCKETS, DEFAULT_VALUE_NODE_WINDOW_NB_OF_BLOCKS, DEFAULT_CHUNK_LAYOUT_NB_OF_BLOCKS};
+use crate::utils::{ErrorKind, string_to_usize_vec, MAX_N_GRAM};
+use super::argv::{Argv, Pipeline, SelectAnnotationLocation, KeyAnnotationSource, EntropyFilter, ChunkByteSizeFilter, OutputFormat, GraphFormat};
+
+/// Run configuration file (TOML). Every field is optional.
+/// NOTE: The fields are the CLI long flags (with '_' instead of '-'),
+///     and the former environment variables (in lowercase).
+///
+/// ex:
+/// ```toml
+/// pipeline = "chunk-semantic-embedding"
+/// annotation = "chunk-header-node"
+/// no_value_node = true
+/// n_gram = [1, 2, 3]
+/// ```
+///
+/// NOTE: Several pipeline variants can be run in a single pass, each graph being built
+///     once per heap dump file and shared by the variants with the same graph.
+///     The fields of a variant override the ones of the file (except files, directories and output).
+///
+/// ex:
+/// ```toml
+/// annotation = "chunk-header-node"
+///
+/// [[variants]]
+/// pipeline = "chunk-semantic-embedding"
+///
+/// [[variants]]
+/// name = "statistic_filtered"
+/// pipeline = "chunk-statistic-embedding"
+/// entropy_filter = "only-max-entropy"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConfigFile {
+    pub files: Option<Vec<String>>,
+    pub directories: Option<Vec<String>>,
+    pub output: Option<String>,
+    pub pipeline: Option<Pipeline>,
+    pub graph_comment_embedding_type: Option<Pipeline>,
+    pub graph_format: Option<GraphFormat>,
+    pub annotation: Option<SelectAnnotationLocation>,
+    pub key_annotation_source: Option<KeyAnnotationSource>,
+    /// text file of the keys of the byte pattern search (see load_key_patterns_file)
+    pub key_patterns_file: Option<PathBuf>,
+    pub entropy_filter: Option<EntropyFilter>,
+    pub chunk_byte_size_filter: Option<ChunkByteSizeFilter>,
+    pub no_value_node: Option<bool>,
+    pub repair_key_pointer_nodes: Option<bool>,
+    pub aggregate: Option<bool>,
+    /// maximum number of samples per shard of an aggregated dataset
+    pub aggregate_shard_nb_samples: Option<usize>,
+    pub output_format: Option<OutputFormat>,
+
+    pub embedding_depth: Option<usize>,
+    pub n_gram: Option<Vec<usize>>,
+    pub chunk_bytes_size_to_keep_filter: Option<Vec<usize>>,
+    pub min_nb_of_chunks_to_keep: Option<usize>,
+    pub chunk_nb_of_start_bytes_for_chunk_entropy: Option<usize>,
+    pub chunk_nb_of_start_bytes_for_chunk_embedding: Option<usize>,
+    /// number of bins of the histogram of the chunk WL subtree embedding
+    pub wl_nb_of_hash_bins: Option<usize>,
+    /// number of source chunks of the approximate betweenness of the chunk centrality embedding
+    pub betweenness_nb_of_sources: Option<usize>,
+    /// number of buckets of the byte bigram counts of the chunk byte histogram embedding
+    pub byte_bigram_nb_of_buckets: Option<usize>,
+    /// number of blocks before and after each value node in the value node window embedding
+    pub value_node_window_nb_of_blocks: Option<usize>,
+    /// number of blocks of the layout of each chunk in the chunk layout embedding
+    pub chunk_layout_nb_of_blocks: Option<usize>,
+    /// [random_walk] table, see RandomWalkParams
+    /// NOTE: The table of a variant replaces the whole table of the file.
+    pub random_walk: Option<RandomWalkParams>,
+    /// [entropy_profile] table, see EntropyProfileParams
+    /// NOTE: The table of a variant replaces the whole table of the file.
+    pub entropy_profile: Option<EntropyProfileParams>,
+
+    /// name of a variant, used as its output subfolder
+    pub name: Option<String>,
+    pub variants: Vec<RunConfigFile>,
+}
+
+impl RunConfigFile {
+    pub fn load(config_file_path: &Path) -> Result<RunConfigFile, ErrorKind> {
+        let content = std::fs::read_to_string(config_file_path).map_err(|err| {
+            ErrorKind::InvalidConfigFile(config_file_path.to_path_buf(), err.to_string())
+        })?;
+        toml::from_str(&content).map_err(|err| {
+            ErrorKind::InvalidConfigFile(config_file_path.to_path_buf(), err.to_string())
+        })
+    }
+
+    /// the fields of the variant, completed by the ones of the base config file
+    fn variant_with_base(self, base: &RunConfigFile) -> Result<RunConfigFile, String> {
+        if self.files.is_some() || self.directories.is_some() || self.output.is_some() || !self.variants.is_empty() {
+            return Err("files, directories, output and variants can't be set in a variant".to_string());
+        }
+        Ok(RunConfigFile {
+            files: base.files.clone(),
+            directories: base.directories.clone(),
+            output: base.output.clone(),
+            pipeline: self.pipeline.or(base.pipeline),
+            graph_comment_embedding_type: self.graph_comment_embedding_type.or(base.graph_comment_embedding_type),
+            graph_format: self.graph_format.or(base.graph_format),
+            annotation: self.annotation.or(base.annotation),
+            key_annotation_source: self.key_annotation_source.or(base.key_annotation_source),
+            key_patterns_file: self.key_patterns_file.or_else(|| base.key_patterns_file.clone()),
+            entropy_filter: self.entropy_filter.or(base.entropy_filter),
+            chunk_byte_size_filter: self.chunk_byte_size_filter.or(base.chunk_byte_size_filter),
+            no_value_node: self.no_value_node.or(base.no_value_node),
+            repair_key_pointer_nodes: self.repair_key_pointer_nodes.or(base.repair_key_pointer_nodes),
+            aggregate: self.aggregate.or(base.aggregate),
+            aggregate_shard_nb_samples: self.aggregate_shard_nb_samples.or(base.aggregate_shard_nb_samples),
+            output_format: self.output_format.or(base.output_format),
+
+            embedding_depth: self.embedding_depth.or(base.embedding_depth),
+            n_gram: self.n_gram.or_else(|| base.n_gram.clone()),
+            chunk_bytes_size_to_keep_filter: self.chunk_bytes_size_to_keep_filter.or_else(
+                || base.chunk_bytes_size_to_keep_filter.clone()
+            ),
+            min_nb_of_chunks_to_keep: self.min_nb_of_chunks_to_keep.or(base.min_nb_of_chunks_to_keep),
+            chunk_nb_of_start_bytes_for_chunk_entropy: self.chunk_nb_of_start_bytes_for_chunk_entropy.or(
+                base.chunk_nb_of_start_bytes_for_chunk_entropy
+            ),
+            chunk_nb_of_start_bytes_for_chunk_embedding: self.chunk_nb_of_start_bytes_for_chunk_embedding.or(
+                base.chunk_nb_of_start_bytes_for_chunk_embedding
+            ),
+            wl_nb_of_hash_bins: self.wl_nb_of_hash_bins.or(base.wl_nb_of_hash_bins),
+            betweenness_nb_of_sources: self.betweenness_nb_of_sources.or(base.betweenness_nb_of_sources),
+            byte_bigram_nb_of_buckets: self.byte_bigram_nb_of_buckets.or(base.byte_bigram_nb_of_buckets),
+            value_node_window_nb_of_blocks: self.value_node_window_nb_of_blocks.or(base.value_node_window_nb_of_blocks),
+            chunk_layout_nb_of_blocks: self.chunk_layout_nb_of_blocks.or(base.chunk_layout_nb_of_blocks),
+            random_walk: self.random_walk.or(base.random_walk),
+            entropy_profile: self.entropy_profile.or(base.entropy_profile),
+
+            name: self.name,
+            variants: Vec::new(),
+        })
+    }
+}
+
+/// Resolved run configuration, with the precedence:
+/// CLI > config file > environment variables > defaults
+/// NOTE: An environment variable is only read if the config file does not set the parameter.
+/// NOTE: This is saved next to every output, so that each output can be traced to its exact parameters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunConfig {
+    /// name of the variant (None if there is a single variant)
+    /// NOTE: Not saved, so that the saved configuration reproduces the output in the given output folder.
+    #[serde(skip_serializing)]
+    pub name: Option<String>,
+    pub files: Option<Vec<String>>,
+    pub directories: Option<Vec<String>>,
+    pub output: Option<String>,
+    pub pipeline: Pipeline,
+    pub graph_comment_embedding_type: Pipeline,
+    pub graph_format: GraphFormat,
+    pub annotation: SelectAnnotationLocation,
+    pub key_annotation_source: KeyAnnotationSource,
+    pub key_patterns_file: Option<PathBuf>,
+    pub entropy_filter: EntropyFilter,
+    pub chunk_byte_size_filter: ChunkByteSizeFilter,
+    pub no_value_node: bool,
+    pub repair_key_pointer_nodes: bool,
+    pub aggregate: bool,
+    pub aggregate_shard_nb_samples: usize,
+    pub output_format: OutputFormat,
+
+    pub embedding_depth: usize,
+    pub n_gram: Vec<usize>,
+    pub chunk_bytes_size_to_keep_filter: Vec<usize>,
+    pub min_nb_of_chunks_to_keep: usize,
+    pub chunk_nb_of_start_bytes_for_chunk_entropy: usize,
+    pub chunk_nb_of_start_bytes_for_chunk_embedding: usize,
+    pub wl_nb_of_hash_bins: usize,
+    pub betweenness_nb_of_sources: usize,
+    pub byte_bigram_nb_of_buckets: usize,
+    pub value_node_window_nb_of_blocks: usize,
+    pub chunk_layout_nb_of_blocks: usize,
+    pub random_walk: RandomWalkParams,
+    pub entropy_profile: EntropyProfileParams,
+
+    /// CLI only, see Argv::resume and Argv::force
+    #[serde(skip_serializing)]
+    pub resume: bool,
+    #[serde(skip_serializing)]
+    pub force: bool,
+}
+
+/// config file value if any, else the parsed value of the environment variable (see .env), None if it is not set
+/// NOTE: The environment variable is only read (and parsed) if the config file doesn't set the value,
+///     so that a malformed variable doesn't matter when it is overridden.
+fn config_or_env<T>(
+    config_value: Option<T>, name: &str, parse: impl Fn(&str) -> Result<T, String>
+) -> Result<Option<T>, String> {
+    if config_value.is_some() {
+        return Ok(config_value);
+    }
+    std::env::var(name).ok()
+        .map(|value| parse(&value).map_err(
+            |err| format!("invalid {} environment variable: {:?}, {}", name, value, err)
+        ))
+        .transpose()
+}
+
+/// parse a single value of an environment variable (ex: "3")
+fn parse_env_value<T: FromStr>(value: &str) -> Result<T, String> where T::Err: std::fmt::Display {
+    value.trim().parse().map_err(|err: T::Err| err.to_string())
+}
+
+/// parse a list of usize of an environment variable, separated by commas (ex: "1,2,3")
+fn parse_env_usize_vec(value: &str) -> Result<Vec<usize>, String> {
+    string_to_usize_vec(value).map_err(|err| err.to_string())
+}
+
+/// default name of a variant, after its index and pipeline (ex: "0_chunk-semantic-embedding")
+fn variant_name(index: usize, pipeline: Pipeline) -> String {
+    format!("{}_{}", index, pipeline.to_possible_value().unwrap().get_name())
+}
+
+/// keep the CLI value if it was given on the command line,
+/// else the config file value if any, else the CLI default value
+fn cli_or_config<T>(matches: &ArgMatches, id: &str, cli_value: T, config_value: Option<T>) -> T {
+    if matches.value_source(id) == Some(ValueSource::CommandLine) {
+        return cli_value;
+    }
+    config_value.unwrap_or(cli_value)
+}
+
+impl RunConfig {
+    /// Resolve the run configuration from the CLI arguments and the optional config file.
+    /// NOTE: The parameters without CLI flag fallback to their environment variable.
+    /// Return an error if an environment variable used by the resolution is malformed.
+    pub fn resolve(argv: &Argv, matches: &ArgMatches, config_file: Option<RunConfigFile>) -> Result<RunConfig, String> {
+        let config_file = config_file.unwrap_or_default();
+
+        // files and directories are exclusive, the CLI input replaces the config file one
+        let (files, directories) = if argv.files.is_some() || argv.directories.is_some() {
+            (argv.files.clone(), argv.directories.clone())
+        } else {
+            (config_file.files, config_file.directories)
+        };
+
+        let mut n_gram = config_or_env(config_file.n_gram, "N_GRAM", parse_env_usize_vec)?
+            .unwrap_or_else(|| DEFAULT_N_GRAM.to_vec());
+        n_gram.sort();
+        if let Some(n) = n_gram.iter().find(|n| **n == 0 || **n > MAX_N_GRAM) {
+            return Err(format!("invalid n_gram size: {}, the n-gram sizes must be between 1 and {} bits", n, MAX_N_GRAM));
+        }
+        let mut chunk_bytes_size_to_keep_filter = config_or_env(
+            config_file.chunk_bytes_size_to_keep_filter, "CHUNK_BYTES_SIZE_TO_KEEP_FILTER", parse_env_usize_vec
+        )?.unwrap_or_else(|| DEFAULT_CHUNK_BYTES_SIZE_TO_KEEP_FILTER.to_vec());
+        chunk_bytes_size_to_keep_filter.sort();
+
+        Ok(RunConfig {
+            name: config_file.name,
+            files,
+            directories,
+            output: argv.output.clone().or(config_file.output),
+            // NOTE: several CLI pipelines are resolved as variants (see resolve_variants)
+            pipeline: cli_or_config(matches, "pipeline", argv.pipeline[0], config_file.pipeline),
+            graph_comment_embedding_type: cli_or_config(
+                matches, "graph_comment_embedding_type", argv.graph_comment_embedding_type, config_file.graph_comment_embedding_type
+            ),
+            graph_format: cli_or_config(matches, "graph_format", argv.graph_format, config_file.graph_format),
+            annotation: cli_or_config(matches, "annotation", argv.annotation, config_file.annotation),
+            key_annotation_source: cli_or_config(
+                matches, "key_annotation_source", argv.key_annotation_source, config_file.key_annotation_source
+            ),
+            key_patterns_file: argv.key_patterns_file.clone().or(config_file.key_patterns_file),
+            entropy_filter: cli_or_config(matches, "entropy_filter", argv.entropy_filter, config_file.entropy_filter),
+            chunk_byte_size_filter: cli_or_config(
+                matches, "chunk_byte_size_filter", argv.chunk_byte_size_filter, config_file.chunk_byte_size_filter
+            ),
+            no_value_node: cli_or_config(matches, "no_value_node", argv.no_value_node, config_file.no_value_node),
+            repair_key_pointer_nodes: cli_or_config(
+                matches, "repair_key_pointer_nodes", argv.repair_key_pointer_nodes, config_file.repair_key_pointer_nodes
+            ),
+            aggregate: cli_or_config(matches, "aggregate", argv.aggregate, config_file.aggregate),
+            aggregate_shard_nb_samples: config_file.aggregate_shard_nb_samples.unwrap_or(DEFAULT_AGGREGATE_SHARD_NB_SAMPLES),
+            output_format: cli_or_config(matches, "output_format", argv.output_format, config_file.output_format),
+
+            embedding_depth: config_or_env(config_file.embedding_depth, "EMBEDDING_DEPTH", parse_env_value)?
+                .unwrap_or(DEFAULT_EMBEDDING_DEPTH),
+            n_gram,
+            chunk_bytes_size_to_keep_filter,
+            min_nb_of_chunks_to_keep: config_or_env(
+                config_file.min_nb_of_chunks_to_keep, "MIN_NB_OF_CHUNKS_TO_KEEP", parse_env_value
+            )?.unwrap_or(DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP),
+            chunk_nb_of_start_bytes_for_chunk_entropy: config_or_env(
+                config_file.chunk_nb_of_start_bytes_for_chunk_entropy, "CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY", parse_env_value
+            )?.unwrap_or(DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY),
+            chunk_nb_of_start_bytes_for_chunk_embedding: config_or_env(
+                config_file.chunk_nb_of_start_bytes_for_chunk_embedding, "CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING", parse_env_value
+            )?.unwrap_or(DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING),
+            wl_nb_of_hash_bins: config_file.wl_nb_of_hash_bins.unwrap_or(DEFAULT_WL_NB_OF_HASH_BINS),
+            betweenness_nb_of_sources: config_file.betweenness_nb_of_sources.unwrap_or(DEFAULT_BETWEENNESS_NB_OF_SOURCES),
+            byte_bigram_nb_of_buckets: config_file.byte_bigram_nb_of_buckets.unwrap_or(DEFAULT_BYTE_BIGRAM_NB_OF_BUCKETS),
+            value_node_window_nb_of_blocks: config_file.value_node_window_nb_of_blocks.unwrap_or(DEFAULT_VALUE_NODE_WINDOW_NB_OF_BLOCKS),
+            chunk_layout_nb_of_blocks: config_file.chunk_layout_nb_of_blocks.unwrap_or(DEFAULT_CHUNK_LAYOUT_NB_OF_BLOCKS),
+            random_walk: config_file.random_walk.unwrap_or_default(),
+            entropy_profile: config_file.entropy_profile.unwrap_or_default(),
+
+            resume: argv.resume,
+            force: argv.force,
+        })
+    }
+
+    /// Resolve the run configuration of each variant of the config file,
+    /// or of each pipeline given on the CLI if there are several,
+    /// or the single run configuration if there is no variant.
+    /// NOTE: The variants without name are named after their index and pipeline (ex: "0_chunk-semantic-embedding").
+    pub fn resolve_variants(argv: &Argv, matches: &ArgMatches, config_file: Option<RunConfigFile>) -> Result<Vec<RunConfig>, String> {
+        let mut config_file = config_file.unwrap_or_default();
+        let variants = std::mem::take(&mut config_file.variants);
+
+        let mut run_configs: Vec<RunConfig> = Vec::new();
+        if argv.pipeline.len() > 1 {
+            if !variants.is_empty() {
+                return Err("several pipelines on the CLI can't be combined with the variants of the config file".to_string());
+            }
+            for (i, pipeline) in argv.pipeline.iter().enumerate() {
+                let mut run_config = RunConfig::resolve(argv, matches, Some(config_file.clone()))?;
+                run_config.pipeline = *pipeline;
+                run_config.name = Some(variant_name(i, *pipeline));
+                run_configs.push(run_config);
+            }
+            return Ok(run_configs);
+        }
+        if variants.is_empty() {
+            return Ok(vec![RunConfig::resolve(argv, matches, Some(config_file))?]);
+        }
+
+        for (i, variant) in variants.into_iter().enumerate() {
+            let mut run_config = RunConfig::resolve(argv, matches, Some(variant.variant_with_base(&config_file)?))?;
+            if run_config.name.is_none() {
+                run_config.name = Some(variant_name(i, run_config.pipeline));
+            }
+            if run_configs.iter().any(|other| other.name == run_config.name) {
+                return Err(format!("duplicated variant name: {}", run_config.name.unwrap()));
+            }
+            run_configs.push(run_config);
+        }
+        Ok(run_configs)
+    }
+
+    /// output folder of the variant, in the given output folder
+    pub fn variant_output_folder(&self, output_folder: &Path) -> PathBuf {
+        match &self.name {
+            Some(name) => output_folder.join(name),
+            None => output_folder.to_path_buf(),
+        }
+    }
+
+    /// builder of the graph embedding, with the parameters of the run
+    pub fn graph_embedding_builder(&self) -> GraphEmbeddingBuilder {
+        GraphEmbeddingBuilder::new()
+            .depth(self.embedding_depth)
+            .annotation(self.annotation)
+            .key_annotation_source(self.key_annotation_source)
+            .key_patterns_file(self.key_patterns_file.clone())
+            .repair_key_pointer_nodes(self.repair_key_pointer_nodes)
+            .entropy_filter(self.entropy_filter)
+            .min_nb_of_chunks_to_keep(self.min_nb_of_chunks_to_keep)
+            .chunk_byte_size_filter(self.chunk_byte_size_filter)
+            .chunk_bytes_size_to_keep_filter(self.chunk_bytes_size_to_keep_filter.iter().cloned().collect())
+            .n_gram(self.n_gram.clone())
+            .chunk_nb_of_start_bytes_for_chunk_entropy(self.chunk_nb_of_start_bytes_for_chunk_entropy)
+            .chunk_nb_of_start_bytes_for_chunk_embedding(self.chunk_nb_of_start_bytes_for_chunk_embedding)
+            .wl_nb_of_hash_bins(self.wl_nb_of_hash_bins)
+            .betweenness_nb_of_sources(self.betweenness_nb_of_sources)
+            .byte_bigram_nb_of_buckets(self.byte_bigram_nb_of_buckets)
+            .value_node_window_nb_of_blocks(self.value_node_window_nb_of_blocks)
+            .chunk_layout_nb_of_blocks(self.chunk_layout_nb_of_blocks)
+            .random_walk_params(self.random_walk)
+            .entropy_profile_params(self.entropy_profile)
+            .graph_comment_embedding_type(self.graph_comment_embedding_type)
+            .graph_format(self.graph_format)
+            .without_value_node(self.no_value_node)
+    }
+
+    /// parameters of the run, that determine its outputs (without the inputs and the output folder)
+    pub fn parameters(&self) -> serde_json::Value {
+        let mut parameters = serde_json::to_value(self).unwrap();
+        let parameters_map = parameters.as_object_mut().unwrap();
+        parameters_map.remove("files");
+        parameters_map.remove("directories");
+        parameters_map.remove("output");
+        parameters
+    }
+
+    /// save the resolved configuration as a TOML file
+    pub fn save(&self, config_path: PathBuf) {
+        std::fs::write(&config_path, toml::to_string_pretty(self).unwrap()).unwrap_or_else(
+            |_| panic!("Cannot create config file: {:?}", config_path)
+        );
+    }
+}
+
+/// Parse the CLI arguments, load the optional config file, and resolve the run configuration of each variant.
+pub fn get_run_configs() -> Vec<RunConfig> {
+    let matches = Argv::command().get_matches();
+    let argv = Argv::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    let config_file = argv.config.as_ref().map(|config_file_path| {
+        RunConfigFile::load(config_file_path).unwrap_or_else(|err| panic!("🚩 {}", err))
+    });
+    RunConfig::resolve_variants(&argv, &matches, config_file).unwrap_or_else(
+        |err| panic!("🚩 Invalid configuration: {}", err)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// parse the given CLI arguments (without the program name)
+    fn parse_args(args: &[&str]) -> (Argv, ArgMatches) {
+        let matches = Argv::command().try_get_matches_from(
+            std::iter::once("mem_to_graph").chain(args.iter().cloned())
+        ).unwrap();
+        let argv = Argv::from_arg_matches(&matches).unwrap();
+        (argv, matches)
+    }
+
+    #[test]
+    fn test_run_config_precedence() {
+        crate::tests::setup();
+
+        let config_file: RunConfigFile = toml::from_str(r#"
+            pipeline = "chunk-semantic-embedding"
+            annotation = "chunk-header-node"
+            entropy_filter = "only-max-entropy"
+            no_value_node = true
+            embedding_depth = 3
+            n_gram = [3, 1]
+
+            [entropy_profile]
+            profile_length = 8
+        "#).unwrap();
+
+        let (argv, matches) = parse_args(&["-a", "none", "-s", "activate"]);
+        let run_config = RunConfig::resolve(&argv, &matches, Some(config_file)).unwrap();
+
+        // CLI > config file
+        assert_eq!(run_config.annotation, SelectAnnotationLocation::None);
+        // config file > defaults
+        assert_eq!(run_config.pipeline, Pipeline::ChunkSemanticEmbedding);
+        assert_eq!(run_config.entropy_filter, EntropyFilter::OnlyMaxEntropy);
+        assert!(run_config.no_value_node);
+        assert_eq!(run_config.embedding_depth, 3);
+        assert_eq!(run_config.n_gram, vec![1, 3]);
+        // the missing fields of a table take their default
+        assert_eq!(run_config.entropy_profile.profile_length, 8);
+        assert_eq!(run_config.entropy_profile.window_nb_of_blocks, crate::params::DEFAULT_ENTROPY_PROFILE_WINDOW_NB_OF_BLOCKS);
+        // CLI > defaults
+        assert_eq!(run_config.chunk_byte_size_filter, ChunkByteSizeFilter::Activate);
+        // the parameters don't depend on the inputs and the output folder
+        let mut other_run_config = run_config.clone();
+        other_run_config.files = Some(vec!["other-heap.raw".to_string()]);
+        other_run_config.output = Some("other".to_string());
+        assert_eq!(run_config.parameters(), other_run_config.parameters());
+        other_run_config.embedding_depth += 1;
+        assert_ne!(run_config.parameters(), other_run_config.parameters());
+        // defaults
+        assert_eq!(run_config.key_annotation_source, KeyAnnotationSource::JsonAddress);
+        assert_eq!(run_config.key_patterns_file, None);
+        assert!(!run_config.repair_key_pointer_nodes);
+        assert!(!run_config.aggregate);
+        assert_eq!(run_config.aggregate_shard_nb_samples, DEFAULT_AGGREGATE_SHARD_NB_SAMPLES);
+        assert_eq!(run_config.wl_nb_of_hash_bins, DEFAULT_WL_NB_OF_HASH_BINS);
+        assert_eq!(run_config.betweenness_nb_of_sources, DEFAULT_BETWEENNESS_NB_OF_SOURCES);
+        assert_eq!(run_config.byte_bigram_nb_of_buckets, DEFAULT_BYTE_BIGRAM_NB_OF_BUCKETS);
+        assert_eq!(run_config.value_node_window_nb_of_blocks, DEFAULT_VALUE_NODE_WINDOW_NB_OF_BLOCKS);
+        assert_eq!(run_config.chunk_layout_nb_of_blocks, DEFAULT_CHUNK_LAYOUT_NB_OF_BLOCKS);
+        assert_eq!(run_config.output_format, OutputFormat::Csv);
+        assert_eq!(run_config.graph_format, GraphFormat::Dot);
+    }
+
+    #[test]
+    fn test_run_config_variants() {
+        crate::tests::setup();
+
+        let config_file: RunConfigFile = toml::from_str(r#"
+            annotation = "chunk-header-node"
+            entropy_filter = "only-max-entropy"
+
+            [[variants]]
+            pipeline = "chunk-semantic-embedding"
+
+            [[variants]]
+            name = "statistic"
+            pipeline = "chunk-statistic-embedding"
+            entropy_filter = "none"
+        "#).unwrap();
+
+        let (argv, matches) = parse_args(&["-s", "activate"]);
+        let run_configs = RunConfig::resolve_variants(&argv, &matches, Some(config_file)).unwrap();
+        assert_eq!(run_configs.len(), 2);
+
+        assert_eq!(run_configs[0].name, Some("0_chunk-semantic-embedding".to_string()));
+        assert_eq!(run_configs[0].pipeline, Pipeline::ChunkSemanticEmbedding);
+        assert_eq!(run_configs[0].entropy_filter, EntropyFilter::OnlyMaxEntropy);
+
+        // variant > config file
+        assert_eq!(run_configs[1].name, Some("statistic".to_string()));
+        assert_eq!(run_configs[1].pipeline, Pipeline::ChunkStatisticEmbedding);
+        assert_eq!(run_configs[1].entropy_filter, EntropyFilter::None);
+
+        for run_config in run_configs.iter() {
+            // config file > defaults
+            assert_eq!(run_config.annotation, SelectAnnotationLocation::ChunkHeaderNode);
+            // CLI > config file
+            assert_eq!(run_config.chunk_byte_size_filter, ChunkByteSizeFilter::Activate);
+        }
+        assert_eq!(
+            run_configs[1].variant_output_folder(Path::new("out")),
+            PathBuf::from("out/statistic")
+        );
+
+        // no variant: single unnamed run configuration
+        let run_configs = RunConfig::resolve_variants(&argv, &matches, None).unwrap();
+        assert_eq!(run_configs.len(), 1);
+        assert_eq!(run_configs[0].variant_output_folder(Path::new("out")), PathBuf::from("out"));
+
+        // invalid variants
+        let config_file: RunConfigFile = toml::from_str(r#"
+            [[variants]]
+            output = "other"
+        "#).unwrap();
+        assert!(RunConfig::resolve_variants(&argv, &matches, Some(config_file)).is_err());
+        let config_file: RunConfigFile = toml::from_str(r#"
+            [[variants]]
+            name = "same"
+            [[variants]]
+            name = "same"
+        "#).unwrap();
+        assert!(RunConfig::resolve_variants(&argv, &matches, Some(config_file)).is_err());
+    }
+
+    #[test]
+    fn test_run_config_cli_pipelines() {
+        crate::tests::setup();
+
+        // repeated or comma separated pipelines: one variant per pipeline
+        let (argv, matches) = parse_args(&[
+            "-p", "chunk-semantic-embedding,chunk-statistic-embedding", "-p", "chunk-semantic-embedding", "-a", "chunk-header-node"
+        ]);
+        let config_file: RunConfigFile = toml::from_str("entropy_filter = \"only-max-entropy\"").unwrap();
+        let run_configs = RunConfig::resolve_variants(&argv, &matches, Some(config_file)).unwrap();
+        let pipelines: Vec<Pipeline> = run_configs.iter().map(|run_config| run_config.pipeline).collect();
+        assert_eq!(pipelines, vec![
+            Pipeline::ChunkSemanticEmbedding, Pipeline::ChunkStatisticEmbedding, Pipeline::ChunkSemanticEmbedding
+        ]);
+        assert_eq!(run_configs[1].name, Some("1_chunk-statistic-embedding".to_string()));
+        assert_eq!(run_configs[2].name, Some("2_chunk-semantic-embedding".to_string()));
+        for run_config in run_configs.iter() {
+            assert_eq!(run_config.annotation, SelectAnnotationLocation::ChunkHeaderNode);
+            assert_eq!(run_config.entropy_filter, EntropyFilter::OnlyMaxEntropy);
+        }
+
+        // a single pipeline: no variant
+        let (argv, matches) = parse_args(&["-p", "chunk-statistic-embedding"]);
+        let run_configs = RunConfig::resolve_variants(&argv, &matches, None).unwrap();
+        assert_eq!(run_configs.len(), 1);
+        assert_eq!(run_configs[0].name, None);
+        assert_eq!(run_configs[0].pipeline, Pipeline::ChunkStatisticEmbedding);
+
+        // several pipelines and config file variants
+        let (argv, matches) = parse_args(&["-p", "chunk-semantic-embedding,chunk-statistic-embedding"]);
+        let config_file: RunConfigFile = toml::from_str(r#"
+            [[variants]]
+            pipeline = "value-node-embedding"
+        "#).unwrap();
+        assert!(RunConfig::resolve_variants(&argv, &matches, Some(config_file)).is_err());
+    }
+
+    #[test]
+    fn test_config_or_env() {
+        // NOTE: a variable of this test only, the tests run in parallel
+        let name = "MEM_TO_GRAPH_TEST_CONFIG_OR_ENV";
+        std::env::set_var(name, "not a number");
+
+        // the config file value overrides a malformed environment variable
+        assert_eq!(config_or_env(Some(3usize), name, parse_env_value), Ok(Some(3)));
+        // a malformed environment variable is reported, not ignored
+        let err = config_or_env::<usize>(None, name, parse_env_value).unwrap_err();
+        assert!(err.contains(name));
+        assert!(config_or_env(None, name, parse_env_usize_vec).is_err());
+        std::env::set_var(name, "1,x,3");
+        assert!(config_or_env(None, name, parse_env_usize_vec).is_err());
+
+        std::env::set_var(name, " 1,2, 16");
+        assert_eq!(config_or_env(None, name, parse_env_usize_vec), Ok(Some(vec![1, 2, 16])));
+        std::env::remove_var(name);
+        assert_eq!(config_or_env::<usize>(None, name, parse_env_value), Ok(None));
+    }
+
+    #[test]
+    fn test_run_config_n_gram_size() {
+        crate::tests::setup();
+
+        let (argv, matches) = parse_args(&[]);
+        for (n_gram, is_valid) in [("[1, 16]", true), ("[24]", false), ("[0, 2]", false)] {
+            let config_file: RunConfigFile = toml::from_str(&format!("n_gram = {}", n_gram)).unwrap();
+            let run_config = RunConfig::resolve(&argv, &matches, Some(config_file));
+            assert_eq!(run_config.is_ok(), is_valid, "n_gram = {}", n_gram);
+        }
+    }
+
+    #[test]
+    fn test_run_config_file_unknown_field() {
+        let config_file: Result<RunConfigFile, _> = toml::from_str("annotations = \"none\"");
+        assert!(config_file.is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// NOTE: useless_conversion is raised by the expansion of the pyo3 macros
+#![allow(clippy::useless_conversion)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use numpy::ndarray::Array2;
+use numpy::{Element, IntoPyArray, PyArray1, PyArray2};
+use pyo3::exceptions::{PyFileNotFoundError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::graph_embedding::{GraphEmbedding, builder::GraphEmbeddingBuilder};
+use crate::graph_embedding::samples::{SampleValue, Samples};
+use crate::graph_embedding::random_walk::RandomWalkParams;
+use crate::graph_structs::EdgeType;
+use crate::utils::MAX_N_GRAM;
+use crate::params::argv::{SelectAnnotationLocation, EntropyFilter, ChunkByteSizeFilter, KeyAnnotationSource};
+use crate::params::{
+    BLOCK_BYTE_SIZE,
+    DEFAULT_EMBEDDING_DEPTH,
+    DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP,
+    DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+    DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING,
+    DEFAULT_WL_NB_OF_HASH_BINS,
+    DEFAULT_RANDOM_WALK_P,
+    DEFAULT_RANDOM_WALK_Q,
+    DEFAULT_RANDOM_WALK_LENGTH,
+    DEFAULT_RANDOM_WALK_NB_WALKS_PER_NODE,
+    DEFAULT_SKIP_GRAM_WINDOW_SIZE,
+    DEFAULT_SKIP_GRAM_DIMENSION,
+    DEFAULT_SKIP_GRAM_NB_NEGATIVE_SAMPLES,
+    DEFAULT_SKIP_GRAM_NB_EPOCHS,
+    DEFAULT_SKIP_GRAM_LEARNING_RATE,
+    DEFAULT_RANDOM_WALK_SEED,
+};
+
+type NumpySamplesAndLabels<'py, T> = (Bound<'py, PyArray2<T>>, Bound<'py, PyArray1<usize>>, Vec<String>);
+
+/// parse a CLI value (ex: "chunk-header-node") into its enum
+fn parse_value_enum<T: ValueEnum>(value: &str) -> PyResult<T> {
+    T::from_str(value, true).map_err(PyValueError::new_err)
+}
+
+/// convert the samples and labels to numpy arrays.
+/// Return (samples, labels, columns), the columns being the names of the features, in their declared order.
+/// NOTE: `to_value` converts a value of the samples to the type of the numpy array (None if not possible).
+fn samples_and_labels_to_numpy<'py, T: Element + Copy>(
+    py: Python<'py>,
+    samples: Samples,
+    to_value: fn(&SampleValue) -> Option<T>,
+) -> PyResult<NumpySamplesAndLabels<'py, T>> {
+    let columns = samples.columns().names().to_vec();
+    let mut values = Vec::with_capacity(samples.len() * columns.len());
+    for (sample, _) in samples.iter() {
+        for value in sample.iter() {
+            values.push(to_value(value).ok_or_else(
+                || PyValueError::new_err(format!("Unsupported value for a numpy array: {:?}", value))
+            )?);
+        }
+    }
+    let numpy_samples = Array2::from_shape_vec((samples.len(), columns.len()), values)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    Ok((numpy_samples.into_pyarray_bound(py), samples.labels().to_vec().into_pyarray_bound(py), columns))
+}
+
+/// integer value of a sample
+fn to_usize(value: &SampleValue) -> Option<usize> {
+    match value {
+        SampleValue::Int(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Memory graph of a heap dump, and its embeddings, for Python.
+/// NOTE: The parameters are the ones of the CLI (same names, same values, same defaults).
+#[pyclass(name = "GraphEmbedding")]
+pub struct PyGraphEmbedding {
+    graph_embedding: GraphEmbedding,
+}
+
+#[pymethods]
+impl PyGraphEmbedding {
+    #[new]
+    #[pyo3(signature = (
+        heap_dump_raw_file_path,
+        annotation = "value-node",
+        key_annotation_source = "json-address",
+        key_patterns_file = None,
+        repair_key_pointer_nodes = false,
+        entropy_filter = "none",
+        min_nb_of_chunks_to_keep = DEFAULT_MIN_NB_OF_CHUNKS_TO_KEEP,
+        chunk_byte_size_filter = "none",
+        chunk_bytes_size_to_keep_filter = None,
+        no_value_node = false,
+        depth = DEFAULT_EMBEDDING_DEPTH,
+        n_gram = None,
+        chunk_nb_of_start_bytes_for_chunk_entropy = DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY,
+        chunk_nb_of_start_bytes_for_chunk_embedding = DEFAULT_CHUNK_NB_OF_START_BYTES_FOR_CHUNK_EMBEDDING,
+        wl_nb_of_hash_bins = DEFAULT_WL_NB_OF_HASH_BINS,
+        random_walk_p = DEFAULT_RANDOM_WALK_P,
+        random_walk_q = DEFAULT_RANDOM_WALK_Q,
+        random_walk_length = DEFAULT_RANDOM_WALK_LENGTH,
+        random_walk_nb_walks_per_node = DEFAULT_RANDOM_WALK_NB_WALKS_PER_NODE,
+        random_walk_window_size = DEFAULT_SKIP_GRAM_WINDOW_SIZE,
+        random_walk_dimension = DEFAULT_SKIP_GRAM_DIMENSION,
+        random_walk_nb_negative_samples = DEFAULT_SKIP_GRAM_NB_NEGATIVE_SAMPLES,
+        random_walk_nb_epochs = DEFAULT_SKIP_GRAM_NB_EPOCHS,
+        random_walk_learning_rate = DEFAULT_SKIP_GRAM_LEARNING_RATE,
+        random_walk_seed = DEFAULT_RANDOM_WALK_SEED,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        heap_dump_raw_file_path: PathBuf,
+        annotation: &str,
+        key_annotation_source: &str,
+        key_patterns_file: Option<PathBuf>,
+        repair_key_pointer_nodes: bool,
+        entropy_filter: &str,
+        min_nb_of_chunks_to_keep: usize,
+        chunk_byte_size_filter: &str,
+        chunk_bytes_size_to_keep_filter: Option<Vec<usize>>,
+        no_value_node: bool,
+        depth: usize,
+        n_gram: Option<Vec<usize>>,
+        chunk_nb_of_start_bytes_for_chunk_entropy: usize,
+        chunk_nb_of_start_bytes_for_chunk_embedding: usize,
+        wl_nb_of_hash_bins: usize,
+        random_walk_p: f64,
+        random_walk_q: f64,
+        random_walk_length: usize,
+        random_walk_nb_walks_per_node: usize,
+        random_walk_window_size: usize,
+        random_walk_dimension: usize,
+        random_walk_nb_negative_samples: usize,
+        random_walk_nb_epochs: usize,
+        random_walk_learning_rate: f64,
+        random_walk_seed: u64,
+    ) -> PyResult<Self> {
+        if !heap_dump_raw_file_path.exists() {
+            return Err(PyFileNotFoundError::new_err(format!("{:?}", heap_dump_raw_file_path)));
+        }
+        if wl_nb_of_hash_bins == 0 {
+            return Err(PyValueError::new_err("wl_nb_of_hash_bins must be positive"));
+        }
+        if random_walk_p <= 0.0 || random_walk_q <= 0.0 {
+            return Err(PyValueError::new_err("random_walk_p and random_walk_q must be positive"));
+        }
+        if n_gram.iter().flatten().any(|n| *n == 0 || *n > MAX_N_GRAM) {
+            return Err(PyValueError::new_err(format!("the n_gram sizes must be between 1 and {} bits", MAX_N_GRAM)));
+        }
+        let mut graph_embedding_builder = GraphEmbeddingBuilder::new()
+            .annotation(parse_value_enum::<SelectAnnotationLocation>(annotation)?)
+            .key_annotation_source(parse_value_enum::<KeyAnnotationSource>(key_annotation_source)?)
+            .key_patterns_file(key_patterns_file)
+            .repair_key_pointer_nodes(repair_key_pointer_nodes)
+            .entropy_filter(parse_value_enum::<EntropyFilter>(entropy_filter)?)
+            .min_nb_of_chunks_to_keep(min_nb_of_chunks_to_keep)
+            .chunk_byte_size_filter(parse_value_enum::<ChunkByteSizeFilter>(chunk_byte_size_filter)?)
+            .without_value_node(no_value_node)
+            .depth(depth)
+            .chunk_nb_of_start_bytes_for_chunk_entropy(chunk_nb_of_start_bytes_for_chunk_entropy)
+            .chunk_nb_of_start_bytes_for_chunk_embedding(chunk_nb_of_start_bytes_for_chunk_embedding)
+            .wl_nb_of_hash_bins(wl_nb_of_hash_bins)
+            .random_walk_params(RandomWalkParams {
+                p: random_walk_p,
+                q: random_walk_q,
+                walk_length: random_walk_length,
+                nb_walks_per_node: random_walk_nb_walks_per_node,
+                window_size: random_walk_window_size,
+                dimension: random_walk_dimension,
+                nb_negative_samples: random_walk_nb_negative_samples,
+                nb_epochs: random_walk_nb_epochs,
+                learning_rate: random_walk_learning_rate,
+                seed: random_walk_seed,
+            });
+        if let Some(chunk_bytes_size_to_keep_filter) = chunk_bytes_size_to_keep_filter {
+            graph_embedding_builder = graph_embedding_builder
+                .chunk_bytes_size_to_keep_filter(chunk_bytes_size_to_keep_filter.into_iter().collect());
+        }
+        if let Some(n_gram) = n_gram {
+            graph_embedding_builder = graph_embedding_builder.n_gram(n_gram);
+        }
+
+        let graph_embedding = graph_embedding_builder.build(heap_dump_raw_file_path)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(PyGraphEmbedding { graph_embedding })
+    }
+
+    /// :return: (samples, labels, columns)
+    fn value_node_semantic_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_value_node_semantic_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (semantic embedding, bytes) are converted to float.
+    fn value_node_window_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_value_node_window_embedding();
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    fn chunk_semantic_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_chunk_semantic_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// :return: (samples, labels, columns)
+    fn chunk_wl_subtree_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_chunk_wl_subtree_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (chunk informations) are converted to float.
+    fn chunk_random_walk_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_chunk_random_walk_embedding();
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (chunk informations, SCC, ssh struct distance) are converted to float.
+    fn chunk_centrality_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_chunk_centrality_embedding();
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (n-gram, chunk informations) are converted to float.
+    fn chunk_statistic_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_chunk_statistic_embedding(
+            self.graph_embedding.get_n_gram(), BLOCK_BYTE_SIZE
+        );
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    fn chunk_top_vn_semantic_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_chunk_top_vn_semantic_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (chunk informations, byte and bigram counts) are converted to float.
+    fn chunk_byte_histogram_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_chunk_byte_histogram_embedding();
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    /// NOTE: The integer features (chunk informations, positions) are converted to float.
+    fn chunk_entropy_profile_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, f64>> {
+        let samples = self.graph_embedding.generate_chunk_entropy_profile_embedding();
+        samples_and_labels_to_numpy(py, samples, SampleValue::as_f64)
+    }
+
+    /// :return: (samples, labels, columns)
+    fn chunk_layout_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_chunk_layout_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// :return: (samples, labels, columns)
+    fn chunk_start_bytes_embedding<'py>(&self, py: Python<'py>) -> PyResult<NumpySamplesAndLabels<'py, usize>> {
+        let samples = self.graph_embedding.generate_chunk_start_bytes_embedding();
+        samples_and_labels_to_numpy(py, samples, to_usize)
+    }
+
+    /// The memory graph as arrays, as a dict:
+    ///     - node_addrs: addresses of the nodes (ascending order)
+    ///     - node_labels: label of each node (0 if not annotated)
+    ///     - edge_index: shape (2, nb_edges), indexes in node_addrs of the source and target of each edge
+    ///     - edge_types: 0 for a chunk edge, 1 for a pointer edge
+    ///     - edge_weights: number of pointers between the two nodes (1 for a chunk edge)
+    fn graph<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let graph_data = &self.graph_embedding.graph_annotate.graph_data;
+
+        let mut node_addrs: Vec<u64> = graph_data.graph.nodes().collect();
+        node_addrs.sort();
+        let node_addr_to_index: HashMap<u64, usize> = node_addrs.iter()
+            .enumerate()
+            .map(|(index, addr)| (*addr, index))
+            .collect();
+        let node_labels: Vec<usize> = node_addrs.iter()
+            .map(|addr| {
+                graph_data.node_addr_to_annotations.get(addr)
+                    .map_or(0, |annotation| annotation.annotation_set_embedding() as usize)
+            })
+            .collect();
+
+        let nb_edges = graph_data.graph.edge_count();
+        let mut edge_index = Array2::<usize>::zeros((2, nb_edges));
+        let mut edge_types = Vec::with_capacity(nb_edges);
+        let mut edge_weights = Vec::with_capacity(nb_edges);
+        for (i, (from, to, edge)) in graph_data.graph.all_edges().enumerate() {
+            edge_index[[0, i]] = node_addr_to_index[&from];
+            edge_index[[1, i]] = node_addr_to_index[&to];
+            edge_types.push(match edge.edge_type {
+                EdgeType::ChunkEdge => 0u8,
+                EdgeType::PointerEdge => 1u8,
+            });
+            edge_weights.push(edge.weight);
+        }
+
+        let graph = PyDict::new_bound(py);
+        graph.set_item("node_addrs", node_addrs.into_pyarray_bound(py))?;
+        graph.set_item("node_labels", node_labels.into_pyarray_bound(py))?;
+        graph.set_item("edge_index", edge_index.into_pyarray_bound(py))?;
+        graph.set_item("edge_types", edge_types.into_pyarray_bound(py))?;
+        graph.set_item("edge_weights", edge_weights.into_pyarray_bound(py))?;
+        Ok(graph)
+    }
+}
+
+/// Python extension module
+#[pymodule]
+fn mem_to_graph(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGraphEmbedding>()?;
+    Ok(())
+}
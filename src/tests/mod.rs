@@ -1,5 +1,6 @@
 #[cfg(test)]
 use std::sync::Once;
+use std::path::PathBuf;
 use lazy_static::lazy_static;
 
 use crate::{utils::{hex_str_to_addr, Endianness, hex_str_to_block_bytes}, params::BLOCK_BYTE_SIZE};
@@ -27,7 +28,6 @@ fn log_order_warning() {
 #[cfg(test)]
 pub fn setup() {
         // initialization code here
-        crate::params::init(); // Call the init() function to load the .env file
         log_order_warning();
 }
 
@@ -43,6 +43,12 @@ lazy_static! {
     // NOTE: pointer representation is in little endian, and ends with 00 00
     
     // WARN: HEAP_START is in big endian!!!
+
+    // NOTE: the test files are relative to the crate, not to the current directory
+    pub static ref TEST_HEAP_DUMP_FILE_PATH: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/17016-1643962152-heap.raw");
+    pub static ref TEST_HEAP_JSON_FILE_PATH: PathBuf = crate::utils::heap_dump_path_to_json_path(&TEST_HEAP_DUMP_FILE_PATH);
+    // WARN: this file is created by the tests
+    pub static ref TEST_CSV_EMBEDDING_FILE_PATH: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/test_17016-1643962152_embedding.csv");
     
     pub static ref TEST_HEAP_START_ADDR: u64 = hex_str_to_addr("558343d1a000", Endianness::Big).unwrap(); // HEAP_START
     static ref TEST_HEAP_SIZE: u64 = 135168; // heap size obtained with command: stat -c %s test/17016-1643962152-heap.raw
@@ -1,7 +1,7 @@
 use crate::tests::*;
 use crate::utils::*;
-use crate::params::TEST_HEAP_DUMP_FILE_PATH;
 
+use std::collections::HashMap;
 use serde_json::json;
 
 #[test]
@@ -129,6 +129,18 @@ fn test_is_pointer() {
     }
 }
 
+#[test]
+fn test_convert_pointer_to_block() {
+    crate::tests::setup();
+    let min_addr: u64 = *TEST_HEAP_START_ADDR; // HEAP_START
+    let max_addr: u64 = *TEST_HEAP_END_ADDR; // HEAP_START + HEAP_SIZE
+
+    for block in [*TEST_PTR_1_VALUE_BYTES, *TEST_PTR_2_VALUE_BYTES] {
+        let pointer = convert_block_to_pointer_if_possible(&block, min_addr, max_addr).unwrap();
+        assert_eq!(convert_pointer_to_block(pointer), block);
+    }
+}
+
 #[test]
 fn test_create_node_from_bytes() {
     crate::tests::setup();
@@ -236,6 +248,46 @@ fn test_generate_bit_combinations_3() {
     assert_eq!(result, vec!["000", "001", "010", "011", "100", "101", "110", "111"]);
 }
 
+#[test]
+fn test_get_n_gram_columns() {
+    let columns = get_n_gram_columns(&[2, 1, 2]);
+    assert_eq!(columns, vec!["0", "1", "00", "01", "10", "11"]);
+
+    // the big n-grams are named in hexadecimal
+    let columns = get_n_gram_columns(&[N_GRAM_MAX_BINARY_COLUMN_NAME_SIZE + 2]);
+    assert_eq!(columns.len(), 1 << (N_GRAM_MAX_BINARY_COLUMN_NAME_SIZE + 2));
+    assert_eq!(columns[0], "10gram_000");
+    assert_eq!(columns[0x2a3], "10gram_2a3");
+}
+
+#[test]
+fn test_count_bit_n_grams() {
+    let bytes: Vec<u8> = vec![0x00, 0xff, 0xa5, 0x3c, 0x01, 0x80, 0x7e, 0xc3, 0x12];
+    let n_gram = vec![1, 2, 3, 7, 12];
+
+    // reference: count the windows of the string of bits
+    let bits: String = bytes.iter().map(|byte| to_n_bits_binary(*byte as u64, 8)).collect();
+    let mut bin_to_count: HashMap<String, usize> = HashMap::new();
+    for n in n_gram.iter() {
+        for start in 0..=(bits.len() - n) {
+            *bin_to_count.entry(bits[start..start + n].to_string()).or_insert(0) += 1;
+        }
+    }
+    let expected: Vec<usize> = n_gram.iter()
+        .flat_map(|n| generate_bit_combinations(*n))
+        .map(|bin| *bin_to_count.get(&bin).unwrap_or(&0))
+        .collect();
+
+    let counts = count_bit_n_grams(&bytes, &n_gram);
+    assert_eq!(counts.len(), get_n_gram_columns(&n_gram).len());
+    assert_eq!(counts, expected);
+
+    // no n-gram bigger than the data
+    let counts = count_bit_n_grams(&bytes[..1], &[4, 12]);
+    assert_eq!(counts.iter().sum::<usize>(), 5);
+    assert_eq!(counts[0], 5);
+}
+
 #[test]
 fn test_to_n_bits_binary_1() {
     let result = to_n_bits_binary(1, 1);
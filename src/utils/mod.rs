@@ -1,12 +1,10 @@
-use lazy_static::lazy_static;
-
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::path::PathBuf;
 use error_chain::error_chain;
 use serde_json::Value;
 
-use crate::params::{PTR_ENDIANNESS, get_n_gram_from_env, BLOCK_BYTE_SIZE, CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY};
+use crate::params::{PTR_ENDIANNESS, BLOCK_BYTE_SIZE};
 use crate::graph_structs::{Node, PointerNode, ValueNode};
 
 /// convert an address to an index
@@ -73,6 +71,26 @@ error_chain! {
             description("Json file not found")
             display("Json file not found: {:?}", json_file_path)
         }
+        InvalidConfigFile(config_file_path: PathBuf, message: String) {
+            description("Invalid config file")
+            display("Invalid config file: {:?}, {}", config_file_path, message)
+        }
+        InvalidSavedGraph(saved_graph_file_path: PathBuf, message: String) {
+            description("Invalid saved graph")
+            display("Invalid saved graph: {:?}, {}", saved_graph_file_path, message)
+        }
+        InvalidDotFile(dot_file_path: PathBuf, message: String) {
+            description("Invalid dot file")
+            display("Invalid dot file: {:?}, {}", dot_file_path, message)
+        }
+        InvalidKeyPattern(key_name: String, message: String) {
+            description("Invalid key pattern")
+            display("Invalid key pattern: {}, {}", key_name, message)
+        }
+        NpzArchiveTooLarge(npz_file_path: PathBuf) {
+            description("NPZ archive too large")
+            display("NPZ archive too large (more than 4 GiB, no ZIP64): {:?}", npz_file_path)
+        }
     }
 }
 
@@ -127,7 +145,7 @@ pub enum Endianness {
 /// NOTE: remember that our heap dump vectors are in the format given as a program argument
 pub fn convert_block_to_pointer_if_possible(data: &[u8], min_addr: u64, max_addr: u64) -> Option<u64> {
     // WARN: THIS IS THE ONLY PLACE WHERE THE POINTER ENDIANNESS IS USED
-    
+    // (with its reverse conversion, see convert_pointer_to_block)
 
     let potential_ptr_int = match PTR_ENDIANNESS {
         Endianness::Big => u64::from_be_bytes(data.try_into().unwrap()),
@@ -142,6 +160,15 @@ pub fn convert_block_to_pointer_if_possible(data: &[u8], min_addr: u64, max_addr
     }
 }
 
+/// convert a pointer back to the block of bytes it has been parsed from
+/// NOTE: reverse of convert_block_to_pointer_if_possible
+pub fn convert_pointer_to_block(pointer: u64) -> [u8; crate::params::BLOCK_BYTE_SIZE] {
+    match PTR_ENDIANNESS {
+        Endianness::Big => pointer.to_be_bytes(),
+        Endianness::Little => pointer.to_le_bytes(),
+    }
+}
+
 /// create a node from a block of bytes, following the specified endianness
 /// NOTE: no need to provide endianess for the pointer conversion directly, 
 /// it uses the global pointer endianness
@@ -223,8 +250,9 @@ pub fn div_round_up(numerator: usize, denominator: usize) -> usize {
 }
 
 /// compute a vector of usize from a string with comma separated values
-pub fn string_to_usize_vec(string: &str) -> Vec<usize> {
-    string.split(',').filter_map(|s| s.parse::<usize>().ok()).collect()
+/// NOTE: Fails on the first value that is not a usize, instead of dropping it.
+pub fn string_to_usize_vec(string: &str) -> std::result::Result<Vec<usize>, std::num::ParseIntError> {
+    string.split(',').map(|s| s.trim().parse::<usize>()).collect()
 }
 
 /// get the string representation as hexa from a vector of bytes (u8)
@@ -236,30 +264,71 @@ pub fn bytes_to_hex_string(bytes: &Vec<u8>) -> String {
 
 // ------------------------------------ binaries utils ------------------------------------
 
+/// maximum size of a bit n-gram (2^n columns, and dense counters for each chunk)
+/// NOTE: 16 bits gives 65536 columns and 512 KiB of counters per chunk, 24 bits would give 16M columns.
+pub const MAX_N_GRAM: usize = 16;
+/// maximum size of a bit n-gram whose columns are named by their bits,
+/// the columns of the bigger ones are named "{n}gram_{hex value}"
+pub const N_GRAM_MAX_BINARY_COLUMN_NAME_SIZE: usize = 8;
+
+/// sorted and deduplicated n-gram sizes
+pub fn get_n_gram_sizes(n_gram: &[usize]) -> Vec<usize> {
+    let mut n_gram_sizes = n_gram.to_vec();
+    n_gram_sizes.sort();
+    n_gram_sizes.dedup();
+    n_gram_sizes
+}
 
-
-
-
-lazy_static! {
-    /// generate all possible bit combinations from n_gram, and associate them to 0.
-    /// NOTE : accelerate map generation with clone
-    static ref BIN_TO_NB_STARTING : HashMap<String, usize> = {
-        let mut bin_to_index = HashMap::new();
-        let n_gram: Vec<usize> = get_n_gram_from_env();
-        for n in n_gram {
-            let i_gram_names = generate_bit_combinations(n);
-            for name in i_gram_names {
-                bin_to_index.insert(name, 0);
+/// names of the n-gram columns: all possible bit combinations of each n,
+/// in number of bit (ascending order), then bitwise order
+/// NOTE: A repeated n is only used once.
+/// NOTE: Above N_GRAM_MAX_BINARY_COLUMN_NAME_SIZE bits, the combinations are written in hexadecimal, ex: "12gram_0a3".
+pub fn get_n_gram_columns(n_gram: &[usize]) -> Vec<String> {
+    get_n_gram_sizes(n_gram).into_iter()
+        .flat_map(|n| {
+            if n <= N_GRAM_MAX_BINARY_COLUMN_NAME_SIZE {
+                generate_bit_combinations(n)
+            } else {
+                (0..1usize << n)
+                    .map(|value| format!("{}gram_{:0width$x}", n, value, width = n.div_ceil(4)))
+                    .collect()
             }
-        }
-        bin_to_index
-    };
-    
+        })
+        .collect()
 }
 
-/// clone the map of all possible bit combinations in N_gram to their count
-pub fn get_bin_to_nb_starting() -> HashMap<String, usize> {
-    BIN_TO_NB_STARTING.clone()
+/// count all the bit n-grams of the bytes (most significant bit first), in the order of get_n_gram_columns
+/// NOTE: The bits slide in a u64 window, each n-gram is the index of its counter.
+pub fn count_bit_n_grams(bytes: &[u8], n_gram: &[usize]) -> Vec<usize> {
+    let n_gram_sizes = get_n_gram_sizes(n_gram);
+    let mut offsets = Vec::with_capacity(n_gram_sizes.len());
+    let mut nb_of_counters = 0;
+    for n in n_gram_sizes.iter() {
+        assert!(
+            *n > 0 && *n <= MAX_N_GRAM,
+            "🚩 The n-gram sizes must be between 1 and {} bits, got {}", MAX_N_GRAM, n
+        );
+        offsets.push(nb_of_counters);
+        nb_of_counters += 1 << n;
+    }
+    let mut counts = vec![0usize; nb_of_counters];
+
+    let mut window: u64 = 0;
+    let mut nb_of_bits: usize = 0;
+    for byte in bytes {
+        for bit_index in (0..8).rev() {
+            window = (window << 1) | ((byte >> bit_index) & 1) as u64;
+            nb_of_bits += 1;
+            for (n, offset) in n_gram_sizes.iter().zip(offsets.iter()) {
+                // the n-gram ending at this bit, once there are enough bits
+                if nb_of_bits < *n {
+                    break;
+                }
+                counts[offset + (window & ((1 << n) - 1)) as usize] += 1;
+            }
+        }
+    }
+    counts
 }
 
 /// generate all possible bit combinations of size n
@@ -373,10 +442,14 @@ pub fn shannon_entropy(data: &Vec<u8>) -> f64 {
     entropy
 }
 
-pub fn compute_chunk_start_bytes_entropy(all_heap_blocks: &Vec<[u8; BLOCK_BYTE_SIZE]>, chunk_data_first_block_index: usize) -> f64 {
+pub fn compute_chunk_start_bytes_entropy(
+    all_heap_blocks: &[[u8; BLOCK_BYTE_SIZE]], 
+    chunk_data_first_block_index: usize,
+    nb_start_bytes: usize,
+) -> f64 {
     let mut start_data_bytes: Vec<u8> = Vec::new();
-    let nb_first_blocks_inf: usize = (*CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY ) / BLOCK_BYTE_SIZE;
-    let nb_bytes_in_last_block = *CHUNK_NB_OF_START_BYTES_FOR_CHUNK_ENTROPY % BLOCK_BYTE_SIZE;
+    let nb_first_blocks_inf: usize = nb_start_bytes / BLOCK_BYTE_SIZE;
+    let nb_bytes_in_last_block = nb_start_bytes % BLOCK_BYTE_SIZE;
 
     // Make sure there are enough blocks
     if all_heap_blocks.len() < nb_first_blocks_inf {